@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::core::common::permission::Permissions;
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct AuthorizationResult {
     pub status: String,
@@ -19,5 +21,5 @@ pub struct SessionPayload {
 #[derive(Deserialize, Clone, Debug)]
 pub struct SessionResult {
     pub session_token: Option<String>,
-    //permissions: Option<Permissions>
+    pub permissions: Option<Permissions>,
 }
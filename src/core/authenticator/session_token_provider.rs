@@ -1,3 +1,5 @@
+use std::sync::{Arc, Mutex};
+
 use hmac::{Hmac, Mac};
 use log::{debug, error};
 use sha1::Sha1;
@@ -10,6 +12,7 @@ use crate::core::{
     },
     common::{
         http_client_factory::http_client_factory,
+        permission::Permissions,
         transport::{FreeboxResponse, FreeboxResponseError},
     },
 };
@@ -20,6 +23,7 @@ use super::{application_token_provider::ApplicationTokenProvider, common::Sessio
 pub struct SessionTokenProvider<'a> {
     app_token_provider: &'a dyn ApplicationTokenProvider,
     api_url: String,
+    permissions: Arc<Mutex<Option<Permissions>>>,
 }
 
 impl<'a> SessionTokenProvider<'a> {
@@ -27,6 +31,7 @@ impl<'a> SessionTokenProvider<'a> {
         Self {
             app_token_provider: app_token_storage,
             api_url,
+            permissions: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -40,6 +45,29 @@ impl<'a> SessionTokenProvider<'a> {
     }
 
     pub async fn login(&self) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let session_result = self.open_session().await?;
+
+        if session_result.permissions.is_some() {
+            *self.permissions.lock().unwrap() = session_result.permissions;
+        }
+
+        match session_result.session_token {
+            Some(t) => Ok(t),
+            None => Err(Box::new(AuthenticationError::new(
+                "cannot get session token".to_string(),
+            ))),
+        }
+    }
+
+    /// Permissions granted to the application, as given by the last opened
+    /// session, `None` until a session reported them
+    pub fn get_permissions(&self) -> Option<Permissions> {
+        self.permissions.lock().unwrap().to_owned()
+    }
+
+    async fn open_session(
+        &self,
+    ) -> Result<SessionResult, Box<dyn std::error::Error + Send + Sync>> {
         debug!("login in");
 
         let token = self.app_token_provider.get().await;
@@ -57,17 +85,7 @@ impl<'a> SessionTokenProvider<'a> {
             Ok(p) => p,
         };
 
-        let session_result = match self.get_session_token(password).await {
-            Err(e) => return Err(e),
-            Ok(s) => s,
-        };
-
-        match session_result.session_token {
-            Some(t) => Ok(t),
-            None => Err(Box::new(AuthenticationError::new(
-                "cannot get session token".to_string(),
-            ))),
-        }
+        self.get_session_token(password).await
     }
 
     async fn get_challenge(
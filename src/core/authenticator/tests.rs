@@ -50,6 +50,49 @@ mod tests {
         };
     }
 
+    #[tokio::test]
+    async fn permissions_test() {
+        let mock_server = MockServer::start().await;
+        let mut store_mock = MockApplicationTokenProvider::new();
+        store_mock
+            .expect_get()
+            .times(1)
+            .returning(|| Ok("foo.bar".to_string()));
+
+        let api_url = format!("{}/api/", mock_server.uri());
+
+        Mock::given(method("GET"))
+            .and(path("/api/v4/login/"))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(json!({
+                "result": { "challenge": "1234" }, "success": true,
+            })))
+            .mount(&mock_server)
+            .await;
+
+        // the permissions come from the session opened by the login
+        Mock::given(method("POST"))
+            .and(path("/api/v4/login/session"))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(json!({
+                "result": {
+                    "session_token": "4321",
+                    "permissions": { "settings": true, "parental": false }
+                },
+                "success": true,
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let authenticator =
+            authenticator::Authenticator::new(api_url.to_owned(), Box::new(store_mock));
+        let factory = authenticator.login().await.unwrap();
+        let permissions = factory.get_permissions().unwrap();
+
+        assert_eq!(Some(true), permissions.settings);
+        assert_eq!(Some(false), permissions.parental);
+        assert_eq!(None, permissions.downloader);
+    }
+
     #[tokio::test]
     async fn login_test() {
         let mock_server = MockServer::start().await;
@@ -83,7 +126,8 @@ mod tests {
             let res = authenticator.login().await;
 
             match res {
-                Ok(_) => {}
+                // the session did not report any permission
+                Ok(factory) => assert!(factory.get_permissions().is_none()),
                 Err(e) => {
                     println!("{e}:#?");
                     panic!();
@@ -1,17 +1,26 @@
-use crate::mappers::wifi::models::WifiConfig;
+use crate::mappers::{system::get_system_config, wifi::models::WifiConfig};
 
 use super::common::{
     http_client_factory::AuthenticatedHttpClientFactory,
+    permission::Permissions,
     transport::{FreeboxResponse, FreeboxResponseError},
 };
-use log::{debug, info};
+use log::{debug, error, info, warn};
 use serde::Deserialize;
 
+/// Boards with internal RAID support, Freebox Delta and Ultra
+const RAID_BOARDS: [&str; 2] = ["fbxgw7r", "fbxgw9r"];
+
 #[derive(Deserialize, Clone, Debug)]
 struct LanConfig {
     mode: Option<String>,
 }
 
+#[derive(Deserialize, Clone, Debug)]
+struct LteConfig {
+    enabled: Option<bool>,
+}
+
 pub struct CapabilitiesAgent<'a> {
     client_factory: &'a AuthenticatedHttpClientFactory<'a>,
 }
@@ -57,6 +66,22 @@ impl<'a> CapabilitiesAgent<'a> {
         );
 
         let is_wifi_enabled = self.is_wifi_enabled().await?;
+        // RAID metrics are optional, the other maps do not depend on the board
+        let is_raid_supported = self.is_raid_supported().await.unwrap_or_else(|e| {
+            error!(
+                "cannot detect RAID support, RAID metrics are disabled: {}",
+                e
+            );
+            false
+        });
+        let is_lte_supported = self.is_lte_supported().await?;
+
+        // unknown permissions leave the maps to report a missing one at runtime
+        let permissions = self.client_factory.get_permissions();
+
+        if permissions.is_none() {
+            warn!("the application permissions were not reported by the box");
+        }
 
         Ok(Capabilities {
             connection: Some(true),
@@ -66,7 +91,10 @@ impl<'a> CapabilitiesAgent<'a> {
             switch: Some(is_router),
             wifi: Some(is_wifi_enabled),
             dhcp: Some(is_router),
+            raid: Some(is_raid_supported),
+            lte: Some(is_lte_supported),
             network_mode: lan_config.mode,
+            permissions,
         })
     }
 
@@ -93,6 +121,48 @@ impl<'a> CapabilitiesAgent<'a> {
 
         Ok(wifi_config.enabled.unwrap_or(false))
     }
+
+    async fn is_raid_supported(&self) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        debug!("Checking if RAID is supported");
+        let system_config = get_system_config(self.client_factory).await?;
+        let board_name = system_config.board_name.unwrap_or_default();
+
+        info!("detected freebox board: {}", board_name);
+
+        Ok(RAID_BOARDS.iter().any(|b| board_name.starts_with(b)))
+    }
+
+    async fn is_lte_supported(&self) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        debug!("Checking if LTE is supported");
+        let client = self.client_factory.create_managed_client().await?;
+        let url = format!("{}v4/connection/lte/config", self.client_factory.api_url);
+        let res = client
+            .get()?
+            .get(url)
+            .send()
+            .await?
+            .json::<FreeboxResponse<LteConfig>>()
+            .await;
+
+        // boxes without 4G module reject the request
+        let lte_config = match res {
+            Ok(r) if r.success.unwrap_or(false) => r.result,
+            _ => None,
+        };
+
+        info!(
+            "detected lte support: {}",
+            lte_config.as_ref().map_or("none", |c| {
+                if c.enabled.unwrap_or(false) {
+                    "enabled"
+                } else {
+                    "disabled"
+                }
+            })
+        );
+
+        Ok(lte_config.is_some())
+    }
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -107,5 +177,19 @@ pub struct Capabilities {
     pub switch: Option<bool>,
     pub wifi: Option<bool>,
     pub dhcp: Option<bool>,
+    pub raid: Option<bool>,
+    pub lte: Option<bool>,
     pub network_mode: Option<String>,
+    pub permissions: Option<Permissions>,
+}
+
+impl Capabilities {
+    /// Whether the permission is known to be refused to the application,
+    /// unknown permissions are reported by the maps at runtime
+    pub fn is_permission_denied(&self, permission: &str) -> bool {
+        self.permissions
+            .as_ref()
+            .and_then(|p| p.is_granted(permission))
+            .is_some_and(|granted| !granted)
+    }
 }
@@ -9,6 +9,8 @@ use reqwest::{
 
 use crate::core::authenticator::SessionTokenProvider;
 
+use super::permission::Permissions;
+
 const FBX_APP_AUTH_HEADER: &str = "X-Fbx-App-Auth";
 
 const FBX_ECC_ROOT: &str = "
@@ -80,6 +82,12 @@ impl<'a> AuthenticatedHttpClientFactory<'a> {
         }
     }
 
+    /// Returns the permissions granted to the application in Freebox OS,
+    /// `None` when the box did not report them
+    pub fn get_permissions(&self) -> Option<Permissions> {
+        self.token_provider.get_permissions()
+    }
+
     /// Creates a new managed HTTP client with the necessary headers and configurations.
     ///
     /// # Returns
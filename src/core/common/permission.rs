@@ -1,3 +1,6 @@
+use std::fmt::Display;
+
+use log::warn;
 use serde::Deserialize;
 
 #[derive(Deserialize, Clone, Debug)]
@@ -13,6 +16,25 @@ pub struct Permissions {
     pub pvr: Option<bool>,
 }
 
+impl Permissions {
+    /// Whether the named permission is granted, `None` for an unknown name
+    pub fn is_granted(&self, permission: &str) -> Option<bool> {
+        let granted = match permission {
+            "connection" => self.connection,
+            "settings" => self.settings,
+            "contacts" => self.contacts,
+            "calls" => self.calls,
+            "explorer" => self.explorer,
+            "downloader" => self.downloader,
+            "parental" => self.parental,
+            "pvr" => self.pvr,
+            _ => return None,
+        };
+
+        Some(granted.unwrap_or(false))
+    }
+}
+
 impl Default for Permissions {
     fn default() -> Self {
         Self {
@@ -27,3 +49,61 @@ impl Default for Permissions {
         }
     }
 }
+
+/// Error code returned by the API when the application lacks a permission
+pub const INSUFFICIENT_RIGHTS: &str = "insufficient_rights";
+
+/// The application was not granted a permission required by an API
+#[derive(Debug)]
+pub struct PermissionError {
+    pub permission: String,
+}
+
+impl PermissionError {
+    pub fn new(permission: &str) -> Self {
+        Self {
+            permission: permission.to_string(),
+        }
+    }
+}
+
+impl Display for PermissionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "missing \"{}\" permission, grant it to the application in Freebox OS access management settings",
+            self.permission
+        )
+    }
+}
+
+impl std::error::Error for PermissionError {}
+
+/// A missing permission cannot be granted without user action, the maps
+/// requiring one warn once and keep running quietly until it is granted
+#[derive(Default)]
+pub struct PermissionWarning {
+    warned: bool,
+}
+
+impl PermissionWarning {
+    /// Swallows a permission error after warning about its first occurrence,
+    /// other results are returned as is
+    pub fn check(
+        &mut self,
+        metrics: &str,
+        res: Result<(), Box<dyn std::error::Error + Send + Sync>>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        match res {
+            Err(e) if e.is::<PermissionError>() => {
+                if !self.warned {
+                    warn!("{metrics} metrics are unavailable: {e}");
+                    self.warned = true;
+                }
+
+                Ok(())
+            }
+            res => res,
+        }
+    }
+}
@@ -6,7 +6,11 @@ use std::{
 };
 use tokio::{fs::File, io::AsyncReadExt};
 
-use crate::core::configuration::sections::PoliciesConfiguration;
+use crate::core::configuration::sections::{
+    ConnectionConfiguration, DeviceConfiguration, InfluxConfiguration, LimitsConfiguration,
+    NotifierConfiguration, OtlpConfiguration, PoliciesConfiguration, PresenceConfiguration,
+    RelabelConfiguration,
+};
 
 pub mod sections;
 pub mod tests;
@@ -17,6 +21,14 @@ pub struct Configuration {
     pub core: CoreConfiguration,
     pub log: LogConfiguration,
     pub policies: Option<PoliciesConfiguration>,
+    pub connection: Option<ConnectionConfiguration>,
+    pub otlp: Option<OtlpConfiguration>,
+    pub influx: Option<InfluxConfiguration>,
+    pub limits: Option<LimitsConfiguration>,
+    pub relabel: Option<Vec<RelabelConfiguration>>,
+    pub devices: Option<Vec<DeviceConfiguration>>,
+    pub presence: Option<PresenceConfiguration>,
+    pub notifier: Option<NotifierConfiguration>,
 }
 
 impl Configuration {
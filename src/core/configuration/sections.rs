@@ -1,9 +1,12 @@
+use std::collections::HashMap;
+
 use serde::Deserialize;
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct CoreConfiguration {
     pub data_directory: Option<String>,
     pub port: Option<u16>,
+    pub expose_metrics: Option<bool>,
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -11,9 +14,25 @@ pub struct ApiConfiguration {
     pub refresh: Option<u64>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Clone, Debug, Default)]
 pub struct PoliciesConfiguration {
     pub unresolved_station_hostnames: Option<String>,
+    pub stale_series_grace: Option<u64>,
+    pub max_download_tasks: Option<u64>,
+    pub phone_numbers: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ConnectionConfiguration {
+    pub sfp: Option<SfpConfiguration>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct SfpConfiguration {
+    pub rx_power_low_dbm: Option<f64>,
+    pub rx_power_high_dbm: Option<f64>,
+    pub tx_power_low_dbm: Option<f64>,
+    pub tx_power_high_dbm: Option<f64>,
 }
 
 
@@ -26,7 +45,16 @@ pub struct CapabilitiesConfiguration {
     pub switch: Option<bool>,
     pub wifi: Option<bool>,
     pub dhcp: Option<bool>,
+    pub devices: Option<bool>,
+    pub storage: Option<bool>,
+    pub downloads: Option<bool>,
+    pub calls: Option<bool>,
+    pub firewall: Option<bool>,
+    pub vpn: Option<bool>,
+    pub parental: Option<bool>,
+    pub freeplug: Option<bool>,
     pub prefix: Option<String>,
+    pub legacy_metrics: Option<bool>,
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -34,3 +62,86 @@ pub struct LogConfiguration {
     pub level: Option<String>,
     pub retention: Option<usize>,
 }
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct OtlpConfiguration {
+    pub enabled: Option<bool>,
+    pub endpoint: Option<String>,
+    pub protocol: Option<String>,
+    pub timeout: Option<u64>,
+    pub headers: Option<HashMap<String, String>>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct InfluxConfiguration {
+    pub enabled: Option<bool>,
+    pub target: Option<String>,
+    pub url: Option<String>,
+    pub org: Option<String>,
+    pub bucket: Option<String>,
+    pub token: Option<String>,
+    pub precision: Option<String>,
+    pub path: Option<String>,
+    pub timeout: Option<u64>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct LimitsConfiguration {
+    pub max_series: Option<usize>,
+    pub metrics: Option<Vec<MetricLimitsConfiguration>>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct MetricLimitsConfiguration {
+    pub name: String,
+    pub max_series: Option<usize>,
+    pub drop_labels: Option<Vec<String>>,
+    pub keep_labels: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct RelabelConfiguration {
+    pub source_labels: Option<Vec<String>>,
+    pub separator: Option<String>,
+    pub regex: Option<String>,
+    pub target_label: Option<String>,
+    pub replacement: Option<String>,
+    pub action: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct DeviceConfiguration {
+    pub mac: String,
+    pub name: Option<String>,
+    pub owner: Option<String>,
+    pub group: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct PresenceConfiguration {
+    pub enabled: Option<bool>,
+    pub absence_threshold: Option<u64>,
+    pub retention: Option<u64>,
+    pub webhook_url: Option<String>,
+    pub webhook_timeout: Option<u64>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct NotifierConfiguration {
+    pub enabled: Option<bool>,
+    pub url: Option<String>,
+    pub format: Option<String>,
+    pub topic: Option<String>,
+    pub timeout: Option<u64>,
+    pub headers: Option<HashMap<String, String>>,
+    pub rules: Option<Vec<NotifierRuleConfiguration>>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct NotifierRuleConfiguration {
+    pub name: String,
+    pub metric: String,
+    pub labels: Option<HashMap<String, String>>,
+    pub operator: String,
+    pub threshold: Option<f64>,
+}
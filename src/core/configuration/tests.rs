@@ -46,7 +46,57 @@ level = \"Info\"
 retention = 31
 
 [policies]
-unresolved_station_hostnames = \"ignore\"";
+unresolved_station_hostnames = \"ignore\"
+
+[connection.sfp]
+rx_power_low_dbm = -27.5
+
+[otlp]
+enabled = true
+endpoint = \"http://localhost:4318/v1/metrics\"
+
+[influx]
+enabled = true
+target = \"stdout\"
+
+[limits]
+max_series = 1000
+
+[[limits.metrics]]
+name = \"lan_browser_device\"
+drop_labels = [\"vendor_name\"]
+
+[[relabel]]
+source_labels = [\"__name__\"]
+regex = \"fbx_(.*)\"
+target_label = \"__name__\"
+replacement = \"freebox_$1\"
+
+[[relabel]]
+target_label = \"site\"
+replacement = \"paris\"
+
+[[devices]]
+mac = \"AA:BB:CC:00:00:01\"
+name = \"Tom's phone\"
+owner = \"tom\"
+group = \"kids\"
+
+[presence]
+enabled = true
+absence_threshold = 3600
+retention = 86400
+
+[notifier]
+enabled = true
+url = \"http://localhost:8080/hook\"
+
+[[notifier.rules]]
+name = \"wan_down\"
+metric = \"connection_state\"
+labels = { state = \"up\" }
+operator = \"<\"
+threshold = 1";
 
         file.write_all(content.as_bytes())
             .await
@@ -84,6 +134,58 @@ unresolved_station_hostnames = \"ignore\"";
         assert_eq!(9102, conf.core.port.unwrap());
         assert_eq!("Info", conf.log.level.unwrap());
         assert_eq!(31, conf.log.retention.unwrap());
+
+        let sfp = conf.connection.unwrap().sfp.unwrap();
+        assert_eq!(-27.5, sfp.rx_power_low_dbm.unwrap());
+        assert!(sfp.tx_power_high_dbm.is_none());
+
+        let otlp = conf.otlp.unwrap();
+        assert_eq!(true, otlp.enabled.unwrap());
+        assert_eq!(
+            "http://localhost:4318/v1/metrics",
+            otlp.endpoint.unwrap()
+        );
+        assert!(otlp.protocol.is_none());
+
+        let influx = conf.influx.unwrap();
+        assert_eq!(true, influx.enabled.unwrap());
+        assert_eq!("stdout", influx.target.unwrap());
+        assert!(influx.bucket.is_none());
+
+        let limits = conf.limits.unwrap();
+        assert_eq!(1000, limits.max_series.unwrap());
+        let metrics = limits.metrics.unwrap();
+        assert_eq!("lan_browser_device", metrics[0].name);
+        assert_eq!(
+            vec!["vendor_name".to_string()],
+            metrics[0].drop_labels.to_owned().unwrap()
+        );
+        assert!(metrics[0].max_series.is_none());
+
+        let relabel = conf.relabel.unwrap();
+        assert_eq!(2, relabel.len());
+        assert_eq!("__name__", relabel[0].target_label.to_owned().unwrap());
+        assert_eq!("freebox_$1", relabel[0].replacement.to_owned().unwrap());
+        assert!(relabel[1].source_labels.is_none());
+        assert!(relabel[1].action.is_none());
+
+        let devices = conf.devices.unwrap();
+        assert_eq!("AA:BB:CC:00:00:01", devices[0].mac);
+        assert_eq!("Tom's phone", devices[0].name.to_owned().unwrap());
+        assert_eq!("kids", devices[0].group.to_owned().unwrap());
+
+        let presence = conf.presence.unwrap();
+        assert_eq!(true, presence.enabled.unwrap());
+        assert_eq!(3600, presence.absence_threshold.unwrap());
+        assert_eq!(86400, presence.retention.unwrap());
+        assert!(presence.webhook_url.is_none());
+
+        let notifier = conf.notifier.unwrap();
+        assert_eq!(true, notifier.enabled.unwrap());
+        let rules = notifier.rules.unwrap();
+        assert_eq!("connection_state", rules[0].metric);
+        assert_eq!("up", rules[0].labels.to_owned().unwrap()["state"]);
+        assert_eq!(1.0, rules[0].threshold.unwrap());
     }
 
     #[test]
@@ -95,6 +197,7 @@ unresolved_station_hostnames = \"ignore\"";
             core: CoreConfiguration {
                 data_directory: Some("nowhere".to_string()),
                 port: None,
+                expose_metrics: None,
             },
             log: LogConfiguration {
                 level: None,
@@ -104,15 +207,35 @@ unresolved_station_hostnames = \"ignore\"";
                 connection: None,
                 system: None,
                 prefix: None,
+                legacy_metrics: None,
                 lan_browser: None,
                 lan: None,
                 switch: None,
                 wifi: None,
                 dhcp: None,
+                devices: None,
+                storage: None,
+                downloads: None,
+                calls: None,
+                firewall: None,
+                vpn: None,
+                parental: None,
+                freeplug: None,
             },
             policies: Some(PoliciesConfiguration {
                 unresolved_station_hostnames: None,
+                stale_series_grace: None,
+                max_download_tasks: None,
+                phone_numbers: None,
             }),
+            connection: None,
+            otlp: None,
+            influx: None,
+            limits: None,
+            relabel: None,
+            devices: None,
+            presence: None,
+            notifier: None,
         };
 
         let conf2 = Configuration {
@@ -122,6 +245,7 @@ unresolved_station_hostnames = \"ignore\"";
             core: CoreConfiguration {
                 data_directory: Some("".to_string()),
                 port: None,
+                expose_metrics: None,
             },
             log: LogConfiguration {
                 level: None,
@@ -131,15 +255,35 @@ unresolved_station_hostnames = \"ignore\"";
                 connection: None,
                 system: None,
                 prefix: None,
+                legacy_metrics: None,
                 lan_browser: None,
                 lan: None,
                 switch: None,
                 wifi: None,
                 dhcp: None,
+                devices: None,
+                storage: None,
+                downloads: None,
+                calls: None,
+                firewall: None,
+                vpn: None,
+                parental: None,
+                freeplug: None,
             },
             policies: Some(PoliciesConfiguration {
                 unresolved_station_hostnames: None,
+                stale_series_grace: None,
+                max_download_tasks: None,
+                phone_numbers: None,
             }),
+            connection: None,
+            otlp: None,
+            influx: None,
+            limits: None,
+            relabel: None,
+            devices: None,
+            presence: None,
+            notifier: None,
         };
 
         let conf3 = Configuration {
@@ -149,6 +293,7 @@ unresolved_station_hostnames = \"ignore\"";
             core: CoreConfiguration {
                 data_directory: Some(".".to_string()),
                 port: None,
+                expose_metrics: None,
             },
             log: LogConfiguration {
                 level: None,
@@ -158,15 +303,35 @@ unresolved_station_hostnames = \"ignore\"";
                 connection: None,
                 system: None,
                 prefix: None,
+                legacy_metrics: None,
                 lan_browser: None,
                 lan: None,
                 switch: None,
                 wifi: None,
                 dhcp: None,
+                devices: None,
+                storage: None,
+                downloads: None,
+                calls: None,
+                firewall: None,
+                vpn: None,
+                parental: None,
+                freeplug: None,
             },
             policies: Some(PoliciesConfiguration {
                 unresolved_station_hostnames: None,
+                stale_series_grace: None,
+                max_download_tasks: None,
+                phone_numbers: None,
             }),
+            connection: None,
+            otlp: None,
+            influx: None,
+            limits: None,
+            relabel: None,
+            devices: None,
+            presence: None,
+            notifier: None,
         };
 
         assert_eq!(true, conf.assert_data_dir_permissions().is_err());
@@ -183,6 +348,7 @@ unresolved_station_hostnames = \"ignore\"";
             core: CoreConfiguration {
                 data_directory: None,
                 port: None,
+                expose_metrics: None,
             },
             log: LogConfiguration {
                 level: None,
@@ -192,15 +358,35 @@ unresolved_station_hostnames = \"ignore\"";
                 connection: None,
                 system: None,
                 prefix: None,
+                legacy_metrics: None,
                 lan_browser: None,
                 lan: None,
                 switch: None,
                 wifi: None,
                 dhcp: None,
+                devices: None,
+                storage: None,
+                downloads: None,
+                calls: None,
+                firewall: None,
+                vpn: None,
+                parental: None,
+                freeplug: None,
             },
             policies: Some(PoliciesConfiguration {
                 unresolved_station_hostnames: None,
+                stale_series_grace: None,
+                max_download_tasks: None,
+                phone_numbers: None,
             }),
+            connection: None,
+            otlp: None,
+            influx: None,
+            limits: None,
+            relabel: None,
+            devices: None,
+            presence: None,
+            notifier: None,
         };
 
         let conf2 = Configuration {
@@ -210,6 +396,7 @@ unresolved_station_hostnames = \"ignore\"";
             core: CoreConfiguration {
                 data_directory: None,
                 port: None,
+                expose_metrics: None,
             },
             log: LogConfiguration {
                 level: None,
@@ -219,15 +406,35 @@ unresolved_station_hostnames = \"ignore\"";
                 connection: None,
                 system: None,
                 prefix: Some(" ".to_string()),
+                legacy_metrics: None,
                 lan_browser: None,
                 lan: None,
                 switch: None,
                 wifi: None,
                 dhcp: None,
+                devices: None,
+                storage: None,
+                downloads: None,
+                calls: None,
+                firewall: None,
+                vpn: None,
+                parental: None,
+                freeplug: None,
             },
             policies: Some(PoliciesConfiguration {
                 unresolved_station_hostnames: None,
+                stale_series_grace: None,
+                max_download_tasks: None,
+                phone_numbers: None,
             }),
+            connection: None,
+            otlp: None,
+            influx: None,
+            limits: None,
+            relabel: None,
+            devices: None,
+            presence: None,
+            notifier: None,
         };
 
         let conf3 = Configuration {
@@ -237,6 +444,7 @@ unresolved_station_hostnames = \"ignore\"";
             core: CoreConfiguration {
                 data_directory: None,
                 port: None,
+                expose_metrics: None,
             },
             log: LogConfiguration {
                 level: None,
@@ -246,15 +454,35 @@ unresolved_station_hostnames = \"ignore\"";
                 connection: None,
                 system: None,
                 prefix: Some("fbx_exporter".to_string()),
+                legacy_metrics: None,
                 lan_browser: None,
                 lan: None,
                 switch: None,
                 wifi: None,
                 dhcp: None,
+                devices: None,
+                storage: None,
+                downloads: None,
+                calls: None,
+                firewall: None,
+                vpn: None,
+                parental: None,
+                freeplug: None,
             },
             policies: Some(PoliciesConfiguration {
                 unresolved_station_hostnames: None,
+                stale_series_grace: None,
+                max_download_tasks: None,
+                phone_numbers: None,
             }),
+            connection: None,
+            otlp: None,
+            influx: None,
+            limits: None,
+            relabel: None,
+            devices: None,
+            presence: None,
+            notifier: None,
         };
 
         assert_eq!(Err(()), conf.assert_metrics_prefix_is_not_empty());
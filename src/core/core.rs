@@ -1,8 +1,19 @@
-use log::info;
+use log::{info, warn};
 
 use crate::{
-    core::{authenticator::Authenticator, capabilities::CapabilitiesAgent, discovery},
-    mappers::Mapper,
+    core::{
+        authenticator::Authenticator,
+        capabilities::CapabilitiesAgent,
+        common::http_client_factory::AuthenticatedHttpClientFactory,
+        discovery,
+        outputs::{
+            influx::InfluxOutput,
+            notifier::NotifierOutput,
+            otlp::{self, OtlpOutput},
+            MetricsOutput,
+        },
+    },
+    mappers::{system, Mapper},
 };
 
 use super::{
@@ -79,8 +90,19 @@ pub async fn auto_register_and_serve(
         capabilities,
         conf.api.clone(),
         conf.policies.clone(),
+        conf.connection.clone(),
+        conf.presence.clone(),
+        conf.core.data_directory.to_owned().unwrap(),
+    );
+    let outputs = create_outputs(conf, &factory).await;
+    let mut server = prometheus::Server::new(
+        port,
+        conf.api.refresh.unwrap_or(5),
+        conf.core.expose_metrics.unwrap_or(true),
+        create_pipeline(conf)?,
+        mapper,
+        outputs,
     );
-    let mut server = prometheus::Server::new(port, conf.api.refresh.unwrap_or(5), mapper);
 
     server.run().await
 }
@@ -220,12 +242,113 @@ pub async fn serve(
         capabilities,
         conf.to_owned().api,
         conf.to_owned().policies,
+        conf.to_owned().connection,
+        conf.to_owned().presence,
+        conf.core.data_directory.to_owned().unwrap(),
+    );
+    let outputs = create_outputs(&conf, &factory).await;
+    let mut server = prometheus::Server::new(
+        port,
+        conf.api.refresh.unwrap_or_else(|| 5),
+        conf.core.expose_metrics.unwrap_or(true),
+        create_pipeline(&conf)?,
+        mapper,
+        outputs,
     );
-    let mut server = prometheus::Server::new(port, conf.api.refresh.unwrap_or_else(|| 5), mapper);
 
     server.run().await
 }
 
+/// ### Create the metrics outputs
+/// This function will create the outputs enabled in the configuration,
+/// the metrics are pushed to them after each refresh iteration
+async fn create_outputs<'a>(
+    conf: &Configuration,
+    factory: &AuthenticatedHttpClientFactory<'a>,
+) -> Vec<Box<dyn MetricsOutput + 'a>> {
+    let mut outputs: Vec<Box<dyn MetricsOutput + 'a>> = vec![];
+
+    if let Some(otlp_conf) = conf.otlp.as_ref() {
+        if otlp_conf.enabled.unwrap_or(false) {
+            let protocol = otlp_conf
+                .protocol
+                .clone()
+                .unwrap_or(otlp::OTLP_HTTP_JSON_PROTOCOL.to_string());
+
+            if protocol != otlp::OTLP_HTTP_JSON_PROTOCOL {
+                warn!(
+                    "otlp protocol {} is not supported, only {} is, otlp output is disabled",
+                    protocol,
+                    otlp::OTLP_HTTP_JSON_PROTOCOL
+                );
+            } else {
+                let system = match system::get_system_config(factory).await {
+                    Ok(r) => Some(r),
+                    Err(e) => {
+                        warn!("unable to describe the box in otlp resource: {}", e);
+                        None
+                    }
+                };
+
+                outputs.push(Box::new(OtlpOutput::new(
+                    otlp_conf,
+                    otlp::get_resource_attributes(system.as_ref()),
+                )));
+            }
+        }
+    }
+
+    if let Some(influx_conf) = conf.influx.as_ref() {
+        if influx_conf.enabled.unwrap_or(false) {
+            match InfluxOutput::new(influx_conf, conf.metrics.prefix.to_owned().unwrap()) {
+                Ok(output) => outputs.push(Box::new(output)),
+                Err(e) => warn!("influx output is disabled: {}", e),
+            }
+        }
+    }
+
+    if let Some(notifier_conf) = conf.notifier.as_ref() {
+        if notifier_conf.enabled.unwrap_or(false) {
+            match NotifierOutput::new(notifier_conf, conf.metrics.prefix.to_owned().unwrap()) {
+                Ok(output) => outputs.push(Box::new(output)),
+                Err(e) => warn!("notifier is disabled: {}", e),
+            }
+        }
+    }
+
+    outputs
+}
+
+/// ### Create the metrics pipeline
+/// This function will create the transformations applied to the metrics
+/// before they are exposed or pushed to the outputs, invalid relabel rules
+/// are reported as an error
+fn create_pipeline(
+    conf: &Configuration,
+) -> Result<prometheus::pipeline::Pipeline, Box<dyn std::error::Error + Send + Sync>> {
+    let prefix = conf.metrics.prefix.to_owned().unwrap();
+    let inventory = conf
+        .devices
+        .as_ref()
+        .map(|devices_conf| prometheus::inventory::Inventory::new(devices_conf, &prefix));
+    let limits = conf
+        .limits
+        .as_ref()
+        .map(|limits_conf| prometheus::limits::Limits::new(limits_conf, &prefix));
+    let relabeler = match conf.relabel.as_ref() {
+        Some(relabel_conf) => Some(prometheus::relabel::Relabeler::new(relabel_conf)?),
+        None => None,
+    };
+
+    Ok(prometheus::pipeline::Pipeline::new(
+        prefix,
+        conf.metrics.legacy_metrics.unwrap_or(true),
+        inventory,
+        limits,
+        relabeler,
+    ))
+}
+
 async fn create_network_agnostic_authenticator(
     conf: &Configuration,
 ) -> Result<authenticator::Authenticator, Box<dyn std::error::Error + Send + Sync>> {
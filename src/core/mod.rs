@@ -6,4 +6,6 @@ pub mod core;
 pub mod discovery;
 pub mod logger;
 pub mod prometheus;
-pub mod capabilities;
\ No newline at end of file
+pub mod capabilities;
+pub mod outputs;
+pub mod webhook;
\ No newline at end of file
@@ -0,0 +1,395 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use log::{debug, info};
+use prometheus_exporter::prometheus::proto::MetricFamily;
+use reqwest::Client;
+use tokio::{fs::OpenOptions, io::AsyncWriteExt};
+
+use super::{get_labels, get_value, MetricsOutput};
+use crate::core::configuration::sections::InfluxConfiguration;
+
+pub const INFLUX_HTTP_TARGET: &str = "http";
+pub const INFLUX_FILE_TARGET: &str = "file";
+pub const INFLUX_STDOUT_TARGET: &str = "stdout";
+const DEFAULT_INFLUX_URL: &str = "http://localhost:8086";
+const DEFAULT_INFLUX_PATH: &str = "metrics.lp";
+const DEFAULT_INFLUX_PRECISION: &str = "s";
+const DEFAULT_INFLUX_TIMEOUT: u64 = 10;
+
+enum InfluxTarget {
+    Http {
+        client: Client,
+        url: String,
+        org: String,
+        bucket: String,
+        token: Option<String>,
+    },
+    File(String),
+    Stdout,
+}
+
+/// Writes metrics as InfluxDB line protocol, either to an InfluxDB v2 write endpoint,
+/// to a file or to the standard output (e.g. for Telegraf `exec` input)
+pub struct InfluxOutput {
+    prefix: String,
+    precision: String,
+    target: InfluxTarget,
+}
+
+impl InfluxOutput {
+    pub fn new(
+        conf: &InfluxConfiguration,
+        prefix: String,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let precision = conf
+            .precision
+            .clone()
+            .unwrap_or(DEFAULT_INFLUX_PRECISION.to_string());
+
+        if !["ns", "us", "ms", "s"].contains(&precision.as_str()) {
+            return Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("unsupported influx precision: {}", precision),
+            )));
+        }
+
+        let target = match conf
+            .target
+            .clone()
+            .unwrap_or(INFLUX_HTTP_TARGET.to_string())
+            .as_str()
+        {
+            INFLUX_HTTP_TARGET => {
+                let (Some(org), Some(bucket)) = (conf.org.clone(), conf.bucket.clone()) else {
+                    return Err(Box::new(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "influx org and bucket are required for http target",
+                    )));
+                };
+
+                let client = reqwest::ClientBuilder::new()
+                    .timeout(Duration::from_secs(
+                        conf.timeout.unwrap_or(DEFAULT_INFLUX_TIMEOUT),
+                    ))
+                    .build()
+                    .expect("cannot create HTTP Client");
+
+                InfluxTarget::Http {
+                    client,
+                    url: conf
+                        .url
+                        .clone()
+                        .unwrap_or(DEFAULT_INFLUX_URL.to_string())
+                        .trim_end_matches('/')
+                        .to_string(),
+                    org,
+                    bucket,
+                    token: conf.token.clone().filter(|t| !t.is_empty()),
+                }
+            }
+            INFLUX_FILE_TARGET => {
+                InfluxTarget::File(conf.path.clone().unwrap_or(DEFAULT_INFLUX_PATH.to_string()))
+            }
+            INFLUX_STDOUT_TARGET => InfluxTarget::Stdout,
+            other => {
+                return Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("unsupported influx target: {}", other),
+                )))
+            }
+        };
+
+        Ok(Self {
+            prefix,
+            precision,
+            target,
+        })
+    }
+
+    /// Metric names are `{prefix}_{map}_{field}`, the first segment after the
+    /// prefix names the measurement, so new maps need no registration here
+    fn get_measurement<'b>(&self, name: &'b str) -> Option<(&'b str, &'b str)> {
+        name.strip_prefix(&format!("{}_", self.prefix))?
+            .split_once('_')
+    }
+
+    fn get_timestamp(&self) -> u128 {
+        let elapsed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        match self.precision.as_str() {
+            "ns" => elapsed.as_nanos(),
+            "us" => elapsed.as_micros(),
+            "ms" => elapsed.as_millis(),
+            _ => elapsed.as_secs() as u128,
+        }
+    }
+
+    /// Converts the gathered families to line protocol, samples sharing the same
+    /// measurement and tag set are written as fields of a single line
+    pub fn to_lines(&self, families: &[MetricFamily], timestamp: u128) -> Vec<String> {
+        let mut series: Vec<(String, Vec<String>)> = vec![];
+
+        for family in families.iter() {
+            let Some((measurement, field)) = self.get_measurement(family.get_name()) else {
+                continue;
+            };
+
+            for metric in family.get_metric().iter() {
+                let value = get_value(family, metric);
+
+                if !value.is_finite() {
+                    continue;
+                }
+
+                let mut tags = get_labels(metric)
+                    .into_iter()
+                    .filter(|(_, v)| !v.is_empty())
+                    .collect::<Vec<(String, String)>>();
+                tags.sort();
+
+                let mut key = escape_measurement(measurement);
+                for (name, value) in tags.iter() {
+                    key.push_str(&format!(",{}={}", escape_tag(name), escape_tag(value)));
+                }
+
+                let field = format!("{}={}", escape_tag(field), value);
+
+                match series.iter_mut().find(|(k, _)| *k == key) {
+                    Some((_, fields)) => fields.push(field),
+                    None => series.push((key, vec![field])),
+                }
+            }
+        }
+
+        series
+            .into_iter()
+            .map(|(key, fields)| format!("{} {} {}", key, fields.join(","), timestamp))
+            .collect()
+    }
+}
+
+fn escape_measurement(value: &str) -> String {
+    value.replace(',', "\\,").replace(' ', "\\ ")
+}
+
+fn escape_tag(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace('=', "\\=")
+        .replace(' ', "\\ ")
+}
+
+#[async_trait]
+impl MetricsOutput for InfluxOutput {
+    async fn init(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        match &self.target {
+            InfluxTarget::Http { url, bucket, .. } => {
+                info!("writing influx line protocol to {} bucket {}", url, bucket)
+            }
+            InfluxTarget::File(path) => info!("writing influx line protocol to file {}", path),
+            InfluxTarget::Stdout => info!("writing influx line protocol to stdout"),
+        }
+        Ok(())
+    }
+
+    async fn push(
+        &mut self,
+        families: &[MetricFamily],
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let lines = self.to_lines(families, self.get_timestamp());
+
+        if lines.is_empty() {
+            return Ok(());
+        }
+
+        debug!("writing {} influx lines", lines.len());
+
+        let mut body = lines.join("\n");
+        body.push('\n');
+
+        match &self.target {
+            InfluxTarget::Http {
+                client,
+                url,
+                org,
+                bucket,
+                token,
+            } => {
+                let mut request = client
+                    .post(format!("{}/api/v2/write", url))
+                    .query(&[
+                        ("org", org.as_str()),
+                        ("bucket", bucket.as_str()),
+                        ("precision", self.precision.as_str()),
+                    ])
+                    .header("Content-Type", "text/plain; charset=utf-8")
+                    .body(body);
+
+                if let Some(token) = token {
+                    request = request.header("Authorization", format!("Token {}", token));
+                }
+
+                let res = request.send().await?;
+
+                if !res.status().is_success() {
+                    return Err(Box::new(std::io::Error::new(
+                        std::io::ErrorKind::Other,
+                        format!(
+                            "influx endpoint responded with status {}: {}",
+                            res.status(),
+                            res.text().await.unwrap_or_default()
+                        ),
+                    )));
+                }
+            }
+            InfluxTarget::File(path) => {
+                let mut file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .await?;
+                file.write_all(body.as_bytes()).await?;
+            }
+            InfluxTarget::Stdout => {
+                let mut stdout = tokio::io::stdout();
+                stdout.write_all(body.as_bytes()).await?;
+                stdout.flush().await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prometheus_exporter::prometheus::{IntGauge, IntGaugeVec, Opts, Registry};
+    use wiremock::{
+        matchers::{header, method, path, query_param},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+
+    fn get_conf(target: &str, url: Option<String>) -> InfluxConfiguration {
+        InfluxConfiguration {
+            enabled: Some(true),
+            target: Some(target.to_string()),
+            url,
+            org: Some("home".to_string()),
+            bucket: Some("freebox".to_string()),
+            token: Some("secret".to_string()),
+            precision: None,
+            path: None,
+            timeout: None,
+        }
+    }
+
+    fn get_registry() -> Registry {
+        let registry = Registry::new();
+
+        let rate_down = IntGaugeVec::new(
+            Opts::new("fbx_connection_rate_down", "download rate"),
+            &["media", "state"],
+        )
+        .unwrap();
+        let rate_up = IntGaugeVec::new(
+            Opts::new("fbx_connection_rate_up", "upload rate"),
+            &["media", "state"],
+        )
+        .unwrap();
+        let hosts = IntGaugeVec::new(
+            Opts::new("fbx_lan_browser_reachable", "reachable"),
+            &["name", "vendor"],
+        )
+        .unwrap();
+        let mode = IntGauge::new("fbx_lan_config_mode_router", "router").unwrap();
+
+        registry.register(Box::new(rate_down.clone())).unwrap();
+        registry.register(Box::new(rate_up.clone())).unwrap();
+        registry.register(Box::new(hosts.clone())).unwrap();
+        registry.register(Box::new(mode.clone())).unwrap();
+
+        rate_down.with_label_values(&["ftth", "up"]).set(1000);
+        rate_up.with_label_values(&["ftth", "up"]).set(500);
+        hosts.with_label_values(&["my pc", ""]).set(1);
+        mode.set(1);
+
+        registry
+    }
+
+    #[test]
+    fn should_convert_families_to_line_protocol() {
+        let output = InfluxOutput::new(&get_conf("stdout", None), "fbx".to_string()).unwrap();
+
+        let lines = output.to_lines(&get_registry().gather(), 1700000000);
+
+        assert!(lines.contains(
+            &"connection,media=ftth,state=up rate_down=1000,rate_up=500 1700000000".to_string()
+        ));
+        assert!(lines.contains(&"lan,name=my\\ pc browser_reachable=1 1700000000".to_string()));
+        assert!(lines.contains(&"lan config_mode_router=1 1700000000".to_string()));
+        assert_eq!(3, lines.len());
+    }
+
+    #[test]
+    fn should_reject_http_target_without_bucket() {
+        let mut conf = get_conf("http", None);
+        conf.bucket = None;
+
+        assert!(InfluxOutput::new(&conf, "fbx".to_string()).is_err());
+    }
+
+    #[tokio::test]
+    async fn should_write_to_influx_endpoint() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/api/v2/write"))
+            .and(query_param("org", "home"))
+            .and(query_param("bucket", "freebox"))
+            .and(query_param("precision", "s"))
+            .and(header("Authorization", "Token secret"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let mut output = InfluxOutput::new(
+            &get_conf("http", Some(mock_server.uri())),
+            "fbx".to_string(),
+        )
+        .unwrap();
+
+        output.push(&get_registry().gather()).await.unwrap();
+
+        let requests = mock_server.received_requests().await.unwrap();
+        let body = String::from_utf8(requests[0].body.clone()).unwrap();
+
+        assert!(body.starts_with("connection,media=ftth,state=up rate_down=1000,rate_up=500 "));
+        assert!(body.ends_with('\n'));
+    }
+
+    #[tokio::test]
+    async fn should_fail_when_influx_rejects_write() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/api/v2/write"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&mock_server)
+            .await;
+
+        let mut output = InfluxOutput::new(
+            &get_conf("http", Some(mock_server.uri())),
+            "fbx".to_string(),
+        )
+        .unwrap();
+
+        assert!(output.push(&get_registry().gather()).await.is_err());
+    }
+}
@@ -0,0 +1,35 @@
+use async_trait::async_trait;
+use prometheus_exporter::prometheus::proto::{Metric, MetricFamily, MetricType};
+
+pub mod influx;
+pub mod notifier;
+pub mod otlp;
+
+/// An output receives the metrics gathered after each refresh iteration,
+/// it runs alongside or instead of the prometheus `/metrics` endpoint
+#[async_trait]
+pub trait MetricsOutput: Send {
+    async fn init(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+    async fn push(
+        &mut self,
+        families: &[MetricFamily],
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// Returns the labels of a sample as (name, value) pairs
+pub fn get_labels(metric: &Metric) -> Vec<(String, String)> {
+    metric
+        .get_label()
+        .iter()
+        .map(|l| (l.get_name().to_string(), l.get_value().to_string()))
+        .collect()
+}
+
+/// Returns the value of a sample, whatever its metric type is
+pub fn get_value(family: &MetricFamily, metric: &Metric) -> f64 {
+    match family.get_field_type() {
+        MetricType::COUNTER => metric.get_counter().get_value(),
+        MetricType::UNTYPED => metric.get_untyped().get_value(),
+        _ => metric.get_gauge().get_value(),
+    }
+}
@@ -0,0 +1,575 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use async_trait::async_trait;
+use log::{info, warn};
+use prometheus_exporter::prometheus::proto::MetricFamily;
+use serde::Serialize;
+
+use super::{get_labels, get_value, MetricsOutput};
+use crate::core::{
+    configuration::sections::{NotifierConfiguration, NotifierRuleConfiguration},
+    webhook::Webhook,
+};
+
+pub const NOTIFIER_JSON_FORMAT: &str = "json";
+pub const NOTIFIER_NTFY_FORMAT: &str = "ntfy";
+pub const NOTIFIER_GOTIFY_FORMAT: &str = "gotify";
+
+const FIRING_STATUS: &str = "firing";
+const RESOLVED_STATUS: &str = "resolved";
+const CHANGED_STATUS: &str = "changed";
+
+/// Undelivered notifications kept for the next push, the oldest are dropped
+/// beyond this count
+const MAX_PENDING_NOTIFICATIONS: usize = 100;
+
+type Labels = Vec<(String, String)>;
+
+#[derive(Debug, PartialEq)]
+enum Operator {
+    Lower(f64),
+    LowerOrEqual(f64),
+    Greater(f64),
+    GreaterOrEqual(f64),
+    Equal(f64),
+    NotEqual(f64),
+    Changed,
+}
+
+impl Operator {
+    fn new(
+        conf: &NotifierRuleConfiguration,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        if conf.operator == "changed" {
+            return Ok(Operator::Changed);
+        }
+
+        let Some(threshold) = conf.threshold else {
+            return Err(format!("notifier rule {} requires a threshold", conf.name).into());
+        };
+
+        match conf.operator.as_str() {
+            "<" => Ok(Operator::Lower(threshold)),
+            "<=" => Ok(Operator::LowerOrEqual(threshold)),
+            ">" => Ok(Operator::Greater(threshold)),
+            ">=" => Ok(Operator::GreaterOrEqual(threshold)),
+            "==" => Ok(Operator::Equal(threshold)),
+            "!=" => Ok(Operator::NotEqual(threshold)),
+            other => Err(format!("unsupported notifier operator {other}").into()),
+        }
+    }
+
+    fn matches(&self, value: f64) -> bool {
+        match self {
+            Operator::Lower(t) => value < *t,
+            Operator::LowerOrEqual(t) => value <= *t,
+            Operator::Greater(t) => value > *t,
+            Operator::GreaterOrEqual(t) => value >= *t,
+            Operator::Equal(t) => value == *t,
+            Operator::NotEqual(t) => value != *t,
+            Operator::Changed => false,
+        }
+    }
+}
+
+struct Rule {
+    name: String,
+    metric: String,
+    labels: HashMap<String, String>,
+    operator: Operator,
+}
+
+impl Rule {
+    /// Returns the series of the rule metric matching its labels
+    fn select(&self, families: &[MetricFamily]) -> Option<BTreeMap<Labels, f64>> {
+        let family = families.iter().find(|f| f.get_name() == self.metric)?;
+
+        let series = family
+            .get_metric()
+            .iter()
+            .map(|m| (get_labels(m), get_value(family, m)))
+            .filter(|(labels, _)| {
+                self.labels
+                    .iter()
+                    .all(|(name, value)| labels.iter().any(|(n, v)| n == name && v == value))
+            })
+            .collect();
+
+        Some(series)
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+struct Notification {
+    rule: String,
+    status: String,
+    metric: String,
+    labels: BTreeMap<String, String>,
+    value: f64,
+    message: String,
+}
+
+impl Notification {
+    fn new(rule: &Rule, status: &str, labels: &Labels, value: f64) -> Self {
+        Self::with_state(rule, status, labels, value, &format!("is {value}"))
+    }
+
+    /// The series is no longer exposed, the value is the last one seen
+    fn absent(rule: &Rule, labels: &Labels, value: f64) -> Self {
+        Self::with_state(rule, RESOLVED_STATUS, labels, value, "is no longer exposed")
+    }
+
+    fn with_state(rule: &Rule, status: &str, labels: &Labels, value: f64, state: &str) -> Self {
+        let labels: BTreeMap<String, String> = labels.iter().cloned().collect();
+        let description = labels
+            .iter()
+            .map(|(name, value)| format!("{name}=\"{value}\""))
+            .collect::<Vec<String>>()
+            .join(",");
+
+        Self {
+            rule: rule.name.to_owned(),
+            status: status.to_string(),
+            metric: rule.metric.to_owned(),
+            message: format!(
+                "{} {status}: {}{{{description}}} {state}",
+                rule.name, rule.metric
+            ),
+            labels,
+            value,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct NtfyPayload {
+    topic: String,
+    title: String,
+    message: String,
+    priority: u8,
+    tags: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct GotifyPayload {
+    title: String,
+    message: String,
+    priority: u8,
+}
+
+/// Evaluates the configured rules against the metrics after each refresh and
+/// notifies a webhook when a rule starts or stops matching, so critical state
+/// changes are reported even when no alerting stack is running
+pub struct NotifierOutput {
+    webhook: Webhook,
+    format: String,
+    topic: String,
+    rules: Vec<Rule>,
+    firing: HashMap<(usize, Labels), f64>,
+    previous: HashMap<usize, BTreeMap<Labels, f64>>,
+    pending: VecDeque<Notification>,
+}
+
+impl NotifierOutput {
+    pub fn new(
+        conf: &NotifierConfiguration,
+        prefix: String,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let Some(url) = conf.url.to_owned() else {
+            return Err("notifier url is required".into());
+        };
+
+        let format = conf
+            .format
+            .to_owned()
+            .unwrap_or(NOTIFIER_JSON_FORMAT.to_string());
+
+        if ![
+            NOTIFIER_JSON_FORMAT,
+            NOTIFIER_NTFY_FORMAT,
+            NOTIFIER_GOTIFY_FORMAT,
+        ]
+        .contains(&format.as_str())
+        {
+            return Err(format!("unsupported notifier format: {format}").into());
+        }
+
+        if format == NOTIFIER_NTFY_FORMAT && conf.topic.is_none() {
+            return Err("notifier topic is required for ntfy format".into());
+        }
+
+        let rules = conf
+            .rules
+            .to_owned()
+            .unwrap_or_default()
+            .iter()
+            .map(|rule| {
+                Ok(Rule {
+                    name: rule.name.to_owned(),
+                    metric: format!("{prefix}_{}", rule.metric),
+                    labels: rule.labels.to_owned().unwrap_or_default(),
+                    operator: Operator::new(rule)?,
+                })
+            })
+            .collect::<Result<Vec<Rule>, Box<dyn std::error::Error + Send + Sync>>>()?;
+
+        Ok(Self {
+            webhook: Webhook::new(url, conf.timeout)
+                .with_headers(conf.headers.to_owned().unwrap_or_default()),
+            format,
+            topic: conf.topic.to_owned().unwrap_or_default(),
+            rules,
+            firing: HashMap::new(),
+            previous: HashMap::new(),
+            pending: VecDeque::new(),
+        })
+    }
+
+    /// Returns the notifications for the rules whose state changed since the
+    /// previous evaluation, a firing rule is only notified once until it recovers.
+    /// A rule whose metric is missing from this collection is left as is
+    fn evaluate(&mut self, families: &[MetricFamily]) -> Vec<Notification> {
+        let mut notifications = vec![];
+
+        for (index, rule) in self.rules.iter().enumerate() {
+            let Some(series) = rule.select(families) else {
+                continue;
+            };
+
+            if rule.operator == Operator::Changed {
+                // only the series whose value changed or which just appeared
+                if let Some(previous) = self.previous.get(&index) {
+                    for (labels, value) in series.iter() {
+                        if previous.get(labels) != Some(value) {
+                            notifications.push(Notification::new(
+                                rule,
+                                CHANGED_STATUS,
+                                labels,
+                                *value,
+                            ));
+                        }
+                    }
+                }
+
+                self.previous.insert(index, series);
+                continue;
+            }
+
+            for (labels, value) in series.iter() {
+                let key = (index, labels.to_owned());
+                let matches = rule.operator.matches(*value);
+
+                if matches && !self.firing.contains_key(&key) {
+                    notifications.push(Notification::new(rule, FIRING_STATUS, labels, *value));
+                } else if !matches && self.firing.remove(&key).is_some() {
+                    notifications.push(Notification::new(rule, RESOLVED_STATUS, labels, *value));
+                }
+
+                if matches {
+                    self.firing.insert(key, *value);
+                }
+            }
+
+            // a firing series which is gone will not recover by itself
+            self.firing.retain(|(i, labels), value| {
+                if *i != index || series.contains_key(labels) {
+                    return true;
+                }
+
+                notifications.push(Notification::absent(rule, labels, *value));
+                false
+            });
+        }
+
+        notifications
+    }
+
+    async fn send(
+        &self,
+        notification: &Notification,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let title = format!("Freebox {} {}", notification.rule, notification.status);
+        let priority = match notification.status.as_str() {
+            RESOLVED_STATUS => 3,
+            _ => 4,
+        };
+
+        match self.format.as_str() {
+            NOTIFIER_NTFY_FORMAT => {
+                self.webhook
+                    .post(&NtfyPayload {
+                        topic: self.topic.to_owned(),
+                        title,
+                        message: notification.message.to_owned(),
+                        priority,
+                        tags: vec![notification.status.to_owned()],
+                    })
+                    .await
+            }
+            NOTIFIER_GOTIFY_FORMAT => {
+                self.webhook
+                    .post(&GotifyPayload {
+                        title,
+                        message: notification.message.to_owned(),
+                        priority: priority * 2,
+                    })
+                    .await
+            }
+            _ => self.webhook.post(notification).await,
+        }
+    }
+}
+
+#[async_trait]
+impl MetricsOutput for NotifierOutput {
+    async fn init(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        info!("evaluating {} notifier rules", self.rules.len());
+        Ok(())
+    }
+
+    /// The webhook may be unreachable while the state it reports lasts (e.g.
+    /// WAN down with a remote ntfy server), undelivered notifications are
+    /// queued and sent in order on the next pushes
+    async fn push(
+        &mut self,
+        families: &[MetricFamily],
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        for notification in self.evaluate(families) {
+            info!("{}", notification.message);
+            self.pending.push_back(notification);
+        }
+
+        while let Some(notification) = self.pending.front() {
+            if let Err(e) = self.send(notification).await {
+                warn!(
+                    "cannot send {} notification, {} pending: {}",
+                    notification.rule,
+                    self.pending.len(),
+                    e
+                );
+                break;
+            }
+
+            self.pending.pop_front();
+        }
+
+        while self.pending.len() > MAX_PENDING_NOTIFICATIONS {
+            if let Some(notification) = self.pending.pop_front() {
+                warn!(
+                    "dropping undelivered notification: {}",
+                    notification.message
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prometheus_exporter::prometheus::{IntGaugeVec, Opts, Registry};
+    use serde_json::Value;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+
+    fn get_conf(url: String, format: &str) -> NotifierConfiguration {
+        NotifierConfiguration {
+            enabled: Some(true),
+            url: Some(url),
+            format: Some(format.to_string()),
+            topic: Some("freebox".to_string()),
+            timeout: None,
+            headers: None,
+            rules: Some(vec![
+                NotifierRuleConfiguration {
+                    name: "wan_down".to_string(),
+                    metric: "connection_state".to_string(),
+                    labels: Some(HashMap::from([("state".to_string(), "up".to_string())])),
+                    operator: "<".to_string(),
+                    threshold: Some(1.0),
+                },
+                NotifierRuleConfiguration {
+                    name: "disk_status".to_string(),
+                    metric: "system_disk_status".to_string(),
+                    labels: None,
+                    operator: "changed".to_string(),
+                    threshold: None,
+                },
+            ]),
+        }
+    }
+
+    #[tokio::test]
+    async fn should_notify_once_and_recover() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+
+        let registry = Registry::new();
+        let state =
+            IntGaugeVec::new(Opts::new("fbx_connection_state", "state"), &["state"]).unwrap();
+        let disk_status = IntGaugeVec::new(
+            Opts::new("fbx_system_disk_status", "disk"),
+            &["disk_status"],
+        )
+        .unwrap();
+        registry.register(Box::new(state.clone())).unwrap();
+        registry.register(Box::new(disk_status.clone())).unwrap();
+
+        let conf = get_conf(format!("{}/hook", mock_server.uri()), NOTIFIER_JSON_FORMAT);
+        let mut output = NotifierOutput::new(&conf, "fbx".to_string()).unwrap();
+
+        state.with_label_values(&["up"]).set(1);
+        disk_status.with_label_values(&["active"]).set(1);
+        output.push(&registry.gather()).await.unwrap();
+
+        state.with_label_values(&["up"]).set(0);
+        output.push(&registry.gather()).await.unwrap();
+        output.push(&registry.gather()).await.unwrap();
+
+        state.with_label_values(&["up"]).set(1);
+        disk_status.reset();
+        disk_status.with_label_values(&["error"]).set(1);
+        output.push(&registry.gather()).await.unwrap();
+
+        let requests = mock_server.received_requests().await.unwrap();
+        let bodies: Vec<Value> = requests
+            .iter()
+            .map(|r| serde_json::from_slice(&r.body).unwrap())
+            .collect();
+
+        assert_eq!(3, bodies.len());
+        assert_eq!("wan_down", bodies[0]["rule"]);
+        assert_eq!(FIRING_STATUS, bodies[0]["status"]);
+        assert_eq!(0.0, bodies[0]["value"]);
+        assert_eq!(RESOLVED_STATUS, bodies[1]["status"]);
+        assert_eq!("disk_status", bodies[2]["rule"]);
+        assert_eq!(CHANGED_STATUS, bodies[2]["status"]);
+        assert_eq!("error", bodies[2]["labels"]["disk_status"]);
+    }
+
+    #[tokio::test]
+    async fn should_send_ntfy_payload() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let registry = Registry::new();
+        let state =
+            IntGaugeVec::new(Opts::new("fbx_connection_state", "state"), &["state"]).unwrap();
+        registry.register(Box::new(state.clone())).unwrap();
+        state.with_label_values(&["up"]).set(0);
+
+        let conf = get_conf(mock_server.uri(), NOTIFIER_NTFY_FORMAT);
+        let mut output = NotifierOutput::new(&conf, "fbx".to_string()).unwrap();
+        output.push(&registry.gather()).await.unwrap();
+
+        let requests = mock_server.received_requests().await.unwrap();
+        let body: Value = serde_json::from_slice(&requests[0].body).unwrap();
+
+        assert_eq!("freebox", body["topic"]);
+        assert_eq!("Freebox wan_down firing", body["title"]);
+        assert_eq!(4, body["priority"]);
+    }
+
+    #[tokio::test]
+    async fn should_retry_undelivered_notifications() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+
+        let registry = Registry::new();
+        let state =
+            IntGaugeVec::new(Opts::new("fbx_connection_state", "state"), &["state"]).unwrap();
+        registry.register(Box::new(state.clone())).unwrap();
+
+        let conf = get_conf(mock_server.uri(), NOTIFIER_JSON_FORMAT);
+        let mut output = NotifierOutput::new(&conf, "fbx".to_string()).unwrap();
+
+        state.with_label_values(&["up"]).set(0);
+        output.push(&registry.gather()).await.unwrap();
+        assert_eq!(1, output.pending.len());
+
+        state.with_label_values(&["up"]).set(1);
+        output.push(&registry.gather()).await.unwrap();
+        assert!(output.pending.is_empty());
+
+        let requests = mock_server.received_requests().await.unwrap();
+        let bodies: Vec<Value> = requests
+            .iter()
+            .map(|r| serde_json::from_slice(&r.body).unwrap())
+            .collect();
+
+        // the failed firing notification is delivered before the resolved one
+        assert_eq!(3, bodies.len());
+        assert_eq!(FIRING_STATUS, bodies[0]["status"]);
+        assert_eq!(FIRING_STATUS, bodies[1]["status"]);
+        assert_eq!(RESOLVED_STATUS, bodies[2]["status"]);
+    }
+
+    #[test]
+    fn should_resolve_absent_series_and_diff_changes() {
+        let registry = Registry::new();
+        let state = IntGaugeVec::new(
+            Opts::new("fbx_connection_state", "state"),
+            &["state", "iface"],
+        )
+        .unwrap();
+        let disk_status =
+            IntGaugeVec::new(Opts::new("fbx_system_disk_status", "disk"), &["disk"]).unwrap();
+        registry.register(Box::new(state.clone())).unwrap();
+        registry.register(Box::new(disk_status.clone())).unwrap();
+
+        let conf = get_conf("http://localhost".to_string(), NOTIFIER_JSON_FORMAT);
+        let mut output = NotifierOutput::new(&conf, "fbx".to_string()).unwrap();
+
+        state.with_label_values(&["up", "wan"]).set(0);
+        state.with_label_values(&["up", "lte"]).set(0);
+        disk_status.with_label_values(&["sda"]).set(1);
+        disk_status.with_label_values(&["sdb"]).set(1);
+        assert_eq!(2, output.evaluate(&registry.gather()).len());
+
+        state.remove_label_values(&["up", "lte"]).unwrap();
+        disk_status.with_label_values(&["sdb"]).set(2);
+        let notifications = output.evaluate(&registry.gather());
+
+        assert_eq!(2, notifications.len());
+        assert_eq!(RESOLVED_STATUS, notifications[0].status);
+        assert_eq!("lte", notifications[0].labels["iface"]);
+        assert!(notifications[0].message.ends_with("is no longer exposed"));
+        assert_eq!(CHANGED_STATUS, notifications[1].status);
+        assert_eq!("sdb", notifications[1].labels["disk"]);
+    }
+
+    #[test]
+    fn should_reject_invalid_rules() {
+        let mut conf = get_conf("http://localhost".to_string(), NOTIFIER_JSON_FORMAT);
+        conf.rules.as_mut().unwrap()[0].operator = "~".to_string();
+        assert!(NotifierOutput::new(&conf, "fbx".to_string()).is_err());
+
+        let conf = get_conf("http://localhost".to_string(), "slack");
+        assert!(NotifierOutput::new(&conf, "fbx".to_string()).is_err());
+    }
+}
@@ -0,0 +1,405 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use log::{debug, info};
+use prometheus_exporter::prometheus::proto::{MetricFamily, MetricType};
+use reqwest::Client;
+use serde::Serialize;
+
+use super::{get_labels, get_value, MetricsOutput};
+use crate::{core::configuration::sections::OtlpConfiguration, mappers::system::SystemConfig};
+
+pub const DEFAULT_OTLP_ENDPOINT: &str = "http://localhost:4318/v1/metrics";
+pub const OTLP_HTTP_JSON_PROTOCOL: &str = "http/json";
+const DEFAULT_OTLP_TIMEOUT: u64 = 10;
+const SCOPE_NAME: &str = env!("CARGO_PKG_NAME");
+const SCOPE_VERSION: &str = env!("CARGO_PKG_VERSION");
+// c.f. https://opentelemetry.io/docs/specs/otel/metrics/data-model/#sums
+const AGGREGATION_TEMPORALITY_CUMULATIVE: i32 = 2;
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ExportMetricsServiceRequest {
+    resource_metrics: Vec<ResourceMetrics>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ResourceMetrics {
+    resource: Resource,
+    scope_metrics: Vec<ScopeMetrics>,
+}
+
+#[derive(Serialize, Debug)]
+struct Resource {
+    attributes: Vec<KeyValue>,
+}
+
+#[derive(Serialize, Debug)]
+struct ScopeMetrics {
+    scope: InstrumentationScope,
+    metrics: Vec<OtlpMetric>,
+}
+
+#[derive(Serialize, Debug)]
+struct InstrumentationScope {
+    name: String,
+    version: String,
+}
+
+#[derive(Serialize, Debug)]
+struct OtlpMetric {
+    name: String,
+    description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gauge: Option<Gauge>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sum: Option<Sum>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Gauge {
+    data_points: Vec<NumberDataPoint>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Sum {
+    data_points: Vec<NumberDataPoint>,
+    aggregation_temporality: i32,
+    is_monotonic: bool,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct NumberDataPoint {
+    attributes: Vec<KeyValue>,
+    start_time_unix_nano: String,
+    time_unix_nano: String,
+    as_double: f64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+struct KeyValue {
+    key: String,
+    value: AnyValue,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct AnyValue {
+    string_value: String,
+}
+
+impl KeyValue {
+    fn new(key: &str, value: &str) -> Self {
+        Self {
+            key: key.to_string(),
+            value: AnyValue {
+                string_value: value.to_string(),
+            },
+        }
+    }
+}
+
+/// Builds the OTLP resource attributes describing the box
+pub fn get_resource_attributes(system: Option<&SystemConfig>) -> Vec<(String, String)> {
+    let mut attributes = vec![
+        ("service.name".to_string(), SCOPE_NAME.to_string()),
+        ("service.version".to_string(), SCOPE_VERSION.to_string()),
+    ];
+
+    let Some(system) = system else {
+        return attributes;
+    };
+
+    let model = system
+        .box_model_name
+        .clone()
+        .or_else(|| system.board_name.clone());
+
+    if let Some(model) = model {
+        attributes.push(("freebox.model".to_string(), model));
+    }
+    if let Some(serial) = system.serial.clone() {
+        attributes.push(("freebox.serial".to_string(), serial));
+    }
+    if let Some(firmware) = system.firmware_version.clone() {
+        attributes.push(("freebox.firmware_version".to_string(), firmware));
+    }
+
+    attributes
+}
+
+/// Pushes metrics to an OpenTelemetry collector using OTLP/HTTP with JSON encoding
+pub struct OtlpOutput {
+    client: Client,
+    endpoint: String,
+    headers: HashMap<String, String>,
+    resource: Vec<KeyValue>,
+    start_time: u128,
+}
+
+impl OtlpOutput {
+    pub fn new(conf: &OtlpConfiguration, resource_attributes: Vec<(String, String)>) -> Self {
+        let timeout = Duration::from_secs(conf.timeout.unwrap_or(DEFAULT_OTLP_TIMEOUT));
+
+        let client = reqwest::ClientBuilder::new()
+            .timeout(timeout)
+            .build()
+            .expect("cannot create HTTP Client");
+
+        Self {
+            client,
+            endpoint: conf
+                .endpoint
+                .clone()
+                .unwrap_or(DEFAULT_OTLP_ENDPOINT.to_string()),
+            headers: conf.headers.clone().unwrap_or_default(),
+            resource: resource_attributes
+                .iter()
+                .map(|(k, v)| KeyValue::new(k, v))
+                .collect(),
+            start_time: get_unix_nanos(),
+        }
+    }
+
+    fn build_request(&self, families: &[MetricFamily], time: u128) -> ExportMetricsServiceRequest {
+        let mut metrics = vec![];
+
+        for family in families.iter() {
+            let data_points: Vec<NumberDataPoint> = family
+                .get_metric()
+                .iter()
+                .map(|m| NumberDataPoint {
+                    attributes: get_labels(m)
+                        .iter()
+                        .map(|(k, v)| KeyValue::new(k, v))
+                        .collect(),
+                    start_time_unix_nano: self.start_time.to_string(),
+                    time_unix_nano: time.to_string(),
+                    as_double: get_value(family, m),
+                })
+                .collect();
+
+            if data_points.is_empty() {
+                continue;
+            }
+
+            let (gauge, sum) = match family.get_field_type() {
+                MetricType::COUNTER => (
+                    None,
+                    Some(Sum {
+                        data_points,
+                        aggregation_temporality: AGGREGATION_TEMPORALITY_CUMULATIVE,
+                        is_monotonic: true,
+                    }),
+                ),
+                _ => (Some(Gauge { data_points }), None),
+            };
+
+            metrics.push(OtlpMetric {
+                name: family.get_name().to_string(),
+                description: family.get_help().to_string(),
+                gauge,
+                sum,
+            });
+        }
+
+        ExportMetricsServiceRequest {
+            resource_metrics: vec![ResourceMetrics {
+                resource: Resource {
+                    attributes: self.resource.clone(),
+                },
+                scope_metrics: vec![ScopeMetrics {
+                    scope: InstrumentationScope {
+                        name: SCOPE_NAME.to_string(),
+                        version: SCOPE_VERSION.to_string(),
+                    },
+                    metrics,
+                }],
+            }],
+        }
+    }
+}
+
+fn get_unix_nanos() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default()
+}
+
+#[async_trait]
+impl MetricsOutput for OtlpOutput {
+    async fn init(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        info!("exporting metrics to OTLP endpoint {}", self.endpoint);
+        Ok(())
+    }
+
+    async fn push(
+        &mut self,
+        families: &[MetricFamily],
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        debug!("pushing metrics to OTLP endpoint");
+
+        let payload = self.build_request(families, get_unix_nanos());
+
+        let mut request = self.client.post(&self.endpoint).json(&payload);
+
+        for (name, value) in self.headers.iter() {
+            request = request.header(name, value);
+        }
+
+        let res = request.send().await?;
+
+        if !res.status().is_success() {
+            return Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!(
+                    "OTLP endpoint responded with status {}: {}",
+                    res.status(),
+                    res.text().await.unwrap_or_default()
+                ),
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prometheus_exporter::prometheus::{IntCounter, IntGaugeVec, Opts, Registry};
+    use serde_json::Value;
+    use wiremock::{
+        matchers::{header, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn should_push_metrics_to_receiver() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/metrics"))
+            .and(header("x-scope-orgid", "home"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let registry = Registry::new();
+        let gauge = IntGaugeVec::new(
+            Opts::new("fbx_connection_rate_down", "download rate"),
+            &["media"],
+        )
+        .unwrap();
+        let counter = IntCounter::new("fbx_connection_down_bytes_total", "bytes").unwrap();
+        registry.register(Box::new(gauge.clone())).unwrap();
+        registry.register(Box::new(counter.clone())).unwrap();
+        gauge.with_label_values(&["ftth"]).set(42);
+        counter.inc_by(1024);
+
+        let conf = OtlpConfiguration {
+            enabled: Some(true),
+            endpoint: Some(format!("{}/v1/metrics", mock_server.uri())),
+            protocol: None,
+            timeout: None,
+            headers: Some(HashMap::from([(
+                "x-scope-orgid".to_string(),
+                "home".to_string(),
+            )])),
+        };
+
+        let system = SystemConfig {
+            mac: None,
+            box_flavor: None,
+            box_model_name: Some("Freebox v9 (r1)".to_string()),
+            device_name: None,
+            api_version: None,
+            temp_hdd: None,
+            temp_cpub: None,
+            temp_cpum: None,
+            temp_t1: None,
+            temp_t2: None,
+            temp_cpu0: None,
+            temp_cpu1: None,
+            temp_cpu2: None,
+            temp_cpu3: None,
+            disk_status: None,
+            box_authenticated: None,
+            board_name: Some("fbxgw9r".to_string()),
+            fan_rpm: None,
+            temp_sw: None,
+            uptime_val: None,
+            user_main_storage: None,
+            serial: Some("FB9XXXXXXXXXXXXXX".to_string()),
+            firmware_version: Some("4.9.14".to_string()),
+        };
+
+        let mut output = OtlpOutput::new(&conf, get_resource_attributes(Some(&system)));
+        output.push(&registry.gather()).await.unwrap();
+
+        let requests = mock_server.received_requests().await.unwrap();
+        let body: Value = serde_json::from_slice(&requests[0].body).unwrap();
+        let resource_metrics = &body["resourceMetrics"][0];
+
+        let attributes = resource_metrics["resource"]["attributes"]
+            .as_array()
+            .unwrap();
+        assert!(attributes.iter().any(|a| a["key"] == "freebox.model"
+            && a["value"]["stringValue"] == "Freebox v9 (r1)"));
+        assert!(attributes.iter().any(|a| a["key"] == "freebox.serial"
+            && a["value"]["stringValue"] == "FB9XXXXXXXXXXXXXX"));
+        assert!(attributes.iter().any(|a| a["key"] == "freebox.firmware_version"
+            && a["value"]["stringValue"] == "4.9.14"));
+
+        let metrics = resource_metrics["scopeMetrics"][0]["metrics"]
+            .as_array()
+            .unwrap();
+
+        let rate = metrics
+            .iter()
+            .find(|m| m["name"] == "fbx_connection_rate_down")
+            .unwrap();
+        assert_eq!(42.0, rate["gauge"]["dataPoints"][0]["asDouble"]);
+        assert_eq!("media", rate["gauge"]["dataPoints"][0]["attributes"][0]["key"]);
+
+        let bytes = metrics
+            .iter()
+            .find(|m| m["name"] == "fbx_connection_down_bytes_total")
+            .unwrap();
+        assert_eq!(1024.0, bytes["sum"]["dataPoints"][0]["asDouble"]);
+        assert_eq!(true, bytes["sum"]["isMonotonic"]);
+    }
+
+    #[tokio::test]
+    async fn should_fail_when_receiver_rejects_payload() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/metrics"))
+            .respond_with(ResponseTemplate::new(400))
+            .mount(&mock_server)
+            .await;
+
+        let conf = OtlpConfiguration {
+            enabled: Some(true),
+            endpoint: Some(format!("{}/v1/metrics", mock_server.uri())),
+            protocol: None,
+            timeout: None,
+            headers: None,
+        };
+
+        let mut output = OtlpOutput::new(&conf, get_resource_attributes(None));
+
+        assert!(output.push(&[]).await.is_err());
+    }
+}
@@ -1,40 +1,80 @@
-use std::net::SocketAddr;
+use std::{net::SocketAddr, sync::Arc};
 
-use log::{debug, info};
+use log::{debug, error, info, warn};
+use prometheus_exporter::prometheus::proto::MetricFamily;
+use tokio::{net::TcpListener, sync::RwLock};
 
-use crate::mappers::Mapper;
+use crate::{core::outputs::MetricsOutput, mappers::Mapper};
+
+pub mod http;
+pub mod inventory;
+pub mod legacy;
+pub mod limits;
+pub mod openmetrics;
+pub mod pipeline;
+pub mod relabel;
 
 pub struct Server<'a> {
     port: u16,
     refresh_interval: u64,
+    expose_metrics: bool,
+    pipeline: Arc<pipeline::Pipeline>,
     mapper: Mapper<'a>,
+    outputs: Vec<Box<dyn MetricsOutput + 'a>>,
 }
 
 impl<'a> Server<'a> {
-    pub fn new(port: u16, refresh_interval: u64, mapper: Mapper<'a>) -> Self {
+    pub fn new(
+        port: u16,
+        refresh_interval: u64,
+        expose_metrics: bool,
+        pipeline: pipeline::Pipeline,
+        mapper: Mapper<'a>,
+        outputs: Vec<Box<dyn MetricsOutput + 'a>>,
+    ) -> Self {
         Self {
             port,
             refresh_interval,
+            expose_metrics,
+            pipeline: Arc::new(pipeline),
             mapper,
+            outputs,
         }
     }
 
     pub async fn run(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         debug!("initiating prometheus server");
 
-        let addr_raw = format!("0.0.0.0:{}", self.port);
+        if !self.expose_metrics && self.outputs.is_empty() {
+            warn!("metrics are neither exposed on /metrics nor exported to any output");
+        }
 
-        info!("starting http server on {}", addr_raw);
+        // families of the last complete refresh, scrapes never wait for the box
+        let snapshot: Arc<RwLock<Vec<MetricFamily>>> = Arc::new(RwLock::new(vec![]));
 
-        let addr: SocketAddr = match addr_raw.parse() {
-            Err(e) => return Err(Box::new(e)),
-            Ok(r) => r,
-        };
+        if self.expose_metrics {
+            let addr_raw = format!("0.0.0.0:{}", self.port);
 
-        let exporter = match prometheus_exporter::start(addr) {
-            Err(e) => return Err(Box::new(e)),
-            Ok(r) => r,
-        };
+            info!("starting http server on {}", addr_raw);
+
+            let addr: SocketAddr = match addr_raw.parse() {
+                Err(e) => return Err(Box::new(e)),
+                Ok(r) => r,
+            };
+
+            let listener = match TcpListener::bind(addr).await {
+                Err(e) => return Err(Box::new(e)),
+                Ok(r) => r,
+            };
+
+            let exposition = http::Exposition {
+                snapshot: snapshot.clone(),
+            };
+
+            tokio::spawn(http::serve(listener, Arc::new(exposition)));
+        } else {
+            info!("/metrics endpoint is disabled");
+        }
 
         let duration = std::time::Duration::from_secs(self.refresh_interval);
 
@@ -45,6 +85,10 @@ impl<'a> Server<'a> {
             _ => {}
         };
 
+        for output in self.outputs.iter_mut() {
+            output.init().await?;
+        }
+
         loop {
             debug!("fetching result from mapper maps");
 
@@ -53,9 +97,21 @@ impl<'a> Server<'a> {
                 _ => {}
             };
 
+            let families = self.pipeline.gather();
+
+            for output in self.outputs.iter_mut() {
+                if let Err(e) = output.push(&families).await {
+                    error!("failed to push metrics to output: {}", e);
+                }
+            }
+
+            if self.expose_metrics {
+                *snapshot.write().await = self.pipeline.expose(families);
+            }
+
             i = i + 1;
 
-            let _guard = exporter.wait_duration(duration);
+            tokio::time::sleep(duration).await;
         }
     }
 }
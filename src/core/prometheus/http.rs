@@ -0,0 +1,207 @@
+use std::{convert::Infallible, sync::Arc, time::Duration};
+
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::{
+    body::Incoming,
+    header::{self, HeaderValue},
+    server::conn::http1,
+    service::service_fn,
+    Method, Request, Response, StatusCode,
+};
+use hyper_util::rt::{TokioIo, TokioTimer};
+use log::{debug, error};
+use prometheus_exporter::prometheus::{proto::MetricFamily, Encoder, TextEncoder, TEXT_FORMAT};
+use tokio::{net::TcpListener, sync::RwLock};
+
+use super::openmetrics;
+
+const METRICS_PATH: &str = "/metrics";
+/// Connections sending no complete request headers within this delay are closed
+const HEADER_READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Shared state of the `/metrics` endpoint, the snapshot is swapped once the
+/// maps are refreshed so scrapes never see a partial update nor wait for the box
+pub struct Exposition {
+    pub snapshot: Arc<RwLock<Vec<MetricFamily>>>,
+}
+
+impl Exposition {
+    /// Encodes the last refreshed metrics in the format negotiated
+    /// with the `Accept` header, returns the content type and the body
+    pub async fn render(
+        &self,
+        accept: &str,
+    ) -> Result<(&'static str, Vec<u8>), Box<dyn std::error::Error + Send + Sync>> {
+        let families = self.snapshot.read().await.clone();
+
+        if accepts_openmetrics(accept) {
+            return Ok((
+                openmetrics::OPENMETRICS_CONTENT_TYPE,
+                openmetrics::encode(&families).into_bytes(),
+            ));
+        }
+
+        let encoder = TextEncoder::new();
+        let mut buffer = vec![];
+        encoder.encode(&families, &mut buffer)?;
+
+        Ok((TEXT_FORMAT, buffer))
+    }
+}
+
+/// Returns true when the client prefers OpenMetrics over the prometheus text format
+pub fn accepts_openmetrics(accept: &str) -> bool {
+    let mut openmetrics_q = 0.0;
+    let mut text_q = 0.0;
+
+    for media_range in accept.split(',') {
+        let mut params = media_range.split(';').map(|p| p.trim());
+        let media_type = params.next().unwrap_or_default().to_lowercase();
+
+        let q = params
+            .filter_map(|p| p.strip_prefix("q="))
+            .next()
+            .and_then(|q| q.parse::<f64>().ok())
+            .unwrap_or(1.0);
+
+        match media_type.as_str() {
+            "application/openmetrics-text" => openmetrics_q = f64::max(openmetrics_q, q),
+            "text/plain" => text_q = f64::max(text_q, q),
+            _ => {}
+        }
+    }
+
+    openmetrics_q > 0.0 && openmetrics_q >= text_q
+}
+
+/// Serves the `/metrics` endpoint until the listener fails
+pub async fn serve(listener: TcpListener, exposition: Arc<Exposition>) {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                error!("failed to accept connection: {}", e);
+                continue;
+            }
+        };
+
+        let exposition = exposition.clone();
+
+        tokio::spawn(async move {
+            let service = service_fn(|req| {
+                let exposition = exposition.clone();
+                async move { handle(req, &exposition).await }
+            });
+
+            let mut builder = http1::Builder::new();
+            builder
+                .timer(TokioTimer::new())
+                .header_read_timeout(HEADER_READ_TIMEOUT)
+                .keep_alive(false);
+
+            if let Err(e) = builder
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                debug!("failed to handle request: {}", e);
+            }
+        });
+    }
+}
+
+async fn handle(
+    req: Request<Incoming>,
+    exposition: &Exposition,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    if req.method() != Method::GET && req.method() != Method::HEAD {
+        return Ok(empty_response(StatusCode::METHOD_NOT_ALLOWED));
+    }
+
+    if req.uri().path() != METRICS_PATH {
+        return Ok(empty_response(StatusCode::NOT_FOUND));
+    }
+
+    let accept = req
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+
+    match exposition.render(accept).await {
+        Ok((content_type, body)) => {
+            let mut res = Response::new(Full::new(Bytes::from(body)));
+            res.headers_mut()
+                .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+
+            Ok(res)
+        }
+        Err(e) => {
+            error!("failed to encode metrics: {}", e);
+            Ok(empty_response(StatusCode::INTERNAL_SERVER_ERROR))
+        }
+    }
+}
+
+fn empty_response(status: StatusCode) -> Response<Full<Bytes>> {
+    let mut res = Response::new(Full::new(Bytes::new()));
+    *res.status_mut() = status;
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use prometheus_exporter::prometheus::{IntGauge, Registry};
+
+    use super::*;
+
+    #[test]
+    fn should_negotiate_openmetrics() {
+        // prometheus 2.x scrape header
+        assert!(accepts_openmetrics(
+            "application/openmetrics-text;version=1.0.0,application/openmetrics-text;version=0.0.1;q=0.75,text/plain;version=0.0.4;q=0.5,*/*;q=0.1"
+        ));
+        assert!(!accepts_openmetrics("text/plain;version=0.0.4"));
+        assert!(!accepts_openmetrics("*/*"));
+        assert!(!accepts_openmetrics(""));
+        assert!(!accepts_openmetrics(
+            "application/openmetrics-text;q=0.2,text/plain;q=0.9"
+        ));
+    }
+    #[tokio::test]
+    async fn should_serve_negotiated_snapshot() {
+        let registry = Registry::new();
+        let gauge = IntGauge::new("fbx_connection_state", "test").unwrap();
+        registry.register(Box::new(gauge.clone())).unwrap();
+        gauge.set(1);
+
+        let exposition = Exposition {
+            snapshot: Arc::new(RwLock::new(registry.gather())),
+        };
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener, Arc::new(exposition)));
+
+        let client = reqwest::Client::new();
+        let res = client
+            .get(format!("http://{addr}/metrics"))
+            .header("Accept", "application/openmetrics-text;version=1.0.0")
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            openmetrics::OPENMETRICS_CONTENT_TYPE,
+            res.headers()["content-type"]
+        );
+        assert!(res.text().await.unwrap().contains("fbx_connection_state 1"));
+
+        let res = client
+            .get(format!("http://{addr}/other"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(404, res.status().as_u16());
+    }
+}
@@ -0,0 +1,312 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use prometheus_exporter::prometheus::proto::{Gauge, LabelPair, Metric, MetricFamily, MetricType};
+
+use crate::core::{configuration::sections::DeviceConfiguration, outputs::get_value};
+
+/// Labels holding a device MAC address, depending on the metric map
+const MAC_LABELS: [&str; 3] = ["mac", "l2ident_id", "ident"];
+const UNASSIGNED_GROUP: &str = "unassigned";
+
+/// Metrics telling a device is currently connected, without the configured
+/// prefix, as (name, needs a non zero value) pairs
+const ACTIVITY_METRICS: [(&str, bool); 3] = [
+    ("lan_browser_device", true),
+    ("wifi_station_active", true),
+    ("switch_port_mac_list", false),
+];
+
+struct Device {
+    name: String,
+    owner: String,
+    group: String,
+}
+
+/// Devices declared in the configuration, their friendly name, owner and group
+/// are added as labels to every series carrying their MAC address, the series
+/// of other devices get the same labels empty so a family keeps one label set
+pub struct Inventory {
+    prefix: String,
+    devices: HashMap<String, Device>,
+}
+
+impl Inventory {
+    pub fn new(confs: &[DeviceConfiguration], prefix: &str) -> Self {
+        let devices = confs
+            .iter()
+            .map(|conf| {
+                (
+                    conf.mac.to_lowercase(),
+                    Device {
+                        name: conf.name.to_owned().unwrap_or_default(),
+                        owner: conf.owner.to_owned().unwrap_or_default(),
+                        group: conf
+                            .group
+                            .to_owned()
+                            .unwrap_or(UNASSIGNED_GROUP.to_string()),
+                    },
+                )
+            })
+            .collect();
+
+        Self {
+            prefix: prefix.to_string(),
+            devices,
+        }
+    }
+
+    fn has_mac(&self, metric: &Metric) -> bool {
+        metric
+            .get_label()
+            .iter()
+            .any(|l| MAC_LABELS.contains(&l.get_name()))
+    }
+
+    fn get_device(&self, metric: &Metric) -> Option<&Device> {
+        metric
+            .get_label()
+            .iter()
+            .filter(|l| MAC_LABELS.contains(&l.get_name()))
+            .find_map(|l| self.devices.get(&l.get_value().to_lowercase()))
+    }
+
+    fn get_active_macs(&self, families: &[MetricFamily]) -> HashSet<String> {
+        let mut active = HashSet::new();
+
+        for (name, needs_value) in ACTIVITY_METRICS {
+            let name = format!("{}_{name}", self.prefix);
+
+            let Some(family) = families.iter().find(|f| f.get_name() == name) else {
+                continue;
+            };
+
+            for metric in family.get_metric().iter() {
+                if needs_value && get_value(family, metric) == 0.0 {
+                    continue;
+                }
+
+                let mac = metric
+                    .get_label()
+                    .iter()
+                    .find(|l| MAC_LABELS.contains(&l.get_name()) && !l.get_value().is_empty());
+
+                if let Some(mac) = mac {
+                    active.insert(mac.get_value().to_lowercase());
+                }
+            }
+        }
+
+        active
+    }
+
+    /// Adds the device labels to the series carrying a MAC address and appends
+    /// the per group aggregates
+    pub fn apply(&self, families: Vec<MetricFamily>) -> Vec<MetricFamily> {
+        let active = self.get_active_macs(&families);
+
+        let mut families: Vec<MetricFamily> = families
+            .into_iter()
+            .map(|mut family| {
+                for metric in family.mut_metric().iter_mut() {
+                    if !self.has_mac(metric) {
+                        continue;
+                    }
+
+                    let (name, owner, group) = match self.get_device(metric) {
+                        Some(d) => (d.name.as_str(), d.owner.as_str(), d.group.as_str()),
+                        None => ("", "", ""),
+                    };
+
+                    let mut labels = metric.take_label().into_vec();
+                    labels.push(label_pair("device_name", name));
+                    labels.push(label_pair("device_owner", owner));
+                    labels.push(label_pair("device_group", group));
+                    labels.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+
+                    metric.set_label(labels.into());
+                }
+
+                family
+            })
+            .collect();
+
+        let mut known: BTreeMap<&str, i64> = BTreeMap::new();
+        let mut connected: BTreeMap<&str, i64> = BTreeMap::new();
+
+        for device in self.devices.values() {
+            *known.entry(&device.group).or_default() += 1;
+            connected.entry(&device.group).or_default();
+        }
+
+        for mac in active.iter() {
+            let group = match self.devices.get(mac) {
+                Some(device) => device.group.as_str(),
+                None => UNASSIGNED_GROUP,
+            };
+
+            *connected.entry(group).or_default() += 1;
+        }
+
+        families.push(group_family(
+            format!("{}_device_group_devices", self.prefix),
+            "Number of inventory devices in the group",
+            &known,
+        ));
+        families.push(group_family(
+            format!("{}_device_group_active_devices", self.prefix),
+            "Number of devices of the group currently connected",
+            &connected,
+        ));
+        families.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+
+        families
+    }
+}
+
+fn label_pair(name: &str, value: &str) -> LabelPair {
+    let mut pair = LabelPair::default();
+    pair.set_name(name.to_string());
+    pair.set_value(value.to_string());
+    pair
+}
+
+fn group_family(name: String, help: &str, counts: &BTreeMap<&str, i64>) -> MetricFamily {
+    let mut family = MetricFamily::default();
+    family.set_name(name);
+    family.set_help(help.to_string());
+    family.set_field_type(MetricType::GAUGE);
+
+    for (group, count) in counts.iter() {
+        let mut gauge = Gauge::default();
+        gauge.set_value(*count as f64);
+
+        let mut metric = Metric::default();
+        metric.set_label(vec![label_pair("group", group)].into());
+        metric.set_gauge(gauge);
+
+        family.mut_metric().push(metric);
+    }
+
+    family
+}
+
+#[cfg(test)]
+mod tests {
+    use prometheus_exporter::prometheus::{core::Collector, IntGaugeVec, Opts};
+
+    use super::*;
+
+    fn device(mac: &str, name: &str, group: &str) -> DeviceConfiguration {
+        DeviceConfiguration {
+            mac: mac.to_string(),
+            name: Some(name.to_string()),
+            owner: Some("alice".to_string()),
+            group: Some(group.to_string()),
+        }
+    }
+
+    fn get_families() -> Vec<MetricFamily> {
+        let stations = IntGaugeVec::new(
+            Opts::new("fbx_wifi_station_active", "test"),
+            &["primary_name", "mac"],
+        )
+        .unwrap();
+
+        stations
+            .with_label_values(&["android-3f2a", "AA:BB:CC:00:00:01"])
+            .set(1);
+        stations
+            .with_label_values(&["printer", "aa:bb:cc:00:00:02"])
+            .set(0);
+        stations
+            .with_label_values(&["laptop", "aa:bb:cc:00:00:03"])
+            .set(1);
+
+        let mac_list = IntGaugeVec::new(
+            Opts::new("fbx_switch_port_mac_list", "test"),
+            &["port", "mac", "hostname"],
+        )
+        .unwrap();
+
+        mac_list
+            .with_label_values(&["1", "aa:bb:cc:00:00:04", ""])
+            .set(1);
+
+        let mut families = stations.collect();
+        families.append(&mut mac_list.collect());
+        families
+    }
+
+    fn get_label<'a>(metric: &'a Metric, name: &str) -> &'a str {
+        metric
+            .get_label()
+            .iter()
+            .find(|l| l.get_name() == name)
+            .map(|l| l.get_value())
+            .unwrap_or_default()
+    }
+
+    fn get_group_value(family: &MetricFamily, group: &str) -> f64 {
+        family
+            .get_metric()
+            .iter()
+            .find(|m| get_label(m, "group") == group)
+            .map(|m| m.get_gauge().get_value())
+            .unwrap_or(-1.0)
+    }
+
+    #[test]
+    fn should_label_known_devices_and_count_groups() {
+        let inventory = Inventory::new(
+            &[
+                device("aa:bb:cc:00:00:01", "Tom's phone", "kids"),
+                device("AA:BB:CC:00:00:02", "Printer", "iot"),
+                device("aa:bb:cc:00:00:04", "NAS", "iot"),
+            ],
+            "fbx",
+        );
+
+        let families = inventory.apply(get_families());
+
+        let stations = families
+            .iter()
+            .find(|f| f.get_name() == "fbx_wifi_station_active")
+            .unwrap();
+        let phone = stations
+            .get_metric()
+            .iter()
+            .find(|m| get_label(m, "primary_name") == "android-3f2a")
+            .unwrap();
+        let laptop = stations
+            .get_metric()
+            .iter()
+            .find(|m| get_label(m, "primary_name") == "laptop")
+            .unwrap();
+
+        assert_eq!("Tom's phone", get_label(phone, "device_name"));
+        assert_eq!("alice", get_label(phone, "device_owner"));
+        assert_eq!("kids", get_label(phone, "device_group"));
+
+        // devices missing from the inventory get the labels empty
+        let names: Vec<&str> = laptop.get_label().iter().map(|l| l.get_name()).collect();
+        assert!(names.contains(&"device_name"));
+        assert!(names.contains(&"device_owner"));
+        assert!(names.contains(&"device_group"));
+        assert_eq!("", get_label(laptop, "device_name"));
+
+        let known = families
+            .iter()
+            .find(|f| f.get_name() == "fbx_device_group_devices")
+            .unwrap();
+        assert_eq!(1.0, get_group_value(known, "kids"));
+        assert_eq!(2.0, get_group_value(known, "iot"));
+
+        let active = families
+            .iter()
+            .find(|f| f.get_name() == "fbx_device_group_active_devices")
+            .unwrap();
+        assert_eq!(1.0, get_group_value(active, "kids"));
+        assert_eq!(1.0, get_group_value(active, "iot"));
+        assert_eq!(1.0, get_group_value(active, UNASSIGNED_GROUP));
+    }
+}
@@ -0,0 +1,284 @@
+use prometheus_exporter::prometheus::proto::{Gauge, Metric, MetricFamily, MetricType};
+
+use crate::core::outputs::get_value;
+
+/// Metrics renamed to follow OpenMetrics naming (units, counters `_total` suffix),
+/// as (current name, legacy name) pairs without the configured prefix
+const RENAMED_METRICS: [(&str, &str); 69] = [
+    ("connection_down_bytes_total", "connection_bytes_down"),
+    ("connection_up_bytes_total", "connection_bytes_up"),
+    (
+        "connection_rate_down_bytes_per_second",
+        "connection_rate_down",
+    ),
+    ("connection_rate_up_bytes_per_second", "connection_rate_up"),
+    (
+        "connection_bandwidth_down_bits_per_second",
+        "connection_bandwidth_down",
+    ),
+    (
+        "connection_bandwidth_up_bits_per_second",
+        "connection_bandwidth_up",
+    ),
+    (
+        "connection_xdsl_status_uptime_seconds",
+        "connection_xdsl_status_uptime",
+    ),
+    (
+        "connection_xdsl_stats_fec_total",
+        "connection_xdsl_stats_fec",
+    ),
+    (
+        "connection_xdsl_stats_crc_total",
+        "connection_xdsl_stats_crc",
+    ),
+    (
+        "connection_xdsl_stats_hec_total",
+        "connection_xdsl_stats_hec",
+    ),
+    (
+        "connection_xdsl_stats_es_seconds_total",
+        "connection_xdsl_stats_es",
+    ),
+    (
+        "connection_xdsl_stats_ses_seconds_total",
+        "connection_xdsl_stats_ses",
+    ),
+    (
+        "connection_xdsl_stats_rxmt_total",
+        "connection_xdsl_stats_rxmt",
+    ),
+    (
+        "connection_xdsl_stats_rxmt_corr_total",
+        "connection_xdsl_stats_rxmt_corr",
+    ),
+    (
+        "connection_xdsl_stats_rxmt_uncorr_total",
+        "connection_xdsl_stats_rxmt_uncorr",
+    ),
+    (
+        "connection_xdsl_stats_rtx_tx_total",
+        "connection_xdsl_stats_rtx_tx",
+    ),
+    (
+        "connection_xdsl_stats_rtx_c_total",
+        "connection_xdsl_stats_rtx_c",
+    ),
+    (
+        "connection_xdsl_stats_rtx_uc_total",
+        "connection_xdsl_stats_rtx_uc",
+    ),
+    ("system_temp_hdd_celsius", "system_temp_hdd"),
+    ("system_temp_t1_celsius", "system_temp_t1"),
+    ("system_temp_t2_celsius", "system_temp_t2"),
+    ("system_temp_cpu_celsius", "system_temp_cpu"),
+    ("system_temp_cpub_celsius", "system_temp_cpub"),
+    ("system_temp_cpum_celsius", "system_temp_cpum"),
+    ("system_temp_sw_celsius", "system_temp_sw"),
+    ("system_uptime_seconds", "system_uptime_val"),
+    (
+        "lan_browser_device_last_activity_timestamp_seconds",
+        "lan_browser_device_last_activity",
+    ),
+    ("dhcp_lease_remaining_seconds", "dhcp_lease_remaining"),
+    ("dhcp_refresh_timestamp_seconds", "dhcp_refresh_time"),
+    ("dhcp_assign_timestamp_seconds", "dhcp_assign_time"),
+    (
+        "switch_stats_rx_packets_per_second",
+        "switch_stats_rx_packets_rate",
+    ),
+    (
+        "switch_stats_tx_packets_per_second",
+        "switch_stats_tx_packets_rate",
+    ),
+    (
+        "switch_stats_rx_bytes_per_second",
+        "switch_stats_rx_bytes_rate",
+    ),
+    (
+        "switch_stats_tx_bytes_per_second",
+        "switch_stats_tx_bytes_rate",
+    ),
+    (
+        "switch_stats_rx_good_bytes_total",
+        "switch_stats_rx_good_bytes",
+    ),
+    (
+        "switch_stats_rx_oversize_packets_total",
+        "switch_stats_rx_oversize_packets",
+    ),
+    (
+        "switch_stats_rx_unicast_packets_total",
+        "switch_stats_rx_unicast_packets",
+    ),
+    (
+        "switch_stats_tx_unicast_packets_total",
+        "switch_stats_tx_unicast_packets",
+    ),
+    ("switch_stats_tx_packets_total", "switch_stats_tx_packets"),
+    (
+        "switch_stats_tx_collisions_total",
+        "switch_stats_tx_collisions",
+    ),
+    ("switch_stats_tx_fcs_total", "switch_stats_tx_fcs"),
+    ("switch_stats_tx_bytes_total", "switch_stats_tx_bytes"),
+    (
+        "switch_stats_rx_jabber_packets_total",
+        "switch_stats_rx_jabber_packets",
+    ),
+    ("switch_stats_tx_single_total", "switch_stats_tx_single"),
+    (
+        "switch_stats_tx_excessive_total",
+        "switch_stats_tx_excessive",
+    ),
+    ("switch_stats_rx_pause_total", "switch_stats_rx_pause"),
+    (
+        "switch_stats_rx_multicast_packets_total",
+        "switch_stats_rx_multicast_packets",
+    ),
+    ("switch_stats_tx_pause_total", "switch_stats_tx_pause"),
+    (
+        "switch_stats_rx_good_packets_total",
+        "switch_stats_rx_good_packets",
+    ),
+    (
+        "switch_stats_rx_broadcast_packets_total",
+        "switch_stats_rx_broadcast_packets",
+    ),
+    ("switch_stats_tx_multiple_total", "switch_stats_tx_multiple"),
+    ("switch_stats_tx_deferred_total", "switch_stats_tx_deferred"),
+    ("switch_stats_tx_late_total", "switch_stats_tx_late"),
+    (
+        "switch_stats_tx_multicast_packets_total",
+        "switch_stats_tx_multicast_packets",
+    ),
+    (
+        "switch_stats_rx_fcs_packets_total",
+        "switch_stats_rx_fcs_packets",
+    ),
+    (
+        "switch_stats_tx_broadcast_packets_total",
+        "switch_stats_tx_broadcast_packets",
+    ),
+    (
+        "switch_stats_rx_err_packets_total",
+        "switch_stats_rx_err_packets",
+    ),
+    (
+        "switch_stats_rx_fragments_packets_total",
+        "switch_stats_rx_fragments_packets",
+    ),
+    (
+        "switch_stats_rx_bad_bytes_total",
+        "switch_stats_rx_bad_bytes",
+    ),
+    (
+        "switch_stats_rx_undersize_packets_total",
+        "switch_stats_rx_undersize_packets",
+    ),
+    ("wifi_station_rx_bytes_total", "wifi_station_rx_bytes"),
+    ("wifi_station_tx_bytes_total", "wifi_station_tx_bytes"),
+    ("wifi_station_rx_bytes_per_second", "wifi_station_rx_rate"),
+    ("wifi_station_tx_bytes_per_second", "wifi_station_tx_rate"),
+    ("wifi_station_signal_dbm", "wifi_station_signal"),
+    ("wifi_station_inactive_seconds", "wifi_station_inactive"),
+    (
+        "wifi_station_last_activity_timestamp_seconds",
+        "wifi_station_last_activity",
+    ),
+    (
+        "wifi_station_last_time_reachable_timestamp_seconds",
+        "wifi_station_last_time_reachable",
+    ),
+    (
+        "wifi_neighbors_access_point_signal_dbm",
+        "wifi_neighbors_access_point",
+    ),
+];
+
+/// Returns the legacy name of a metric, if it was renamed
+fn get_legacy_name(name: &str, prefix: &str) -> Option<String> {
+    let name = name.strip_prefix(&format!("{prefix}_"))?;
+
+    RENAMED_METRICS
+        .iter()
+        .find(|(current, _)| *current == name)
+        .map(|(_, legacy)| format!("{prefix}_{legacy}"))
+}
+
+/// Appends a gauge copy of each renamed metric under its legacy name,
+/// so dashboards built on the former names keep working
+pub fn append_legacy_families(families: Vec<MetricFamily>, prefix: &str) -> Vec<MetricFamily> {
+    let mut legacy_families = vec![];
+
+    for family in families.iter() {
+        let Some(legacy_name) = get_legacy_name(family.get_name(), prefix) else {
+            continue;
+        };
+
+        let mut legacy_family = MetricFamily::default();
+        legacy_family.set_name(legacy_name);
+        legacy_family.set_help(family.get_help().to_string());
+        legacy_family.set_field_type(MetricType::GAUGE);
+
+        for metric in family.get_metric().iter() {
+            let mut gauge = Gauge::default();
+            gauge.set_value(get_value(family, metric));
+
+            let mut legacy_metric = Metric::default();
+            legacy_metric.set_label(metric.get_label().to_vec().into());
+            legacy_metric.set_gauge(gauge);
+
+            legacy_family.mut_metric().push(legacy_metric);
+        }
+
+        legacy_families.push(legacy_family);
+    }
+
+    let mut families = families;
+    families.append(&mut legacy_families);
+    families.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+
+    families
+}
+
+#[cfg(test)]
+mod tests {
+    use prometheus_exporter::prometheus::{IntCounterVec, Opts, Registry};
+
+    use super::*;
+
+    #[test]
+    fn should_append_legacy_gauges() {
+        let registry = Registry::new();
+        let counter = IntCounterVec::new(
+            Opts::new("fbx_wifi_station_rx_bytes_total", "rx bytes"),
+            &["mac"],
+        )
+        .unwrap();
+        registry.register(Box::new(counter.clone())).unwrap();
+        counter.with_label_values(&["00:11:22:33:44:55"]).inc_by(42);
+
+        let families = append_legacy_families(registry.gather(), "fbx");
+
+        assert_eq!(2, families.len());
+
+        let legacy = families
+            .iter()
+            .find(|f| f.get_name() == "fbx_wifi_station_rx_bytes")
+            .unwrap();
+
+        assert_eq!(MetricType::GAUGE, legacy.get_field_type());
+        assert_eq!(42.0, legacy.get_metric()[0].get_gauge().get_value());
+        assert_eq!("mac", legacy.get_metric()[0].get_label()[0].get_name());
+    }
+
+    #[test]
+    fn should_ignore_metrics_with_another_prefix() {
+        assert!(get_legacy_name("other_system_uptime_seconds", "fbx").is_none());
+        assert_eq!(
+            Some("fbx_system_uptime_val".to_string()),
+            get_legacy_name("fbx_system_uptime_seconds", "fbx")
+        );
+    }
+}
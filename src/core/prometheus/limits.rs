@@ -0,0 +1,302 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+};
+
+use prometheus_exporter::prometheus::{
+    core::Collector,
+    proto::{LabelPair, Metric, MetricFamily},
+    register_int_gauge_vec, IntGaugeVec,
+};
+
+use crate::core::configuration::sections::{LimitsConfiguration, MetricLimitsConfiguration};
+
+const REASON_LABEL_COLLISION: &str = "label_collision";
+const REASON_MAX_SERIES: &str = "max_series";
+
+type Labels = Vec<(String, String)>;
+
+/// Cardinality guardrails applied to every family the metric maps register,
+/// before the metrics are exposed or pushed to the outputs
+pub struct Limits {
+    max_series: usize,
+    rules: HashMap<String, MetricLimitsConfiguration>,
+    dropped_series: IntGaugeVec,
+    /// Series let through the caps per metric, an admitted series keeps its
+    /// slot while it is collected and extra series are dropped until some
+    /// disappear, so the exposed series do not depend on the collection order
+    admitted: Mutex<HashMap<String, HashSet<Labels>>>,
+}
+
+impl Limits {
+    pub fn new(conf: &LimitsConfiguration, prefix: &str) -> Self {
+        let dropped_series = register_int_gauge_vec!(
+            format!("{prefix}_exporter_dropped_series"),
+            "Series currently dropped by the limits",
+            &["metric", "reason"]
+        )
+        .expect(&format!(
+            "cannot create {prefix}_exporter_dropped_series gauge"
+        ));
+
+        Self::with_dropped_series(conf, prefix, dropped_series)
+    }
+
+    fn with_dropped_series(
+        conf: &LimitsConfiguration,
+        prefix: &str,
+        dropped_series: IntGaugeVec,
+    ) -> Self {
+        let rules = conf
+            .metrics
+            .to_owned()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|rule| (format!("{prefix}_{}", rule.name), rule))
+            .collect();
+
+        Self {
+            max_series: conf.max_series.unwrap_or(0),
+            rules,
+            dropped_series,
+            admitted: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The dropped series family was gathered before the label rules ran,
+    /// it is replaced by its current value so it describes this collection
+    pub fn apply(&self, families: Vec<MetricFamily>) -> Vec<MetricFamily> {
+        let dropped_series_name = self.dropped_series.desc()[0].fq_name.to_owned();
+
+        let mut families: Vec<MetricFamily> = families
+            .into_iter()
+            .filter(|family| family.get_name() != dropped_series_name)
+            .map(|mut family| {
+                let name = family.get_name().to_string();
+                let rule = self.rules.get(&name);
+
+                if let Some(rule) = rule {
+                    if rule.drop_labels.is_some() || rule.keep_labels.is_some() {
+                        let collisions = filter_labels(&mut family, rule);
+
+                        self.dropped_series
+                            .with_label_values(&[&name, REASON_LABEL_COLLISION])
+                            .set(collisions as i64);
+                    }
+                }
+
+                let max_series = rule.and_then(|r| r.max_series).unwrap_or(self.max_series);
+
+                if max_series > 0 {
+                    let refused = self.cap_series(&mut family, max_series);
+                    self.set_refused(&name, refused);
+                }
+
+                family
+            })
+            .collect();
+
+        families.extend(
+            self.dropped_series
+                .collect()
+                .into_iter()
+                .filter(|family| !family.get_metric().is_empty()),
+        );
+
+        families
+    }
+
+    /// Keeps the admitted series still collected, admits new ones while the
+    /// cap allows it, returns how many were dropped
+    fn cap_series(&self, family: &mut MetricFamily, max_series: usize) -> usize {
+        let mut admitted = self.admitted.lock().unwrap();
+        let admitted = admitted.entry(family.get_name().to_string()).or_default();
+
+        let metrics = family.take_metric().into_vec();
+        let collected: HashSet<Labels> = metrics.iter().map(get_labels).collect();
+        admitted.retain(|labels| collected.contains(labels));
+
+        let mut refused = 0;
+
+        for metric in metrics {
+            let labels = get_labels(&metric);
+
+            if !admitted.contains(&labels) {
+                if admitted.len() >= max_series {
+                    refused += 1;
+                    continue;
+                }
+
+                admitted.insert(labels);
+            }
+
+            family.mut_metric().push(metric);
+        }
+
+        refused
+    }
+
+    fn set_refused(&self, name: &str, refused: usize) {
+        if refused > 0 {
+            self.dropped_series
+                .with_label_values(&[name, REASON_MAX_SERIES])
+                .set(refused as i64);
+        } else {
+            let _ = self
+                .dropped_series
+                .remove_label_values(&[name, REASON_MAX_SERIES]);
+        }
+    }
+}
+
+fn get_labels(metric: &Metric) -> Labels {
+    metric
+        .get_label()
+        .iter()
+        .map(|l| (l.get_name().to_string(), l.get_value().to_string()))
+        .collect()
+}
+
+fn is_label_kept(rule: &MetricLimitsConfiguration, name: &str) -> bool {
+    if let Some(keep_labels) = &rule.keep_labels {
+        if !keep_labels.iter().any(|l| l == name) {
+            return false;
+        }
+    }
+
+    if let Some(drop_labels) = &rule.drop_labels {
+        if drop_labels.iter().any(|l| l == name) {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Removes the dropped labels from each series, series which become
+/// identical to a previous one are dropped, returns how many were dropped
+fn filter_labels(family: &mut MetricFamily, rule: &MetricLimitsConfiguration) -> usize {
+    let mut seen = HashSet::new();
+    let mut collisions = 0;
+
+    let metrics = family.take_metric().into_vec();
+
+    for mut metric in metrics {
+        let labels: Vec<LabelPair> = metric
+            .take_label()
+            .into_iter()
+            .filter(|l| is_label_kept(rule, l.get_name()))
+            .collect();
+
+        let key: Labels = labels
+            .iter()
+            .map(|l| (l.get_name().to_string(), l.get_value().to_string()))
+            .collect();
+
+        if !seen.insert(key) {
+            collisions += 1;
+            continue;
+        }
+
+        metric.set_label(labels.into());
+        family.mut_metric().push(metric);
+    }
+
+    collisions
+}
+
+#[cfg(test)]
+mod tests {
+    use prometheus_exporter::prometheus::Opts;
+
+    use super::*;
+
+    fn get_families() -> Vec<MetricFamily> {
+        let vec = IntGaugeVec::new(
+            Opts::new("fbx_lan_browser_device_l3_connectivity", "test"),
+            &["name", "addr", "af"],
+        )
+        .unwrap();
+
+        vec.with_label_values(&["phone", "fe80::1", "ipv6"]).set(1);
+        vec.with_label_values(&["phone", "fe80::2", "ipv6"]).set(1);
+        vec.with_label_values(&["phone", "192.168.1.10", "ipv4"])
+            .set(1);
+        vec.with_label_values(&["laptop", "192.168.1.11", "ipv4"])
+            .set(1);
+
+        vec.collect()
+    }
+
+    fn get_dropped(dropped_series: &IntGaugeVec, reason: &str) -> i64 {
+        dropped_series
+            .with_label_values(&["fbx_lan_browser_device_l3_connectivity", reason])
+            .get()
+    }
+
+    #[test]
+    fn should_drop_labels_and_merge_series() {
+        let dropped_series =
+            IntGaugeVec::new(Opts::new("test_dropped", "test"), &["metric", "reason"]).unwrap();
+        let conf = LimitsConfiguration {
+            max_series: None,
+            metrics: Some(vec![MetricLimitsConfiguration {
+                name: "lan_browser_device_l3_connectivity".to_string(),
+                max_series: None,
+                drop_labels: Some(vec!["addr".to_string()]),
+                keep_labels: None,
+            }]),
+        };
+        let limits = Limits::with_dropped_series(&conf, "fbx", dropped_series.clone());
+
+        let families = limits.apply(get_families());
+        let metrics = families[0].get_metric();
+
+        assert_eq!(3, metrics.len());
+        assert!(metrics
+            .iter()
+            .all(|m| m.get_label().iter().all(|l| l.get_name() != "addr")));
+        assert_eq!(1, get_dropped(&dropped_series, REASON_LABEL_COLLISION));
+
+        // the count of this collection is exposed right away
+        assert_eq!("test_dropped", families[1].get_name());
+        assert_eq!(1, families[1].get_metric().len());
+    }
+
+    #[test]
+    fn should_keep_admitted_series_beyond_cap() {
+        let dropped_series =
+            IntGaugeVec::new(Opts::new("test_dropped", "test"), &["metric", "reason"]).unwrap();
+        let conf = LimitsConfiguration {
+            max_series: Some(2),
+            metrics: None,
+        };
+        let limits = Limits::with_dropped_series(&conf, "fbx", dropped_series.clone());
+
+        let get_names = |families: &[MetricFamily]| -> Vec<String> {
+            families[0]
+                .get_metric()
+                .iter()
+                .map(|m| m.get_label()[1].get_value().to_string())
+                .collect()
+        };
+
+        let families = limits.apply(get_families());
+        let admitted = get_names(&families);
+
+        assert_eq!(2, admitted.len());
+        assert_eq!(2, get_dropped(&dropped_series, REASON_MAX_SERIES));
+
+        // the admitted series keep their slot whatever the collection order
+        let mut reversed = get_families();
+        let metrics: Vec<Metric> = reversed[0].take_metric().into_iter().rev().collect();
+        reversed[0].set_metric(metrics.into());
+
+        let mut names = get_names(&limits.apply(reversed));
+        names.sort();
+        let mut expected = admitted.clone();
+        expected.sort();
+
+        assert_eq!(expected, names);
+    }
+}
@@ -0,0 +1,122 @@
+use prometheus_exporter::prometheus::proto::{MetricFamily, MetricType};
+
+use crate::core::outputs::get_value;
+
+pub const OPENMETRICS_CONTENT_TYPE: &str =
+    "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Units announced with `# UNIT`, a family name must end with its unit
+const UNITS: [&str; 6] = [
+    "bytes_per_second",
+    "bits_per_second",
+    "bytes",
+    "celsius",
+    "seconds",
+    "dbm",
+];
+
+/// Encodes the families using the OpenMetrics text format,
+/// c.f. https://github.com/OpenObservability/OpenMetrics/blob/main/specification/OpenMetrics.md
+pub fn encode(families: &[MetricFamily]) -> String {
+    let mut out = String::new();
+
+    for family in families.iter() {
+        if family.get_metric().is_empty() {
+            continue;
+        }
+
+        let (name, kind, suffix) = match family.get_field_type() {
+            MetricType::COUNTER => (
+                family
+                    .get_name()
+                    .strip_suffix("_total")
+                    .unwrap_or(family.get_name()),
+                "counter",
+                "_total",
+            ),
+            MetricType::GAUGE => (family.get_name(), "gauge", ""),
+            _ => (family.get_name(), "unknown", ""),
+        };
+
+        out.push_str(&format!("# TYPE {name} {kind}\n"));
+
+        if let Some(unit) = UNITS.iter().find(|u| name.ends_with(&format!("_{u}"))) {
+            out.push_str(&format!("# UNIT {name} {unit}\n"));
+        }
+
+        out.push_str(&format!("# HELP {name} {}\n", escape(family.get_help())));
+
+        for metric in family.get_metric().iter() {
+            out.push_str(name);
+            out.push_str(suffix);
+
+            let labels = metric
+                .get_label()
+                .iter()
+                .map(|l| format!("{}=\"{}\"", l.get_name(), escape(l.get_value())))
+                .collect::<Vec<String>>();
+
+            if !labels.is_empty() {
+                out.push_str(&format!("{{{}}}", labels.join(",")));
+            }
+
+            out.push_str(&format!(" {}\n", format_value(get_value(family, metric))));
+        }
+    }
+
+    out.push_str("# EOF\n");
+
+    out
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prometheus_exporter::prometheus::{IntCounter, IntGaugeVec, Opts, Registry};
+
+    use super::*;
+
+    #[test]
+    fn should_encode_openmetrics() {
+        let registry = Registry::new();
+        let counter = IntCounter::new("fbx_connection_down_bytes_total", "Total bytes").unwrap();
+        let gauge = IntGaugeVec::new(
+            Opts::new("fbx_system_temp_cpu_celsius", "CPU \"core\" temperature"),
+            &["core"],
+        )
+        .unwrap();
+        registry.register(Box::new(counter.clone())).unwrap();
+        registry.register(Box::new(gauge.clone())).unwrap();
+        counter.inc_by(1024);
+        gauge.with_label_values(&["0"]).set(42);
+
+        let expected = "# TYPE fbx_connection_down_bytes counter
+# UNIT fbx_connection_down_bytes bytes
+# HELP fbx_connection_down_bytes Total bytes
+fbx_connection_down_bytes_total 1024
+# TYPE fbx_system_temp_cpu_celsius gauge
+# UNIT fbx_system_temp_cpu_celsius celsius
+# HELP fbx_system_temp_cpu_celsius CPU \\\"core\\\" temperature
+fbx_system_temp_cpu_celsius{core=\"0\"} 42
+# EOF
+";
+
+        assert_eq!(expected, encode(&registry.gather()));
+    }
+}
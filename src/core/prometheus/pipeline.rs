@@ -0,0 +1,113 @@
+use prometheus_exporter::prometheus::{self, proto::MetricFamily};
+
+use super::{inventory::Inventory, legacy, limits::Limits, relabel::Relabeler};
+
+/// Transformations applied to the registered metrics before they are
+/// exposed on `/metrics` or pushed to the outputs
+pub struct Pipeline {
+    prefix: String,
+    legacy_metrics: bool,
+    inventory: Option<Inventory>,
+    limits: Option<Limits>,
+    relabeler: Option<Relabeler>,
+}
+
+impl Pipeline {
+    pub fn new(
+        prefix: String,
+        legacy_metrics: bool,
+        inventory: Option<Inventory>,
+        limits: Option<Limits>,
+        relabeler: Option<Relabeler>,
+    ) -> Self {
+        Self {
+            prefix,
+            legacy_metrics,
+            inventory,
+            limits,
+            relabeler,
+        }
+    }
+
+    /// Gathers the registered metrics and runs them through the pipeline
+    pub fn gather(&self) -> Vec<MetricFamily> {
+        self.process(prometheus::gather())
+    }
+
+    /// Families exposed on `/metrics`, the legacy copies are derived from the
+    /// processed families so they follow the limits and relabel rules of the
+    /// metric they copy, the outputs never receive them
+    pub fn expose(&self, families: Vec<MetricFamily>) -> Vec<MetricFamily> {
+        if self.legacy_metrics {
+            legacy::append_legacy_families(families, &self.prefix)
+        } else {
+            families
+        }
+    }
+
+    /// Device labels are added first so the limits and relabel rules can use them,
+    /// limits are applied on the original metric names, relabel rules run last
+    pub fn process(&self, families: Vec<MetricFamily>) -> Vec<MetricFamily> {
+        let families = match &self.inventory {
+            Some(inventory) => inventory.apply(families),
+            None => families,
+        };
+
+        let families = match &self.limits {
+            Some(limits) => limits.apply(families),
+            None => families,
+        };
+
+        match &self.relabeler {
+            Some(relabeler) => relabeler.apply(families),
+            None => families,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prometheus_exporter::prometheus::{IntCounterVec, Opts, Registry};
+
+    use super::*;
+    use crate::core::configuration::sections::RelabelConfiguration;
+
+    #[test]
+    fn should_derive_legacy_copies_from_processed_families() {
+        let registry = Registry::new();
+        let rx_bytes = IntCounterVec::new(
+            Opts::new("fbx_wifi_station_rx_bytes_total", "rx bytes"),
+            &["mac", "hostname"],
+        )
+        .unwrap();
+        registry.register(Box::new(rx_bytes.clone())).unwrap();
+        rx_bytes.with_label_values(&["00:01", "phone"]).inc_by(42);
+
+        let relabeler = Relabeler::new(&[RelabelConfiguration {
+            source_labels: None,
+            separator: None,
+            regex: Some("hostname".to_string()),
+            target_label: None,
+            replacement: None,
+            action: Some("labeldrop".to_string()),
+        }])
+        .unwrap();
+        let pipeline = Pipeline::new("fbx".to_string(), true, None, None, Some(relabeler));
+
+        let families = pipeline.expose(pipeline.process(registry.gather()));
+        let legacy = families
+            .iter()
+            .find(|f| f.get_name() == "fbx_wifi_station_rx_bytes")
+            .unwrap();
+
+        assert_eq!(2, families.len());
+        assert_eq!(1, legacy.get_metric()[0].get_label().len());
+        assert_eq!("mac", legacy.get_metric()[0].get_label()[0].get_name());
+
+        let pipeline = Pipeline::new("fbx".to_string(), false, None, None, None);
+        assert_eq!(
+            1,
+            pipeline.expose(pipeline.process(registry.gather())).len()
+        );
+    }
+}
@@ -0,0 +1,308 @@
+use std::collections::{HashMap, HashSet};
+
+use prometheus_exporter::prometheus::proto::{LabelPair, Metric, MetricFamily};
+use regex::Regex;
+
+use crate::core::configuration::sections::RelabelConfiguration;
+
+const METRIC_NAME_LABEL: &str = "__name__";
+const DEFAULT_SEPARATOR: &str = ";";
+const DEFAULT_REGEX: &str = "(.*)";
+const DEFAULT_REPLACEMENT: &str = "$1";
+
+#[derive(Debug, PartialEq)]
+enum RelabelAction {
+    Replace,
+    Keep,
+    Drop,
+    LabelDrop,
+}
+
+/// A single relabel rule, following the semantic of Prometheus `relabel_configs`.
+/// The metric name is available as the `__name__` label
+struct RelabelRule {
+    action: RelabelAction,
+    source_labels: Vec<String>,
+    separator: String,
+    regex: Regex,
+    target_label: Option<String>,
+    replacement: String,
+}
+
+impl RelabelRule {
+    fn new(conf: &RelabelConfiguration) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let action = match conf.action.as_deref().unwrap_or("replace") {
+            "replace" => RelabelAction::Replace,
+            "keep" => RelabelAction::Keep,
+            "drop" => RelabelAction::Drop,
+            "labeldrop" => RelabelAction::LabelDrop,
+            other => return Err(format!("unknown relabel action {other}").into()),
+        };
+
+        if action == RelabelAction::Replace && conf.target_label.is_none() {
+            return Err("relabel replace action requires a target_label".into());
+        }
+
+        // regexes are fully anchored, like in Prometheus
+        let regex = Regex::new(&format!(
+            "^(?:{})$",
+            conf.regex.as_deref().unwrap_or(DEFAULT_REGEX)
+        ))?;
+
+        Ok(Self {
+            action,
+            source_labels: conf.source_labels.to_owned().unwrap_or_default(),
+            separator: conf
+                .separator
+                .to_owned()
+                .unwrap_or(DEFAULT_SEPARATOR.to_string()),
+            regex,
+            target_label: conf.target_label.to_owned(),
+            replacement: conf
+                .replacement
+                .to_owned()
+                .unwrap_or(DEFAULT_REPLACEMENT.to_string()),
+        })
+    }
+
+    /// Applies the rule to the labels of a series, returns false when the series is dropped
+    fn apply(&self, labels: &mut HashMap<String, String>) -> bool {
+        let value = self
+            .source_labels
+            .iter()
+            .map(|l| labels.get(l).map(|v| v.as_str()).unwrap_or_default())
+            .collect::<Vec<&str>>()
+            .join(self.separator.as_str());
+
+        match self.action {
+            RelabelAction::Keep => self.regex.is_match(&value),
+            RelabelAction::Drop => !self.regex.is_match(&value),
+            RelabelAction::LabelDrop => {
+                labels.retain(|name, _| name == METRIC_NAME_LABEL || !self.regex.is_match(name));
+                true
+            }
+            RelabelAction::Replace => {
+                let Some(captures) = self.regex.captures(&value) else {
+                    return true;
+                };
+
+                let mut target = String::new();
+                captures.expand(&self.replacement, &mut target);
+
+                let target_label = self.target_label.to_owned().unwrap_or_default();
+
+                if target.is_empty() {
+                    labels.remove(&target_label);
+                } else {
+                    labels.insert(target_label, target);
+                }
+
+                true
+            }
+        }
+    }
+}
+
+/// Relabel rules from the configuration, applied in order to every series
+pub struct Relabeler {
+    rules: Vec<RelabelRule>,
+}
+
+impl Relabeler {
+    pub fn new(
+        confs: &[RelabelConfiguration],
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let rules = confs
+            .iter()
+            .map(RelabelRule::new)
+            .collect::<Result<Vec<RelabelRule>, _>>()?;
+
+        Ok(Self { rules })
+    }
+
+    fn relabel(&self, name: &str, metric: &Metric) -> Option<(String, Vec<LabelPair>)> {
+        let mut labels: HashMap<String, String> = metric
+            .get_label()
+            .iter()
+            .map(|l| (l.get_name().to_string(), l.get_value().to_string()))
+            .collect();
+
+        labels.insert(METRIC_NAME_LABEL.to_string(), name.to_string());
+
+        for rule in self.rules.iter() {
+            if !rule.apply(&mut labels) {
+                return None;
+            }
+        }
+
+        let name = labels.remove(METRIC_NAME_LABEL).unwrap_or_default();
+
+        if name.is_empty() {
+            return None;
+        }
+
+        let mut labels: Vec<LabelPair> = labels
+            .into_iter()
+            .map(|(label, value)| {
+                let mut pair = LabelPair::default();
+                pair.set_name(label);
+                pair.set_value(value);
+                pair
+            })
+            .collect();
+
+        labels.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+
+        Some((name, labels))
+    }
+
+    /// Relabels every series, series renamed to the same metric are merged
+    /// in a single family and duplicated label sets are only kept once
+    pub fn apply(&self, families: Vec<MetricFamily>) -> Vec<MetricFamily> {
+        if self.rules.is_empty() {
+            return families;
+        }
+
+        let mut relabeled: Vec<MetricFamily> = vec![];
+        let mut indexes: HashMap<String, usize> = HashMap::new();
+        let mut seen: HashSet<(String, Vec<(String, String)>)> = HashSet::new();
+
+        for family in families.iter() {
+            for metric in family.get_metric().iter() {
+                let Some((name, labels)) = self.relabel(family.get_name(), metric) else {
+                    continue;
+                };
+
+                let key = labels
+                    .iter()
+                    .map(|l| (l.get_name().to_string(), l.get_value().to_string()))
+                    .collect();
+
+                if !seen.insert((name.to_owned(), key)) {
+                    continue;
+                }
+
+                let index = *indexes.entry(name.to_owned()).or_insert_with(|| {
+                    let mut target = MetricFamily::default();
+                    target.set_name(name.to_owned());
+                    target.set_help(family.get_help().to_string());
+                    target.set_field_type(family.get_field_type());
+                    relabeled.push(target);
+                    relabeled.len() - 1
+                });
+
+                let mut metric = metric.clone();
+                metric.set_label(labels.into());
+
+                relabeled[index].mut_metric().push(metric);
+            }
+        }
+
+        relabeled.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+
+        relabeled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prometheus_exporter::prometheus::{core::Collector, IntGaugeVec, Opts};
+
+    use super::*;
+
+    fn rule(
+        action: &str,
+        source_labels: &[&str],
+        regex: Option<&str>,
+        target_label: Option<&str>,
+        replacement: Option<&str>,
+    ) -> RelabelConfiguration {
+        RelabelConfiguration {
+            action: Some(action.to_string()),
+            source_labels: Some(source_labels.iter().map(|l| l.to_string()).collect()),
+            separator: None,
+            regex: regex.map(|r| r.to_string()),
+            target_label: target_label.map(|t| t.to_string()),
+            replacement: replacement.map(|r| r.to_string()),
+        }
+    }
+
+    fn get_families() -> Vec<MetricFamily> {
+        let vec = IntGaugeVec::new(
+            Opts::new("fbx_wifi_station_signal_dbm", "test"),
+            &["primary_name", "band"],
+        )
+        .unwrap();
+
+        vec.with_label_values(&["android-3f2a", "2d4g"]).set(-60);
+        vec.with_label_values(&["laptop", "5g"]).set(-50);
+        vec.with_label_values(&["printer", "2d4g"]).set(-70);
+
+        vec.collect()
+    }
+
+    fn get_label<'a>(metric: &'a Metric, name: &str) -> &'a str {
+        metric
+            .get_label()
+            .iter()
+            .find(|l| l.get_name() == name)
+            .map(|l| l.get_value())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn should_rename_rewrite_and_add_labels() {
+        let relabeler = Relabeler::new(&[
+            rule(
+                "replace",
+                &["__name__"],
+                Some("fbx_wifi_(.*)"),
+                Some("__name__"),
+                Some("home_wifi_$1"),
+            ),
+            rule(
+                "replace",
+                &["primary_name"],
+                Some("android-.*"),
+                Some("primary_name"),
+                Some("android"),
+            ),
+            rule("replace", &[], None, Some("site"), Some("paris")),
+        ])
+        .unwrap();
+
+        let families = relabeler.apply(get_families());
+
+        assert_eq!(1, families.len());
+        assert_eq!("home_wifi_station_signal_dbm", families[0].get_name());
+
+        let metrics = families[0].get_metric();
+        assert_eq!(3, metrics.len());
+        assert!(metrics.iter().all(|m| get_label(m, "site") == "paris"));
+        assert!(metrics
+            .iter()
+            .any(|m| get_label(m, "primary_name") == "android"));
+    }
+
+    #[test]
+    fn should_drop_series() {
+        let relabeler = Relabeler::new(&[
+            rule("drop", &["primary_name"], Some("printer"), None, None),
+            rule("labeldrop", &[], Some("band"), None, None),
+        ])
+        .unwrap();
+
+        let families = relabeler.apply(get_families());
+        let metrics = families[0].get_metric();
+
+        assert_eq!(2, metrics.len());
+        assert!(metrics.iter().all(|m| get_label(m, "band").is_empty()));
+    }
+
+    #[test]
+    fn should_reject_invalid_rules() {
+        assert!(Relabeler::new(&[rule("replace", &["band"], None, None, None)]).is_err());
+        assert!(Relabeler::new(&[rule("hashmod", &["band"], None, None, None)]).is_err());
+        assert!(Relabeler::new(&[rule("drop", &["band"], Some("("), None, None)]).is_err());
+    }
+}
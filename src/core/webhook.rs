@@ -0,0 +1,65 @@
+use std::{collections::HashMap, time::Duration};
+
+use log::debug;
+use reqwest::Client;
+use serde::Serialize;
+
+pub const DEFAULT_WEBHOOK_TIMEOUT: u64 = 10;
+
+/// Posts JSON payloads to a configured URL
+pub struct Webhook {
+    client: Client,
+    url: String,
+    headers: HashMap<String, String>,
+}
+
+impl Webhook {
+    pub fn new(url: String, timeout: Option<u64>) -> Self {
+        let client = reqwest::ClientBuilder::new()
+            .timeout(Duration::from_secs(
+                timeout.unwrap_or(DEFAULT_WEBHOOK_TIMEOUT),
+            ))
+            .build()
+            .expect("cannot create HTTP Client");
+
+        Self {
+            client,
+            url,
+            headers: HashMap::new(),
+        }
+    }
+
+    /// Adds headers sent with every request, e.g. an authentication token
+    pub fn with_headers(mut self, headers: HashMap<String, String>) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    pub async fn post<T: Serialize + ?Sized>(
+        &self,
+        payload: &T,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        debug!("posting webhook to {}", self.url);
+
+        let mut request = self.client.post(&self.url).json(payload);
+
+        for (name, value) in self.headers.iter() {
+            request = request.header(name, value);
+        }
+
+        let res = request.send().await?;
+
+        if !res.status().is_success() {
+            return Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!(
+                    "webhook responded with status {}: {}",
+                    res.status(),
+                    res.text().await.unwrap_or_default()
+                ),
+            )));
+        }
+
+        Ok(())
+    }
+}
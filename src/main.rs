@@ -3,6 +3,7 @@ use core::{
     configuration::get_configuration,
     core::{auto_register_and_serve, register, serve, session_diagnostic},
     logger::CustomLogFilter,
+    outputs::influx::INFLUX_STDOUT_TARGET,
 };
 
 use clap::Parser;
@@ -25,6 +26,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
 
     let conf = get_configuration(conf_path.to_string()).await?;
 
+    // line protocol written to stdout must not be interleaved with log lines
+    let duplicate = match conf.influx.as_ref() {
+        Some(influx)
+            if influx.enabled.unwrap_or(false)
+                && influx.target.as_deref() == Some(INFLUX_STDOUT_TARGET) =>
+        {
+            flexi_logger::Duplicate::None
+        }
+        _ => flexi_logger::Duplicate::Debug,
+    };
+
     let specs = FileSpec::default().directory(conf.core.data_directory.clone().unwrap());
 
     let logger = flexi_logger::Logger::try_with_env_or_str(
@@ -45,7 +57,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     .filter(Box::new(CustomLogFilter))
     .log_to_file(specs)
     .write_mode(flexi_logger::WriteMode::BufferAndFlush)
-    .duplicate_to_stdout(flexi_logger::Duplicate::Debug)
+    .duplicate_to_stdout(duplicate)
     .set_palette("b1;3;2;4;6".to_string())
     .cleanup_in_background_thread(true)
     .rotate(
@@ -0,0 +1,516 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use log::{info, warn};
+use prometheus_exporter::prometheus::{
+    register_int_counter_vec, register_int_gauge, IntCounterVec, IntGauge,
+};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+
+use crate::core::common::{
+    http_client_factory::AuthenticatedHttpClientFactory, permission::PermissionWarning,
+};
+
+use super::{client::MapClient, counters::set_counter, MetricMap};
+
+const STATE_FILE: &str = "calls.json";
+/// Per install secret keying the phone number hashes
+const SECRET_FILE: &str = "calls.key";
+const SECRET_LENGTH: usize = 32;
+pub const CALLS_PERMISSION: &str = "calls";
+/// Hashed phone numbers are truncated, enough to tell callers apart
+const HASHED_NUMBER_LENGTH: usize = 12;
+
+type HmacSha1 = Hmac<Sha1>;
+/// Numbers kept per direction, the least recent ones are merged into `OTHER_NUMBERS`
+const MAX_NUMBERS: usize = 100;
+const OTHER_NUMBERS: &str = "other";
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct CallEntry {
+    id: Option<i64>,
+    #[serde(rename = "type")]
+    _type: Option<String>,
+    number: Option<String>,
+    datetime: Option<i64>,
+    duration: Option<i64>,
+}
+
+impl CallEntry {
+    /// Call direction as exported, the API names answered incoming calls "accepted"
+    fn get_direction(&self) -> &str {
+        match self._type.as_deref() {
+            Some("accepted") => "incoming",
+            Some("outgoing") => "outgoing",
+            Some("missed") => "missed",
+            _ => "unknown",
+        }
+    }
+}
+
+/// How phone numbers are exposed in the `number` label
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NumberPolicy {
+    Label,
+    Hash,
+    Drop,
+}
+
+impl NumberPolicy {
+    pub fn from_conf(value: Option<&str>) -> Self {
+        match value.unwrap_or("hash") {
+            "label" => NumberPolicy::Label,
+            "hash" => NumberPolicy::Hash,
+            "drop" => NumberPolicy::Drop,
+            other => {
+                warn!("unknown phone_numbers policy \"{other}\", phone numbers are dropped");
+                NumberPolicy::Drop
+            }
+        }
+    }
+
+    /// Phone numbers are few enough to be enumerated, they are hashed with a
+    /// secret kept on this install so the label cannot be reversed without it
+    fn apply(&self, number: &str, secret: &[u8]) -> String {
+        match self {
+            NumberPolicy::Label => number.to_string(),
+            NumberPolicy::Hash if number.is_empty() => String::new(),
+            NumberPolicy::Hash => {
+                let mut mac =
+                    HmacSha1::new_from_slice(secret).expect("hmac accepts keys of any length");
+                mac.update(number.as_bytes());
+
+                let mut hash = to_hex(&mac.finalize().into_bytes());
+                hash.truncate(HASHED_NUMBER_LENGTH);
+                hash
+            }
+            NumberPolicy::Drop => String::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+struct CallTotal {
+    calls: i64,
+    duration: i64,
+    /// Last call datetime, the least recent numbers are merged first
+    #[serde(default)]
+    last_call: i64,
+}
+
+/// Highest call id already counted and the resulting totals, persisted under
+/// the data directory so a restart does not count the call log again
+#[derive(Serialize, Deserialize, Default, Debug)]
+struct CallState {
+    last_id: Option<i64>,
+    /// Totals per direction then per number label
+    totals: BTreeMap<String, BTreeMap<String, CallTotal>>,
+    last_missed: Option<i64>,
+}
+
+/// What an update did to the call state
+#[derive(Default, Debug)]
+struct CallUpdate {
+    changed: bool,
+    /// (direction, number) totals merged into the other numbers
+    merged: Vec<(String, String)>,
+}
+
+impl CallState {
+    /// Counts the calls above the highest id counted so far. Call ids grow on
+    /// the box, a log whose ids are all lower was cleared or the box was reset,
+    /// its calls are counted from scratch. A truncated or empty log changes nothing
+    fn update(&mut self, calls: &[CallEntry], numbers: NumberPolicy, secret: &[u8]) -> CallUpdate {
+        let mut update = CallUpdate::default();
+
+        let Some(max_id) = calls.iter().filter_map(|c| c.id).max() else {
+            return update;
+        };
+
+        let last_id = match self.last_id {
+            Some(last_id) if max_id < last_id => {
+                info!("call log ids restarted at {max_id}, counting the new log");
+                None
+            }
+            last_id => last_id,
+        };
+
+        for call in calls.iter() {
+            let Some(id) = call.id else {
+                continue;
+            };
+
+            if last_id.is_some_and(|last_id| id <= last_id) {
+                continue;
+            }
+
+            let direction = call.get_direction();
+            let number = numbers.apply(call.number.as_deref().unwrap_or_default(), secret);
+            let total = self
+                .totals
+                .entry(direction.to_string())
+                .or_default()
+                .entry(number)
+                .or_default();
+
+            total.calls += 1;
+            total.duration += call.duration.unwrap_or_default();
+            total.last_call = total.last_call.max(call.datetime.unwrap_or_default());
+
+            if direction == "missed" {
+                self.last_missed = self.last_missed.max(call.datetime);
+            }
+
+            update.changed = true;
+        }
+
+        if self.last_id != Some(max_id) {
+            self.last_id = Some(max_id);
+            update.changed = true;
+        }
+
+        for (direction, totals) in self.totals.iter_mut() {
+            for number in merge_least_recent(totals) {
+                update.merged.push((direction.to_owned(), number));
+            }
+        }
+
+        update
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn from_hex(value: &str) -> Option<Vec<u8>> {
+    if value.len() % 2 == 1 {
+        return None;
+    }
+
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Merges the least recent numbers into the other numbers total until at most
+/// `MAX_NUMBERS` are left, so the totals of a direction stay complete
+fn merge_least_recent(totals: &mut BTreeMap<String, CallTotal>) -> Vec<String> {
+    let mut merged = vec![];
+
+    while totals.len() > MAX_NUMBERS {
+        let Some(number) = totals
+            .iter()
+            .filter(|(number, _)| *number != OTHER_NUMBERS)
+            .min_by_key(|(_, total)| total.last_call)
+            .map(|(number, _)| number.to_owned())
+        else {
+            break;
+        };
+
+        let total = totals.remove(&number).unwrap_or_default();
+        let other = totals.entry(OTHER_NUMBERS.to_string()).or_default();
+
+        other.calls += total.calls;
+        other.duration += total.duration;
+        other.last_call = other.last_call.max(total.last_call);
+
+        merged.push(number);
+    }
+
+    merged
+}
+
+pub struct CallsMetricMap<'a> {
+    client: MapClient<'a>,
+    path: PathBuf,
+    secret_path: PathBuf,
+    numbers: NumberPolicy,
+    secret: Option<Vec<u8>>,
+    state: Option<CallState>,
+    permission_warning: PermissionWarning,
+    calls_counter: IntCounterVec,
+    duration_counter: IntCounterVec,
+    last_missed_gauge: IntGauge,
+}
+
+impl<'a> CallsMetricMap<'a> {
+    pub fn new(
+        factory: &'a AuthenticatedHttpClientFactory<'a>,
+        prefix: String,
+        data_directory: &str,
+        numbers: NumberPolicy,
+    ) -> Self {
+        let prfx = format!("{prefix}_calls");
+
+        Self {
+            client: MapClient::with_permission(factory, CALLS_PERMISSION),
+            path: PathBuf::from(data_directory).join(STATE_FILE),
+            secret_path: PathBuf::from(data_directory).join(SECRET_FILE),
+            numbers,
+            secret: None,
+            state: None,
+            permission_warning: PermissionWarning::default(),
+            calls_counter: register_int_counter_vec!(
+                format!("{prfx}_total"),
+                "Phone calls per direction (incoming, outgoing, missed)",
+                &["type", "number"]
+            )
+            .expect(&format!("cannot create {prfx}_total counter")),
+            duration_counter: register_int_counter_vec!(
+                format!("{prfx}_duration_seconds_total"),
+                "Phone calls duration in seconds",
+                &["type", "number"]
+            )
+            .expect(&format!(
+                "cannot create {prfx}_duration_seconds_total counter"
+            )),
+            last_missed_gauge: register_int_gauge!(
+                format!("{prfx}_last_missed_timestamp_seconds"),
+                "Last missed call as a unix timestamp, 0 when unknown"
+            )
+            .expect(&format!(
+                "cannot create {prfx}_last_missed_timestamp_seconds gauge"
+            )),
+        }
+    }
+
+    async fn load(&self) -> CallState {
+        let content = match tokio::fs::read_to_string(&self.path).await {
+            Ok(r) => r,
+            Err(_) => {
+                info!(
+                    "no call state found at {}, counting the whole call log",
+                    self.path.display()
+                );
+                return CallState::default();
+            }
+        };
+
+        serde_json::from_str::<CallState>(&content).unwrap_or_else(|e| {
+            warn!(
+                "cannot read call state {}, starting over: {}",
+                self.path.display(),
+                e
+            );
+            CallState::default()
+        })
+    }
+
+    /// Reads the hashing secret, a new one is generated and saved on first use,
+    /// losing it only changes the hashes of the numbers counted afterwards
+    async fn load_secret(&self) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        if let Ok(content) = tokio::fs::read_to_string(&self.secret_path).await {
+            match from_hex(content.trim()) {
+                Some(secret) if !secret.is_empty() => return Ok(secret),
+                _ => warn!(
+                    "cannot read phone number secret {}, generating a new one",
+                    self.secret_path.display()
+                ),
+            }
+        }
+
+        let mut secret = vec![0; SECRET_LENGTH];
+        openssl::rand::rand_bytes(&mut secret)?;
+        tokio::fs::write(&self.secret_path, to_hex(&secret)).await?;
+
+        info!(
+            "phone number secret saved to {}",
+            self.secret_path.display()
+        );
+
+        Ok(secret)
+    }
+
+    async fn save(
+        &self,
+        state: &CallState,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let content = serde_json::to_string_pretty(state)?;
+        tokio::fs::write(&self.path, content).await?;
+
+        Ok(())
+    }
+
+    fn set_metrics(&self, state: &CallState, merged: &[(String, String)]) {
+        for (direction, number) in merged.iter() {
+            let _ = self.calls_counter.remove_label_values(&[direction, number]);
+            let _ = self
+                .duration_counter
+                .remove_label_values(&[direction, number]);
+        }
+
+        for (direction, numbers) in state.totals.iter() {
+            for (number, total) in numbers.iter() {
+                set_counter(
+                    &self.calls_counter.with_label_values(&[direction, number]),
+                    total.calls,
+                );
+                set_counter(
+                    &self
+                        .duration_counter
+                        .with_label_values(&[direction, number]),
+                    total.duration,
+                );
+            }
+        }
+
+        self.last_missed_gauge
+            .set(state.last_missed.unwrap_or_default());
+    }
+
+    async fn set_all(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let calls: Vec<CallEntry> = self.client.get("v4/call/log/").await?;
+
+        let secret = match self.secret.take() {
+            Some(secret) => secret,
+            None if self.numbers == NumberPolicy::Hash => self.load_secret().await?,
+            None => vec![],
+        };
+
+        let mut state = match self.state.take() {
+            Some(state) => state,
+            None => self.load().await,
+        };
+
+        let update = state.update(&calls, self.numbers, &secret);
+        self.secret = Some(secret);
+        self.set_metrics(&state, &update.merged);
+
+        let res = if update.changed {
+            self.save(&state).await
+        } else {
+            Ok(())
+        };
+        self.state = Some(state);
+
+        res
+    }
+}
+
+#[async_trait]
+impl<'a> MetricMap<'a> for CallsMetricMap<'a> {
+    async fn init(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+    }
+
+    async fn set(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let res = self.set_all().await;
+
+        self.permission_warning.check("calls", res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mappers::api_specs_provider::get_result;
+
+    async fn get_calls() -> Vec<CallEntry> {
+        get_result("calls", "api_v4_call_log-get").await
+    }
+
+    #[tokio::test]
+    async fn should_count_each_call_once() {
+        let calls = get_calls().await;
+        let mut state = CallState::default();
+
+        assert!(state.update(&calls, NumberPolicy::Label, &[]).changed);
+        assert!(!state.update(&calls, NumberPolicy::Label, &[]).changed);
+
+        let missed = state
+            .totals
+            .get("missed")
+            .unwrap()
+            .get("0612345678")
+            .unwrap();
+        assert_eq!(2, missed.calls);
+        assert_eq!(Some(1760790120), state.last_missed);
+
+        let incoming = state
+            .totals
+            .get("incoming")
+            .unwrap()
+            .get("0140000000")
+            .unwrap();
+        assert_eq!(184, incoming.duration);
+
+        // a truncated or empty log does not count anything again
+        assert!(!state.update(&calls[..3], NumberPolicy::Label, &[]).changed);
+        assert!(!state.update(&[], NumberPolicy::Label, &[]).changed);
+        assert_eq!(Some(42), state.last_id);
+
+        // ids restarting below the highest one counted start a new log
+        assert!(state.update(&calls[3..], NumberPolicy::Label, &[]).changed);
+        assert_eq!(Some(39), state.last_id);
+        assert_eq!(
+            3,
+            state
+                .totals
+                .get("missed")
+                .unwrap()
+                .get("0612345678")
+                .unwrap()
+                .calls
+        );
+    }
+
+    #[test]
+    fn should_merge_least_recent_numbers() {
+        let mut totals: BTreeMap<String, CallTotal> = (0..=MAX_NUMBERS as i64)
+            .map(|i| {
+                (
+                    format!("06{i:08}"),
+                    CallTotal {
+                        calls: 1,
+                        duration: 10,
+                        last_call: 1000 + i,
+                    },
+                )
+            })
+            .collect();
+
+        let merged = merge_least_recent(&mut totals);
+
+        assert_eq!(
+            vec!["0600000000".to_string(), "0600000001".to_string()],
+            merged
+        );
+        assert_eq!(MAX_NUMBERS, totals.len());
+        assert_eq!(2, totals.get(OTHER_NUMBERS).unwrap().calls);
+        assert_eq!(20, totals.get(OTHER_NUMBERS).unwrap().duration);
+    }
+
+    #[tokio::test]
+    async fn should_hide_phone_numbers() {
+        let calls = get_calls().await;
+
+        let mut state = CallState::default();
+        state.update(&calls, NumberPolicy::Hash, b"secret");
+
+        let missed = state.totals.get("missed").unwrap();
+        assert_eq!(1, missed.len());
+        assert_eq!(HASHED_NUMBER_LENGTH, missed.keys().next().unwrap().len());
+        assert!(!missed.contains_key("0612345678"));
+
+        // the hashes depend on the install secret
+        let mut other = CallState::default();
+        other.update(&calls, NumberPolicy::Hash, b"other secret");
+
+        assert_ne!(
+            missed.keys().next(),
+            other.totals.get("missed").unwrap().keys().next()
+        );
+
+        let mut state = CallState::default();
+        state.update(&calls, NumberPolicy::Drop, &[]);
+
+        assert_eq!(
+            2,
+            state.totals.get("missed").unwrap().get("").unwrap().calls
+        );
+    }
+}
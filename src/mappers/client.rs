@@ -0,0 +1,87 @@
+use log::debug;
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+
+use crate::core::common::{
+    http_client_factory::{AuthenticatedHttpClientFactory, ManagedHttpClient},
+    permission::{PermissionError, INSUFFICIENT_RIGHTS},
+    transport::{FreeboxResponse, FreeboxResponseError},
+};
+
+/// Managed client shared by the maps reading plain API results, created on
+/// first use and renewed once expired
+pub struct MapClient<'a> {
+    factory: &'a AuthenticatedHttpClientFactory<'a>,
+    managed_client: Option<ManagedHttpClient>,
+    permission: Option<&'static str>,
+}
+
+impl<'a> MapClient<'a> {
+    pub fn new(factory: &'a AuthenticatedHttpClientFactory<'a>) -> Self {
+        Self {
+            factory,
+            managed_client: None,
+            permission: None,
+        }
+    }
+
+    /// Refusals of the API are reported as a `PermissionError` on `permission`
+    pub fn with_permission(
+        factory: &'a AuthenticatedHttpClientFactory<'a>,
+        permission: &'static str,
+    ) -> Self {
+        Self {
+            permission: Some(permission),
+            ..Self::new(factory)
+        }
+    }
+
+    pub async fn get_managed_client(
+        &mut self,
+    ) -> Result<Client, Box<dyn std::error::Error + Send + Sync>> {
+        if self.managed_client.is_none() {
+            debug!("creating managed client");
+            self.managed_client = Some(self.factory.create_managed_client().await?);
+        }
+
+        match self.managed_client.as_ref().unwrap().get() {
+            Ok(client) => Ok(client),
+            Err(_) => {
+                debug!("renewing managed client");
+                self.managed_client = Some(self.factory.create_managed_client().await?);
+                self.managed_client.as_ref().unwrap().get()
+            }
+        }
+    }
+
+    /// Empty results are given as a missing result, e.g. no disk plugged,
+    /// they are returned as the default value
+    pub async fn get<T: DeserializeOwned + Clone + Default>(
+        &mut self,
+        path: &str,
+    ) -> Result<T, Box<dyn std::error::Error + Send + Sync>> {
+        debug!("fetching {path}");
+
+        let client = self.get_managed_client().await?;
+        let res = client
+            .get(format!("{}{}", self.factory.api_url, path))
+            .send()
+            .await?
+            .json::<FreeboxResponse<T>>()
+            .await?;
+
+        if !res.success.unwrap_or(false) {
+            if let Some(permission) = self.permission {
+                if res.error_code.as_deref() == Some(INSUFFICIENT_RIGHTS) {
+                    return Err(Box::new(PermissionError::new(permission)));
+                }
+            }
+
+            return Err(Box::new(FreeboxResponseError::new(
+                res.msg.unwrap_or_default(),
+            )));
+        }
+
+        Ok(res.result.unwrap_or_default())
+    }
+}
@@ -1,31 +1,184 @@
+use std::path::PathBuf;
+
 use async_trait::async_trait;
 use log::{debug, info};
 use models::{
-    ConnectionConfiguration, ConnectionFtth, ConnectionIpv6Configuration, ConnectionStatus,
-    XdslInfo, XdslStats,
+    ConnectionConfiguration, ConnectionFtth, ConnectionIpv6Configuration, ConnectionLogEntry,
+    ConnectionStatus, LteConfig, XdslInfo, XdslStats,
 };
 use prometheus_exporter::prometheus::{
-    register_int_gauge, register_int_gauge_vec, IntGauge, IntGaugeVec,
+    register_gauge_vec, register_int_counter, register_int_counter_vec, register_int_gauge,
+    register_int_gauge_vec, GaugeVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
 };
 use reqwest::Client;
 
 
-use super::MetricMap;
+use super::{counters::set_counter, info::InfoMetric, merge_results, MetricMap};
 use crate::{
-    core::common::{
-        http_client_factory::{AuthenticatedHttpClientFactory, ManagedHttpClient},
-        transport::{FreeboxResponse, FreeboxResponseError},
+    core::{
+        common::{
+            http_client_factory::{AuthenticatedHttpClientFactory, ManagedHttpClient},
+            transport::{FreeboxResponse, FreeboxResponseError},
+        },
+        configuration::sections::SfpConfiguration,
     },
 };
+mod logs;
 mod models;
 mod unittests;
 
+const LOG_STATE_FILE: &str = "connection_log.json";
+/// ONU activation state once ranging completed and traffic flows
+const PON_OPERATION_STATE: &str = "O5";
+
+/// Optical power warning thresholds in dBm, exposed as gauges for alert rules
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SfpThresholds {
+    pub rx_low: f64,
+    pub rx_high: f64,
+    pub tx_low: f64,
+    pub tx_high: f64,
+}
+
+impl Default for SfpThresholds {
+    /// GPON class B+ optics budget
+    fn default() -> Self {
+        Self {
+            rx_low: -28.0,
+            rx_high: -8.0,
+            tx_low: 0.5,
+            tx_high: 5.0,
+        }
+    }
+}
+
+impl SfpThresholds {
+    pub fn from_conf(conf: Option<&SfpConfiguration>) -> Self {
+        let default = SfpThresholds::default();
+        let Some(conf) = conf else {
+            return default;
+        };
+
+        Self {
+            rx_low: conf.rx_power_low_dbm.unwrap_or(default.rx_low),
+            rx_high: conf.rx_power_high_dbm.unwrap_or(default.rx_high),
+            tx_low: conf.tx_power_low_dbm.unwrap_or(default.tx_low),
+            tx_high: conf.tx_power_high_dbm.unwrap_or(default.tx_high),
+        }
+    }
+}
+
+/// Value-in-label gauges superseded by the SFP info metric and power gauges
+/// in hundredths of dBm superseded by the dBm ones,
+/// registered only when legacy metrics are enabled
+struct FtthLegacyMetrics {
+    sfp_model_metric: IntGaugeVec,
+    sfp_vendor_metric: IntGaugeVec,
+    sfp_serial_metric: IntGaugeVec,
+    sfp_pwr_tx_metric: IntGauge,
+    sfp_pwr_rx_metric: IntGauge,
+}
+
+impl FtthLegacyMetrics {
+    fn new(prefix: &str) -> Self {
+        Self {
+            sfp_model_metric: register_int_gauge_vec!(
+                format!("{prefix}_connection_ftth_sfp_model"),
+                "SFP model, 1 when reported",
+                &["sfp_model"]
+            )
+            .expect(&format!(
+                "cannot create {prefix}_connection_ftth_sfp_model gauge"
+            )),
+            sfp_vendor_metric: register_int_gauge_vec!(
+                format!("{prefix}_connection_ftth_sfp_vendor"),
+                "SFP vendor, 1 when reported",
+                &["sfp_vendor"]
+            )
+            .expect(&format!(
+                "cannot create {prefix}_connection_ftth_sfp_vendor gauge"
+            )),
+            sfp_serial_metric: register_int_gauge_vec!(
+                format!("{prefix}_connection_ftth_sfp_serial"),
+                "SFP serial number, 1 when reported",
+                &["sfp_serial"]
+            )
+            .expect(&format!(
+                "cannot create {prefix}_connection_ftth_sfp_serial gauge"
+            )),
+            sfp_pwr_tx_metric: register_int_gauge!(
+                format!("{prefix}_connection_ftth_sfp_pwr_tx"),
+                "SFP transmitted optical power, in hundredths of dBm"
+            )
+            .expect(&format!(
+                "cannot create {prefix}_connection_ftth_sfp_pwr_tx gauge"
+            )),
+            sfp_pwr_rx_metric: register_int_gauge!(
+                format!("{prefix}_connection_ftth_sfp_pwr_rx"),
+                "SFP received optical power, in hundredths of dBm"
+            )
+            .expect(&format!(
+                "cannot create {prefix}_connection_ftth_sfp_pwr_rx gauge"
+            )),
+        }
+    }
+
+    fn reset(&self) {
+        self.sfp_model_metric.reset();
+        self.sfp_vendor_metric.reset();
+        self.sfp_serial_metric.reset();
+        self.sfp_pwr_tx_metric.set(0);
+        self.sfp_pwr_rx_metric.set(0);
+    }
+
+    fn set(&self, ftth: &ConnectionFtth) {
+        self.sfp_model_metric
+            .with_label_values(&[&ftth.sfp_model.clone().unwrap_or_default()])
+            .set(ftth.sfp_model.is_some().into());
+        self.sfp_vendor_metric
+            .with_label_values(&[&ftth.sfp_vendor.clone().unwrap_or_default()])
+            .set(ftth.sfp_vendor.is_some().into());
+        self.sfp_serial_metric
+            .with_label_values(&[&ftth.sfp_serial.clone().unwrap_or_default()])
+            .set(ftth.sfp_serial.is_some().into());
+        self.sfp_pwr_tx_metric
+            .set(ftth.sfp_pwr_tx.unwrap_or_default());
+        self.sfp_pwr_rx_metric
+            .set(ftth.sfp_pwr_rx.unwrap_or_default());
+    }
+}
+
+/// LTE carrier bandwidth is given in MHz
+const LTE_BANDWIDTH_UNIT: i64 = 1_000_000;
+
+/// Media carrying the WAN connection, the box reports `backup_4g` once it
+/// failed over to LTE only
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ConnectionMedia {
+    Ftth,
+    Xdsl,
+    Lte,
+}
+
+impl ConnectionMedia {
+    fn from_status(media: &str) -> Self {
+        match media.trim().to_lowercase().as_str() {
+            "ftth" => ConnectionMedia::Ftth,
+            "backup_4g" | "lte" => ConnectionMedia::Lte,
+            _ => ConnectionMedia::Xdsl,
+        }
+    }
+}
+
 pub struct ConnectionMetricMap<'a> {
     factory: &'a AuthenticatedHttpClientFactory<'a>,
-    is_ftth: Option<bool>,
+    media: Option<ConnectionMedia>,
+    has_lte: bool,
+    log_path: PathBuf,
+    log_state: Option<logs::ConnectionLogState>,
     managed_client: Option<ManagedHttpClient>,
-    bytes_down_metric: IntGauge,
-    bytes_up_metric: IntGauge,
+    bytes_down_metric: IntCounter,
+    bytes_up_metric: IntCounter,
     rate_down_metric: IntGauge,
     rate_up_metric: IntGauge,
     bandwidth_down_metric: IntGauge,
@@ -49,170 +202,208 @@ pub struct ConnectionMetricMap<'a> {
     delegations_metric: IntGaugeVec,
     sfp_has_power_report_metric: IntGauge,
     sfp_has_signal_metric: IntGauge,
-    sfp_model_metric: IntGaugeVec,
-    sfp_vendor_metric: IntGaugeVec,
-    sfp_pwr_tx_metric: IntGauge,
-    sfp_pwr_rx_metric: IntGauge,
     link_metric: IntGauge,
     sfp_alim_ok_metric: IntGauge,
-    sfp_serial_metric: IntGaugeVec,
     sfp_present_metric: IntGauge,
+    sfp_info_metric: InfoMetric,
+    sfp_pwr_tx_dbm_metric: GaugeVec,
+    sfp_pwr_rx_dbm_metric: GaugeVec,
+    sfp_pwr_threshold_metric: GaugeVec,
+    sfp_thresholds: SfpThresholds,
+    pon_state_metric: IntGaugeVec,
+    pon_bip_errors_metric: IntCounter,
+    pon_fec_corrected_metric: IntCounter,
+    pon_fec_uncorrected_metric: IntCounter,
+    ftth_legacy_metrics: Option<FtthLegacyMetrics>,
     xdsl_status_uptime: IntGaugeVec,
     xdsl_stats_maxrate: IntGaugeVec,
     xdsl_stats_rate: IntGaugeVec,
     xdsl_stats_snr: IntGaugeVec,
     xdsl_stats_attn: IntGaugeVec,
-    xdsl_stats_fec: IntGaugeVec,
-    xdsl_stats_crc: IntGaugeVec,
-    xdsl_stats_hec: IntGaugeVec,
-    xdsl_stats_es: IntGaugeVec,
-    xdsl_stats_ses: IntGaugeVec,
-    xdsl_stats_rxmt: IntGaugeVec,
-    xdsl_stats_rxmt_corr: IntGaugeVec,
-    xdsl_stats_rxmt_uncorr: IntGaugeVec,
-    xdsl_stats_rtx_tx: IntGaugeVec,
-    xdsl_stats_rtx_c: IntGaugeVec,
-    xdsl_stats_rtx_uc: IntGaugeVec,
+    xdsl_stats_fec: IntCounterVec,
+    xdsl_stats_crc: IntCounterVec,
+    xdsl_stats_hec: IntCounterVec,
+    xdsl_stats_es: IntCounterVec,
+    xdsl_stats_ses: IntCounterVec,
+    xdsl_stats_rxmt: IntCounterVec,
+    xdsl_stats_rxmt_corr: IntCounterVec,
+    xdsl_stats_rxmt_uncorr: IntCounterVec,
+    xdsl_stats_rtx_tx: IntCounterVec,
+    xdsl_stats_rtx_c: IntCounterVec,
+    xdsl_stats_rtx_uc: IntCounterVec,
+    xdsl_info: IntGaugeVec,
+    xdsl_stats_phyr: IntGaugeVec,
+    xdsl_stats_ginp: IntGaugeVec,
+    xdsl_stats_nitro: IntGaugeVec,
+    xdsl_stats_rate_margin: IntGaugeVec,
+    xdsl_stats_rate_ratio: IntGaugeVec,
+    lte_enabled_metric: IntGauge,
+    lte_state_metric: IntGaugeVec,
+    lte_network_up_metric: IntGauge,
+    lte_operator_metric: IntGaugeVec,
+    lte_band_rsrp_metric: IntGaugeVec,
+    lte_band_rsrq_metric: GaugeVec,
+    lte_band_sinr_metric: GaugeVec,
+    lte_band_bandwidth_metric: IntGaugeVec,
+    lte_tunnel_connected_metric: IntGaugeVec,
+    lte_aggregation_metric: IntGauge,
+    link_transitions_metric: IntCounterVec,
+    last_outage_metric: IntGauge,
+    last_outage_duration_metric: IntGauge,
 }
 
 impl<'a> ConnectionMetricMap<'a> {
-    pub fn new(factory: &'a AuthenticatedHttpClientFactory<'a>, prefix: String) -> Self {
+    pub fn new(
+        factory: &'a AuthenticatedHttpClientFactory<'a>,
+        prefix: String,
+        has_lte: bool,
+        data_directory: &str,
+        legacy_metrics: bool,
+        sfp_thresholds: SfpThresholds,
+    ) -> Self {
         Self {
             factory,
-            is_ftth: None,
+            media: None,
+            has_lte,
+            log_path: PathBuf::from(data_directory).join(LOG_STATE_FILE),
+            log_state: None,
             managed_client: None,
-            bytes_down_metric: register_int_gauge!(
-                format!("{prefix}_connection_bytes_down"),
-                format!("{prefix}_connection_bytes_down")
+            bytes_down_metric: register_int_counter!(
+                format!("{prefix}_connection_down_bytes_total"),
+                "Total bytes received on the WAN interface"
             )
             .expect(&format!(
-                "cannot create {prefix}_connection_bytes_down gauge"
+                "cannot create {prefix}_connection_down_bytes_total counter"
             )),
-            bytes_up_metric: register_int_gauge!(
-                format!("{prefix}_connection_bytes_up"),
-                format!("{prefix}_connection_bytes_up")
+            bytes_up_metric: register_int_counter!(
+                format!("{prefix}_connection_up_bytes_total"),
+                "Total bytes sent on the WAN interface"
             )
-            .expect(&format!("cannot create {prefix}_connection_bytes_up gauge")),
+            .expect(&format!(
+                "cannot create {prefix}_connection_up_bytes_total counter"
+            )),
             rate_down_metric: register_int_gauge!(
-                format!("{prefix}_connection_rate_down"),
-                format!("{prefix}_connection_rate_down")
+                format!("{prefix}_connection_rate_down_bytes_per_second"),
+                "Current download rate in bytes per second"
             )
             .expect(&format!(
-                "cannot create {prefix}_connection_rate_down gauge"
+                "cannot create {prefix}_connection_rate_down_bytes_per_second gauge"
             )),
             rate_up_metric: register_int_gauge!(
-                format!("{prefix}_connection_rate_up"),
-                format!("{prefix}_connection_rate_up")
+                format!("{prefix}_connection_rate_up_bytes_per_second"),
+                "Current upload rate in bytes per second"
             )
-            .expect(&format!("cannot create {prefix}_connection_rate_up gauge")),
+            .expect(&format!(
+                "cannot create {prefix}_connection_rate_up_bytes_per_second gauge"
+            )),
             bandwidth_down_metric: register_int_gauge!(
-                format!("{prefix}_connection_bandwidth_down"),
-                format!("{prefix}_connection_bandwidth_down")
+                format!("{prefix}_connection_bandwidth_down_bits_per_second"),
+                "Available download bandwidth in bits per second"
             )
             .expect(&format!(
-                "cannot create {prefix}_connection_bandwidth_down gauge"
+                "cannot create {prefix}_connection_bandwidth_down_bits_per_second gauge"
             )),
             bandwidth_up_metric: register_int_gauge!(
-                format!("{prefix}_connection_bandwidth_up"),
-                format!("{prefix}_connection_bandwidth_up")
+                format!("{prefix}_connection_bandwidth_up_bits_per_second"),
+                "Available upload bandwidth in bits per second"
             )
             .expect(&format!(
-                "cannot create {prefix}_connection_bandwidth_up gauge"
+                "cannot create {prefix}_connection_bandwidth_up_bits_per_second gauge"
             )),
             type_metric: register_int_gauge_vec!(
                 format!("{prefix}_connection_type"),
-                format!("{prefix}_connection_type"),
+                "Connection type (ethernet, rfc2684, pppoatm), always 1",
                 &["type"]
             )
             .expect(&format!("cannot create {prefix}_connection_type gauge")),
             media_metric: register_int_gauge_vec!(
                 format!("{prefix}_connection_media"),
-                format!("{prefix}_connection_media"),
+                "Connection media (ftth, xdsl, backup_4g when failed over to LTE), always 1",
                 &["media"]
             )
             .expect(&format!("cannot create {prefix}_connection_media gauge")),
             state_metric: register_int_gauge_vec!(
                 format!("{prefix}_connection_state"),
-                format!("{prefix}_connection_state"),
+                "Connection state, 1 when the connection is up",
                 &["state"]
             )
             .expect(&format!("cannot create {prefix}_connection_state gauge")),
             ipv4_metric: register_int_gauge_vec!(
                 format!("{prefix}_connection_ipv4"),
-                format!("{prefix}_connection_ipv4"),
+                "Public IPv4 address, always 1",
                 &["ipv4"]
             )
             .expect(&format!("cannot create {prefix}_connection_ipv4 gauge")),
             ipv6_metric: register_int_gauge_vec!(
                 format!("{prefix}_connection_ipv6"),
-                format!("{prefix}_connection_ipv6"),
+                "Public IPv6 address, always 1",
                 &["ipv6"]
             )
             .expect(&format!("cannot create {prefix}_connection_ipv6 gauge")),
             ping_metric: register_int_gauge!(
                 format!("{prefix}_connection_conf_ping"),
-                format!("{prefix}_connection_conf_ping")
+                "1 when the box answers to ping on the WAN side"
             )
             .expect(&format!(
                 "cannot create {prefix}_connection_conf_ping gauge"
             )),
             is_secure_pass_metric: register_int_gauge!(
                 format!("{prefix}_connection_conf_is_secure_pass"),
-                format!("{prefix}_connection_conf_is_secure_pass")
+                "1 when the remote access password is considered secure"
             )
             .expect(&format!(
                 "cannot create {prefix}_connection_conf_is_secure_pass gauge"
             )),
             remote_access_port_metric: register_int_gauge!(
                 format!("{prefix}_connection_conf_remote_access_port"),
-                format!("{prefix}_connection_conf_remote_access_port")
+                "Port used for the HTTP remote access"
             )
             .expect(&format!(
                 "cannot create {prefix}_connection_conf_remote_access_port gauge"
             )),
             remote_access_metric: register_int_gauge!(
                 format!("{prefix}_connection_conf_remote_access"),
-                format!("{prefix}_connection_conf_remote_access")
+                "1 when HTTP remote access is enabled"
             )
             .expect(&format!(
                 "cannot create {prefix}_connection_conf_remote_access gauge"
             )),
             wol_metric: register_int_gauge!(
                 format!("{prefix}_connection_wol_conf"),
-                format!("{prefix}_connection_conf_wol")
+                "1 when wake on LAN proxy from the WAN side is enabled"
             )
             .expect(&format!("cannot create {prefix}_connection_conf_wol gauge")),
             adblock_metric: register_int_gauge!(
                 format!("{prefix}_connection_conf_adblock"),
-                format!("{prefix}_connection_conf_adblock")
+                "1 when the ad blocker is enabled"
             )
             .expect(&format!(
                 "cannot create {prefix}_connection_conf_adblock gauge"
             )),
             adblock_not_set_metric: register_int_gauge!(
                 format!("{prefix}_connection_conf_adblock_not_set"),
-                format!("{prefix}_connection_conf_adblock_not_set")
+                "1 when the ad blocker was never configured"
             )
             .expect(&format!(
                 "cannot {prefix}_create connection_conf_adblock_not_set"
             )),
             api_remote_access_metric: register_int_gauge!(
                 format!("{prefix}_connection_conf_api_remote_access"),
-                format!("{prefix}_connection_conf_api_remote_access")
+                "1 when the API is reachable from the WAN side"
             )
             .expect(&format!(
                 "cannot create {prefix}_connection_conf_api_remote_access gauge"
             )),
             allow_token_request_metric: register_int_gauge!(
                 format!("{prefix}_connection_conf_allow_token_request"),
-                format!("{prefix}_connection_conf_allow_token_request")
+                "1 when new applications can request a token"
             )
             .expect(&format!(
                 "cannot create {prefix}_connection_conf_allow_token_request gauge"
             )),
             remote_access_ip_metric: register_int_gauge_vec!(
                 format!("{prefix}_connection_conf_remote_access_ip"),
-                format!("{prefix}_connection_conf_remote_access_ip"),
+                "IP address to use for remote access, 1 when remote access is configured",
                 &["remote_access_ip"]
             )
             .expect(&format!(
@@ -220,14 +411,14 @@ impl<'a> ConnectionMetricMap<'a> {
             )),
             ipv6_enabled_metric: register_int_gauge!(
                 format!("{prefix}_connection_ipv6_conf_ipv6_enabled"),
-                format!("{prefix}_connection_ipv6_conf_ipv6_enabled")
+                "1 when IPv6 is enabled"
             )
             .expect(&format!(
                 "cannot create {prefix}_create connection_ipv6_conf_ipv6_enabled"
             )),
             delegations_metric: register_int_gauge_vec!(
                 format!("{prefix}_connection_ipv6_conf_delegations"),
-                format!("{prefix}_connection_ipv6_conf_delegations"),
+                "IPv6 prefix delegation, always 1",
                 &["prefix", "next_hop"]
             )
             .expect(&format!(
@@ -235,88 +426,117 @@ impl<'a> ConnectionMetricMap<'a> {
             )),
             sfp_has_power_report_metric: register_int_gauge!(
                 format!("{prefix}_connection_ftth_sfp_has_power_report"),
-                format!("{prefix}_connection_ftth_sfp_has_power_report")
+                "1 when the SFP reports its optical power"
             )
             .expect(&format!(
                 "cannot create {prefix}_connection_ftth_sfp_has_power_report gauge"
             )),
             sfp_has_signal_metric: register_int_gauge!(
                 format!("{prefix}_connection_ftth_sfp_has_signal"),
-                format!("{prefix}_connection_ftth_sfp_has_signal")
+                "1 when the SFP receives a signal"
             )
             .expect(&format!(
                 "cannot create {prefix}_connection_ftth_sfp_has_signal gauge"
             )),
-            sfp_model_metric: register_int_gauge_vec!(
-                format!("{prefix}_connection_ftth_sfp_model"),
-                format!("{prefix}_connection_ftth_sfp_model"),
-                &["sfp_model"]
+            link_metric: register_int_gauge!(
+                format!("{prefix}_connection_ftth_link"),
+                "1 when the FTTH link is up"
             )
             .expect(&format!(
-                "cannot create {prefix}_connection_ftth_sfp_model gauge"
+                "cannot create {prefix}_connection_ftth_link gauge"
             )),
-            sfp_vendor_metric: register_int_gauge_vec!(
-                format!("{prefix}_connection_ftth_sfp_vendor"),
-                format!("{prefix}_connection_ftth_sfp_vendor"),
-                &["sfp_vendor"]
+            sfp_alim_ok_metric: register_int_gauge!(
+                format!("{prefix}_connection_ffth_sfp_alim_ok"),
+                "1 when the SFP power supply is ok"
             )
             .expect(&format!(
-                "cannot create {prefix}_connection_ftth_sfp_vendor gauge"
+                "cannot create {prefix}_connection_ffth_sfp_alim_ok gauge"
             )),
-            sfp_pwr_tx_metric: register_int_gauge!(
-                format!("{prefix}_connection_ftth_sfp_pwr_tx"),
-                format!("{prefix}_connection_ftth_sfp_pwr_tx")
+            sfp_present_metric: register_int_gauge!(
+                format!("{prefix}_connection_ffth_sfp_present"),
+                "1 when a SFP is present"
             )
             .expect(&format!(
-                "cannot create {prefix}_connection_ftth_sfp_pwr_tx gauge"
+                "cannot create {prefix}_connection_ffth_sfp_present gauge"
             )),
-            sfp_pwr_rx_metric: register_int_gauge!(
-                format!("{prefix}_connection_ftth_sfp_pwr_rx"),
-                format!("{prefix}_connection_ftth_sfp_pwr_rx")
+            sfp_info_metric: InfoMetric::new(
+                register_int_gauge_vec!(
+                    format!("{prefix}_connection_ftth_sfp_info"),
+                    "SFP model, vendor, serial number and PON technology, always 1",
+                    &["model", "vendor", "serial", "link_type"]
+                )
+                .expect(&format!(
+                    "cannot create {prefix}_connection_ftth_sfp_info gauge"
+                )),
+            ),
+            // no labels, the series is removed when the SFP reports no power
+            // instead of exposing 0 dBm, which is within the alert thresholds
+            sfp_pwr_tx_dbm_metric: register_gauge_vec!(
+                format!("{prefix}_connection_ftth_sfp_pwr_tx_dbm"),
+                "SFP transmitted optical power in dBm, only when reported",
+                &[]
             )
             .expect(&format!(
-                "cannot create {prefix}_connection_ftth_sfp_pwr_rx gauge"
+                "cannot create {prefix}_connection_ftth_sfp_pwr_tx_dbm gauge"
             )),
-            link_metric: register_int_gauge!(
-                format!("{prefix}_connection_ftth_link"),
-                format!("{prefix}_connection_ftth_link")
+            sfp_pwr_rx_dbm_metric: register_gauge_vec!(
+                format!("{prefix}_connection_ftth_sfp_pwr_rx_dbm"),
+                "SFP received optical power in dBm, only when reported",
+                &[]
             )
             .expect(&format!(
-                "cannot create {prefix}_connection_ftth_link gauge"
+                "cannot create {prefix}_connection_ftth_sfp_pwr_rx_dbm gauge"
             )),
-            sfp_alim_ok_metric: register_int_gauge!(
-                format!("{prefix}_connection_ffth_sfp_alim_ok"),
-                format!("{prefix}_connection_ffth_sfp_alim_ok")
+            sfp_pwr_threshold_metric: register_gauge_vec!(
+                format!("{prefix}_connection_ftth_sfp_pwr_threshold_dbm"),
+                "SFP optical power warning thresholds in dBm, from the configuration",
+                &["direction", "level"]
             )
             .expect(&format!(
-                "cannot create {prefix}_connection_ffth_sfp_alim_ok gauge"
+                "cannot create {prefix}_connection_ftth_sfp_pwr_threshold_dbm gauge"
             )),
-            sfp_serial_metric: register_int_gauge_vec!(
-                format!("{prefix}_connection_ftth_sfp_serial"),
-                format!("{prefix}_connection_ftth_sfp_serial"),
-                &["sfp_serial"]
+            sfp_thresholds,
+            pon_state_metric: register_int_gauge_vec!(
+                format!("{prefix}_connection_ftth_pon_state"),
+                "PON ONU activation state (O1 to O5), 1 when in operation",
+                &["state"]
             )
             .expect(&format!(
-                "cannot create {prefix}_connection_ftth_sfp_serial gauge"
+                "cannot create {prefix}_connection_ftth_pon_state gauge"
             )),
-            sfp_present_metric: register_int_gauge!(
-                format!("{prefix}_connection_ffth_sfp_present"),
-                format!("{prefix}_connection_ffth_sfp_present")
+            pon_bip_errors_metric: register_int_counter!(
+                format!("{prefix}_connection_ftth_pon_bip_errors_total"),
+                "PON bit interleaved parity errors"
             )
             .expect(&format!(
-                "cannot create {prefix}_connection_ffth_sfp_present gauge"
+                "cannot create {prefix}_connection_ftth_pon_bip_errors_total counter"
+            )),
+            pon_fec_corrected_metric: register_int_counter!(
+                format!("{prefix}_connection_ftth_pon_fec_corrected_total"),
+                "PON codewords corrected by FEC"
+            )
+            .expect(&format!(
+                "cannot create {prefix}_connection_ftth_pon_fec_corrected_total counter"
+            )),
+            pon_fec_uncorrected_metric: register_int_counter!(
+                format!("{prefix}_connection_ftth_pon_fec_uncorrected_total"),
+                "PON codewords FEC could not correct"
+            )
+            .expect(&format!(
+                "cannot create {prefix}_connection_ftth_pon_fec_uncorrected_total counter"
             )),
+            ftth_legacy_metrics: legacy_metrics.then(|| FtthLegacyMetrics::new(&prefix)),
             xdsl_status_uptime: register_int_gauge_vec!(
-                format!("{prefix}_connection_xdsl_status_uptime"),
-                format!("{prefix}_connection_xdsl_status_uptime"),
+                format!("{prefix}_connection_xdsl_status_uptime_seconds"),
+                "xDSL line uptime in seconds",
                 &["status", "protocol", "modulation"]
             )
             .expect(&format!(
-                "cannot create {prefix}_connection_xdsl_status_uptime gauge"
+                "cannot create {prefix}_connection_xdsl_status_uptime_seconds gauge"
             )),
             xdsl_stats_maxrate: register_int_gauge_vec!(
                 format!("{prefix}_connection_xdsl_stats_maxrate"),
-                format!("{prefix}_connection_xdsl_stats_maxrate"),
+                "xDSL maximum attainable rate in kbit/s",
                 &["direction"]
             )
             .expect(&format!(
@@ -324,7 +544,7 @@ impl<'a> ConnectionMetricMap<'a> {
             )),
             xdsl_stats_rate: register_int_gauge_vec!(
                 format!("{prefix}_connection_xdsl_stats_rate"),
-                format!("{prefix}_connection_xdsl_stats_rate"),
+                "xDSL synchronization rate in kbit/s",
                 &["direction"]
             )
             .expect(&format!(
@@ -332,7 +552,7 @@ impl<'a> ConnectionMetricMap<'a> {
             )),
             xdsl_stats_snr: register_int_gauge_vec!(
                 format!("{prefix}_connection_xdsl_stats_snr"),
-                format!("{prefix}_connection_xdsl_stats_snr"),
+                "xDSL signal to noise ratio margin in dB",
                 &["direction"]
             )
             .expect(&format!(
@@ -340,99 +560,246 @@ impl<'a> ConnectionMetricMap<'a> {
             )),
             xdsl_stats_attn: register_int_gauge_vec!(
                 format!("{prefix}_connection_xdsl_stats_attn"),
-                format!("{prefix}_connection_xdsl_stats_attn"),
+                "xDSL line attenuation in dB",
                 &["direction"]
             )
             .expect(&format!(
                 "cannot create {prefix}_connection_xdsl_stats_attn gauge"
             )),
-            xdsl_stats_fec: register_int_gauge_vec!(
-                format!("{prefix}_connection_xdsl_stats_fec"),
-                format!("{prefix}_connection_xdsl_stats_fec"),
+            xdsl_stats_fec: register_int_counter_vec!(
+                format!("{prefix}_connection_xdsl_stats_fec_total"),
+                "xDSL forward error correction events",
+                &["direction"]
+            )
+            .expect(&format!(
+                "cannot create {prefix}_connection_xdsl_stats_fec_total counter"
+            )),
+            xdsl_stats_crc: register_int_counter_vec!(
+                format!("{prefix}_connection_xdsl_stats_crc_total"),
+                "xDSL CRC errors",
+                &["direction"]
+            )
+            .expect(&format!(
+                "cannot create {prefix}_connection_xdsl_stats_crc_total counter"
+            )),
+            xdsl_stats_hec: register_int_counter_vec!(
+                format!("{prefix}_connection_xdsl_stats_hec_total"),
+                "xDSL header error control errors",
+                &["direction"]
+            )
+            .expect(&format!(
+                "cannot create {prefix}_connection_xdsl_stats_hec_total counter"
+            )),
+            xdsl_stats_es: register_int_counter_vec!(
+                format!("{prefix}_connection_xdsl_stats_es_seconds_total"),
+                "xDSL errored seconds",
                 &["direction"]
             )
             .expect(&format!(
-                "cannot create {prefix}_connection_xdsl_stats_fec gauge"
+                "cannot create {prefix}_connection_xdsl_stats_es_seconds_total counter"
             )),
-            xdsl_stats_crc: register_int_gauge_vec!(
-                format!("{prefix}_connection_xdsl_stats_crc"),
-                format!("{prefix}_connection_xdsl_stats_crc"),
+            xdsl_stats_ses: register_int_counter_vec!(
+                format!("{prefix}_connection_xdsl_stats_ses_seconds_total"),
+                "xDSL severely errored seconds",
                 &["direction"]
             )
             .expect(&format!(
-                "cannot create {prefix}_connection_xdsl_stats_crc gauge"
+                "cannot create {prefix}_connection_xdsl_stats_ses_seconds_total counter"
             )),
-            xdsl_stats_hec: register_int_gauge_vec!(
-                format!("{prefix}_connection_xdsl_stats_hec"),
-                format!("{prefix}_connection_xdsl_stats_hec"),
+            xdsl_stats_rxmt: register_int_counter_vec!(
+                format!("{prefix}_connection_xdsl_stats_rxmt_total"),
+                "xDSL retransmission requests",
                 &["direction"]
             )
             .expect(&format!(
-                "cannot create {prefix}_connection_xdsl_stats_hec gauge"
+                "cannot create {prefix}_connection_xdsl_stats_rxmt_total counter"
             )),
-            xdsl_stats_es: register_int_gauge_vec!(
-                format!("{prefix}_connection_xdsl_stats_es"),
-                format!("{prefix}_connection_xdsl_stats_es"),
+            xdsl_stats_rxmt_corr: register_int_counter_vec!(
+                format!("{prefix}_connection_xdsl_stats_rxmt_corr_total"),
+                "xDSL corrected retransmissions",
                 &["direction"]
             )
             .expect(&format!(
-                "cannot create {prefix}_connection_xdsl_stats_es gauge"
+                "cannot create {prefix}_connection_xdsl_stats_rxmt_corr_total counter"
             )),
-            xdsl_stats_ses: register_int_gauge_vec!(
-                format!("{prefix}_connection_xdsl_stats_ses"),
-                format!("{prefix}_connection_xdsl_stats_ses"),
+            xdsl_stats_rxmt_uncorr: register_int_counter_vec!(
+                format!("{prefix}_connection_xdsl_stats_rxmt_uncorr_total"),
+                "xDSL uncorrected retransmissions",
                 &["direction"]
             )
             .expect(&format!(
-                "cannot create {prefix}_connection_xdsl_stats_ses gauge"
+                "cannot create {prefix}_connection_xdsl_stats_rxmt_uncorr_total counter"
             )),
-            xdsl_stats_rxmt: register_int_gauge_vec!(
-                format!("{prefix}_connection_xdsl_stats_rxmt"),
-                format!("{prefix}_connection_xdsl_stats_rxmt"),
+            xdsl_stats_rtx_tx: register_int_counter_vec!(
+                format!("{prefix}_connection_xdsl_stats_rtx_tx_total"),
+                "xDSL G.INP retransmitted DTUs",
                 &["direction"]
             )
             .expect(&format!(
-                "cannot create {prefix}_connection_xdsl_stats_rxmt gauge"
+                "cannot create {prefix}_connection_xdsl_stats_rtx_tx_total counter"
             )),
-            xdsl_stats_rxmt_corr: register_int_gauge_vec!(
-                format!("{prefix}_connection_xdsl_stats_rxmt_corr"),
-                format!("{prefix}_connection_xdsl_stats_rxmt_corr"),
+            xdsl_stats_rtx_c: register_int_counter_vec!(
+                format!("{prefix}_connection_xdsl_stats_rtx_c_total"),
+                "xDSL G.INP corrected DTUs",
                 &["direction"]
             )
             .expect(&format!(
-                "cannot create {prefix}_connection_xdsl_stats_rxmt_corr gauge"
+                "cannot create {prefix}_connection_xdsl_stats_rtx_c_total counter"
             )),
-            xdsl_stats_rxmt_uncorr: register_int_gauge_vec!(
-                format!("{prefix}_connection_xdsl_stats_rxmt_uncorr"),
-                format!("{prefix}_connection_xdsl_stats_rxmt_uncorr"),
+            xdsl_stats_rtx_uc: register_int_counter_vec!(
+                format!("{prefix}_connection_xdsl_stats_rtx_uc_total"),
+                "xDSL G.INP uncorrected DTUs",
                 &["direction"]
             )
             .expect(&format!(
-                "cannot create {prefix}_connection_xdsl_stats_rxmt_uncorr gauge"
+                "cannot create {prefix}_connection_xdsl_stats_rtx_uc_total counter"
             )),
-            xdsl_stats_rtx_tx: register_int_gauge_vec!(
-                format!("{prefix}_connection_xdsl_stats_rtx_tx"),
-                format!("{prefix}_connection_xdsl_stats_rtx_tx"),
+            xdsl_info: register_int_gauge_vec!(
+                format!("{prefix}_connection_xdsl_info"),
+                "xDSL line status (showtime, training, down...), protocol and modulation, always 1",
+                &["status", "protocol", "modulation"]
+            )
+            .expect(&format!(
+                "cannot create {prefix}_connection_xdsl_info gauge"
+            )),
+            xdsl_stats_phyr: register_int_gauge_vec!(
+                format!("{prefix}_connection_xdsl_stats_phyr"),
+                "xDSL PhyR retransmission enabled",
+                &["direction"]
+            )
+            .expect(&format!(
+                "cannot create {prefix}_connection_xdsl_stats_phyr gauge"
+            )),
+            xdsl_stats_ginp: register_int_gauge_vec!(
+                format!("{prefix}_connection_xdsl_stats_ginp"),
+                "xDSL G.INP retransmission enabled",
+                &["direction"]
+            )
+            .expect(&format!(
+                "cannot create {prefix}_connection_xdsl_stats_ginp gauge"
+            )),
+            xdsl_stats_nitro: register_int_gauge_vec!(
+                format!("{prefix}_connection_xdsl_stats_nitro"),
+                "xDSL nitro (ADSL2+ framing overhead reduction) enabled",
                 &["direction"]
             )
             .expect(&format!(
-                "cannot create {prefix}_connection_xdsl_stats_rtx_tx gauge"
+                "cannot create {prefix}_connection_xdsl_stats_nitro gauge"
             )),
-            xdsl_stats_rtx_c: register_int_gauge_vec!(
-                format!("{prefix}_connection_xdsl_stats_rtx_c"),
-                format!("{prefix}_connection_xdsl_stats_rtx_c"),
+            xdsl_stats_rate_margin: register_int_gauge_vec!(
+                format!("{prefix}_connection_xdsl_stats_rate_margin"),
+                "xDSL rate headroom, maximum attainable minus synchronized rate in kbit/s",
                 &["direction"]
             )
             .expect(&format!(
-                "cannot create {prefix}_connection_xdsl_stats_rtx_c gauge"
+                "cannot create {prefix}_connection_xdsl_stats_rate_margin gauge"
             )),
-            xdsl_stats_rtx_uc: register_int_gauge_vec!(
-                format!("{prefix}_connection_xdsl_stats_rtx_uc"),
-                format!("{prefix}_connection_xdsl_stats_rtx_uc"),
+            xdsl_stats_rate_ratio: register_int_gauge_vec!(
+                format!("{prefix}_connection_xdsl_stats_rate_to_maxrate_percent"),
+                "xDSL synchronized rate relative to the maximum attainable rate",
                 &["direction"]
             )
             .expect(&format!(
-                "cannot create {prefix}_connection_xdsl_stats_rtx_uc gauge"
+                "cannot create {prefix}_connection_xdsl_stats_rate_to_maxrate_percent gauge"
+            )),
+            lte_enabled_metric: register_int_gauge!(
+                format!("{prefix}_connection_lte_enabled"),
+                "LTE link enabled, for 4G aggregation or backup"
+            )
+            .expect(&format!(
+                "cannot create {prefix}_connection_lte_enabled gauge"
+            )),
+            lte_state_metric: register_int_gauge_vec!(
+                format!("{prefix}_connection_lte_state"),
+                "LTE link state, 1 when connected",
+                &["state"]
+            )
+            .expect(&format!(
+                "cannot create {prefix}_connection_lte_state gauge"
+            )),
+            lte_network_up_metric: register_int_gauge!(
+                format!("{prefix}_connection_lte_network_up"),
+                "LTE data session (PDN) is up"
+            )
+            .expect(&format!(
+                "cannot create {prefix}_connection_lte_network_up gauge"
+            )),
+            lte_operator_metric: register_int_gauge_vec!(
+                format!("{prefix}_connection_lte_operator"),
+                "LTE network operator (PLMN), 1 when the radio is associated",
+                &["plmn"]
+            )
+            .expect(&format!(
+                "cannot create {prefix}_connection_lte_operator gauge"
+            )),
+            lte_band_rsrp_metric: register_int_gauge_vec!(
+                format!("{prefix}_connection_lte_band_rsrp_dbm"),
+                "LTE reference signal received power per band",
+                &["band"]
+            )
+            .expect(&format!(
+                "cannot create {prefix}_connection_lte_band_rsrp_dbm gauge"
+            )),
+            lte_band_rsrq_metric: register_gauge_vec!(
+                format!("{prefix}_connection_lte_band_rsrq_db"),
+                "LTE reference signal received quality per band",
+                &["band"]
+            )
+            .expect(&format!(
+                "cannot create {prefix}_connection_lte_band_rsrq_db gauge"
+            )),
+            lte_band_sinr_metric: register_gauge_vec!(
+                format!("{prefix}_connection_lte_band_sinr_db"),
+                "LTE signal to interference plus noise ratio per band",
+                &["band"]
+            )
+            .expect(&format!(
+                "cannot create {prefix}_connection_lte_band_sinr_db gauge"
+            )),
+            lte_band_bandwidth_metric: register_int_gauge_vec!(
+                format!("{prefix}_connection_lte_band_bandwidth_hertz"),
+                "LTE carrier bandwidth per band",
+                &["band"]
+            )
+            .expect(&format!(
+                "cannot create {prefix}_connection_lte_band_bandwidth_hertz gauge"
+            )),
+            lte_tunnel_connected_metric: register_int_gauge_vec!(
+                format!("{prefix}_connection_lte_tunnel_connected"),
+                "Aggregation tunnel link (lte, xdsl), 1 when the link is bonded",
+                &["link"]
+            )
+            .expect(&format!(
+                "cannot create {prefix}_connection_lte_tunnel_connected gauge"
+            )),
+            lte_aggregation_metric: register_int_gauge!(
+                format!("{prefix}_connection_lte_aggregation_active"),
+                "4G aggregation is active, both LTE and xDSL links are bonded"
+            )
+            .expect(&format!(
+                "cannot create {prefix}_connection_lte_aggregation_active gauge"
+            )),
+            link_transitions_metric: register_int_counter_vec!(
+                format!("{prefix}_connection_link_transitions_total"),
+                "WAN link transitions from the connection log per reached state (up, down)",
+                &["state"]
+            )
+            .expect(&format!(
+                "cannot create {prefix}_connection_link_transitions_total counter"
+            )),
+            last_outage_metric: register_int_gauge!(
+                format!("{prefix}_connection_last_outage_timestamp_seconds"),
+                "Last WAN link outage start as a unix timestamp, 0 when unknown"
+            )
+            .expect(&format!(
+                "cannot create {prefix}_connection_last_outage_timestamp_seconds gauge"
+            )),
+            last_outage_duration_metric: register_int_gauge!(
+                format!("{prefix}_connection_last_outage_duration_seconds"),
+                "Last WAN link outage duration, 0 while the outage is ongoing"
+            )
+            .expect(&format!(
+                "cannot create {prefix}_connection_last_outage_duration_seconds gauge"
             )),
         }
     }
@@ -504,28 +871,74 @@ impl<'a> ConnectionMetricMap<'a> {
             Some(r) => r,
         };
 
+        self.reset_ftth();
+
         self.sfp_has_power_report_metric
             .set(ftth.sfp_has_power_report.unwrap_or_default().into());
         self.sfp_has_signal_metric
             .set(ftth.sfp_has_signal.unwrap_or_default().into());
-        self.sfp_model_metric
-            .with_label_values(&[&ftth.sfp_model.clone().unwrap_or_default()])
-            .set(ftth.sfp_model.is_some().into());
-        self.sfp_vendor_metric
-            .with_label_values(&[&ftth.sfp_vendor.clone().unwrap_or_default()])
-            .set(ftth.sfp_vendor.is_some().into());
-        self.sfp_pwr_tx_metric
-            .set(ftth.sfp_pwr_tx.unwrap_or_default());
-        self.sfp_pwr_rx_metric
-            .set(ftth.sfp_pwr_rx.unwrap_or_default());
         self.link_metric.set(ftth.link.unwrap_or_default().into());
         self.sfp_alim_ok_metric
             .set(ftth.sfp_alim_ok.unwrap_or_default().into());
-        self.sfp_serial_metric
-            .with_label_values(&[&ftth.sfp_serial.clone().unwrap_or_default()])
-            .set(ftth.sfp_serial.is_some().into());
         self.sfp_present_metric
             .set(ftth.sfp_present.unwrap_or_default().into());
+
+        self.sfp_info_metric.set(&[
+            &ftth.sfp_model.clone().unwrap_or_default(),
+            &ftth.sfp_vendor.clone().unwrap_or_default(),
+            &ftth.sfp_serial.clone().unwrap_or_default(),
+            &ftth.link_type.clone().unwrap_or_default(),
+        ]);
+
+        if ftth.sfp_has_power_report.unwrap_or_default() {
+            if let Some(pwr_tx) = ftth.get_pwr_tx_dbm() {
+                self.sfp_pwr_tx_dbm_metric
+                    .with_label_values(&[])
+                    .set(pwr_tx);
+            }
+            if let Some(pwr_rx) = ftth.get_pwr_rx_dbm() {
+                self.sfp_pwr_rx_dbm_metric
+                    .with_label_values(&[])
+                    .set(pwr_rx);
+            }
+        }
+
+        let thresholds = self.sfp_thresholds;
+        for (direction, level, value) in [
+            ("rx", "low", thresholds.rx_low),
+            ("rx", "high", thresholds.rx_high),
+            ("tx", "low", thresholds.tx_low),
+            ("tx", "high", thresholds.tx_high),
+        ] {
+            self.sfp_pwr_threshold_metric
+                .with_label_values(&[direction, level])
+                .set(value);
+        }
+
+        if let Some(pon) = &ftth.pon {
+            let state = pon.onu_state.clone().unwrap_or_default();
+
+            self.pon_state_metric
+                .with_label_values(&[&state])
+                .set((state == PON_OPERATION_STATE).into());
+            set_counter(
+                &self.pon_bip_errors_metric,
+                pon.bip_errors.unwrap_or_default(),
+            );
+            set_counter(
+                &self.pon_fec_corrected_metric,
+                pon.fec_corrected.unwrap_or_default(),
+            );
+            set_counter(
+                &self.pon_fec_uncorrected_metric,
+                pon.fec_uncorrected.unwrap_or_default(),
+            );
+        }
+
+        if let Some(legacy_metrics) = &self.ftth_legacy_metrics {
+            legacy_metrics.set(&ftth);
+        }
+
         Ok(())
     }
 
@@ -561,6 +974,8 @@ impl<'a> ConnectionMetricMap<'a> {
         &mut self,
         status: &ConnectionStatus,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.reset_status();
+
         self.type_metric
             .with_label_values(&[&status.clone()._type.unwrap_or_default()])
             .set(1);
@@ -580,10 +995,11 @@ impl<'a> ConnectionMetricMap<'a> {
         self.ipv6_metric
             .with_label_values(&[&status.clone().ipv6.unwrap_or_default()])
             .set(1);
-        self.bytes_down_metric
-            .set(status.bytes_down.unwrap_or_default());
-        self.bytes_up_metric
-            .set(status.bytes_up.unwrap_or_default());
+        set_counter(
+            &self.bytes_down_metric,
+            status.bytes_down.unwrap_or_default(),
+        );
+        set_counter(&self.bytes_up_metric, status.bytes_up.unwrap_or_default());
         self.rate_down_metric
             .set(status.rate_down.unwrap_or_default());
         self.rate_up_metric.set(status.rate_up.unwrap_or_default());
@@ -630,6 +1046,8 @@ impl<'a> ConnectionMetricMap<'a> {
             Some(r) => r,
         };
 
+        self.reset_conf();
+
         self.ping_metric.set(conf.ping.unwrap_or_default().into());
         self.is_secure_pass_metric
             .set(conf.is_secure_pass.unwrap_or_default().into());
@@ -689,6 +1107,8 @@ impl<'a> ConnectionMetricMap<'a> {
             Some(r) => r,
         };
 
+        self.reset_ipv6_conf();
+
         self.ipv6_enabled_metric
             .set(conf.ipv6_enabled.unwrap_or_default().into());
 
@@ -733,13 +1153,19 @@ impl<'a> ConnectionMetricMap<'a> {
         let info = self.get_xdsl_info().await?;
         let status = info.status.unwrap();
 
+        self.reset_xdsl();
+
+        let labels = [
+            status.status.unwrap_or_default(),
+            status.protocol.unwrap_or_default(),
+            status.modulation.unwrap_or_default(),
+        ];
+        let labels: Vec<&str> = labels.iter().map(|l| l.as_str()).collect();
+
         self.xdsl_status_uptime
-            .with_label_values(&[
-                &status.status.unwrap_or_default(),
-                &status.protocol.unwrap_or_default(),
-                &status.modulation.unwrap_or_default(),
-            ])
+            .with_label_values(&labels)
             .set(status.uptime.unwrap_or_default().into());
+        self.xdsl_info.with_label_values(&labels).set(1);
 
         let up = info.up.unwrap();
         let down = info.down.unwrap();
@@ -773,47 +1199,236 @@ impl<'a> ConnectionMetricMap<'a> {
             self.xdsl_stats_attn
                 .with_label_values(&[&stats.direction])
                 .set(stats.stats.attn.unwrap_or_default().into());
-            self.xdsl_stats_fec
-                .with_label_values(&[&stats.direction])
-                .set(stats.stats.fec.unwrap_or_default().into());
-            self.xdsl_stats_crc
-                .with_label_values(&[&stats.direction])
-                .set(stats.stats.crc.unwrap_or_default().into());
-            self.xdsl_stats_hec
-                .with_label_values(&[&stats.direction])
-                .set(stats.stats.hec.unwrap_or_default().into());
-            self.xdsl_stats_es
+            self.xdsl_stats_phyr
                 .with_label_values(&[&stats.direction])
-                .set(stats.stats.es.unwrap_or_default().into());
-            self.xdsl_stats_ses
+                .set(stats.stats.phyr.unwrap_or_default().into());
+            self.xdsl_stats_ginp
                 .with_label_values(&[&stats.direction])
-                .set(stats.stats.ses.unwrap_or_default().into());
-            self.xdsl_stats_rxmt
+                .set(stats.stats.ginp.unwrap_or_default().into());
+            self.xdsl_stats_nitro
                 .with_label_values(&[&stats.direction])
-                .set(stats.stats.rxmt.unwrap_or_default().into());
-            self.xdsl_stats_rxmt_corr
+                .set(stats.stats.nitro.unwrap_or_default().into());
+            self.xdsl_stats_rate_margin
                 .with_label_values(&[&stats.direction])
-                .set(stats.stats.rxmt_corr.unwrap_or_default().into());
-            self.xdsl_stats_rxmt_uncorr
-                .with_label_values(&[&stats.direction])
-                .set(stats.stats.rxmt_uncorr.unwrap_or_default().into());
-            self.xdsl_stats_rtx_tx
-                .with_label_values(&[&stats.direction])
-                .set(stats.stats.rtx_tx.unwrap_or_default().into());
-            self.xdsl_stats_rtx_c
-                .with_label_values(&[&stats.direction])
-                .set(stats.stats.rtx_c.unwrap_or_default().into());
-            self.xdsl_stats_rtx_uc
-                .with_label_values(&[&stats.direction])
-                .set(stats.stats.rtx_uc.unwrap_or_default().into());
+                .set(stats.stats.get_rate_margin());
+
+            if let Some(ratio) = stats.stats.get_rate_ratio_percent() {
+                self.xdsl_stats_rate_ratio
+                    .with_label_values(&[&stats.direction])
+                    .set(ratio);
+            }
+            set_counter(
+                &self.xdsl_stats_fec.with_label_values(&[&stats.direction]),
+                stats.stats.fec.unwrap_or_default().into(),
+            );
+            set_counter(
+                &self.xdsl_stats_crc.with_label_values(&[&stats.direction]),
+                stats.stats.crc.unwrap_or_default().into(),
+            );
+            set_counter(
+                &self.xdsl_stats_hec.with_label_values(&[&stats.direction]),
+                stats.stats.hec.unwrap_or_default().into(),
+            );
+            set_counter(
+                &self.xdsl_stats_es.with_label_values(&[&stats.direction]),
+                stats.stats.es.unwrap_or_default().into(),
+            );
+            set_counter(
+                &self.xdsl_stats_ses.with_label_values(&[&stats.direction]),
+                stats.stats.ses.unwrap_or_default().into(),
+            );
+            set_counter(
+                &self.xdsl_stats_rxmt.with_label_values(&[&stats.direction]),
+                stats.stats.rxmt.unwrap_or_default().into(),
+            );
+            set_counter(
+                &self
+                    .xdsl_stats_rxmt_corr
+                    .with_label_values(&[&stats.direction]),
+                stats.stats.rxmt_corr.unwrap_or_default().into(),
+            );
+            set_counter(
+                &self
+                    .xdsl_stats_rxmt_uncorr
+                    .with_label_values(&[&stats.direction]),
+                stats.stats.rxmt_uncorr.unwrap_or_default().into(),
+            );
+            set_counter(
+                &self
+                    .xdsl_stats_rtx_tx
+                    .with_label_values(&[&stats.direction]),
+                stats.stats.rtx_tx.unwrap_or_default().into(),
+            );
+            set_counter(
+                &self.xdsl_stats_rtx_c.with_label_values(&[&stats.direction]),
+                stats.stats.rtx_c.unwrap_or_default().into(),
+            );
+            set_counter(
+                &self
+                    .xdsl_stats_rtx_uc
+                    .with_label_values(&[&stats.direction]),
+                stats.stats.rtx_uc.unwrap_or_default().into(),
+            );
+        }
+
+        Ok(())
+    }
+
+    async fn get_lte_config(
+        &mut self,
+    ) -> Result<LteConfig, Box<dyn std::error::Error + Send + Sync>> {
+        debug!("fetching lte configuration");
+
+        let client = self.get_managed_client().await?;
+        let res = client
+            .get(format!("{}v4/connection/lte/config", self.factory.api_url))
+            .send()
+            .await?
+            .json::<FreeboxResponse<LteConfig>>()
+            .await?;
+
+        if !res.success.unwrap_or(false) {
+            return Err(Box::new(FreeboxResponseError::new(
+                res.msg.unwrap_or_default(),
+            )));
+        }
+
+        res.result.ok_or_else(|| {
+            Box::new(FreeboxResponseError::new(
+                "v4/connection/lte/config response was empty".to_string(),
+            )) as Box<dyn std::error::Error + Send + Sync>
+        })
+    }
+
+    async fn set_lte_status(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let lte = self.get_lte_config().await?;
+        let state = lte.state.unwrap_or_default();
+
+        self.reset_lte();
+
+        self.lte_enabled_metric
+            .set(lte.enabled.unwrap_or_default().into());
+        self.lte_state_metric
+            .with_label_values(&[&state])
+            .set((state == "connected").into());
+
+        if let Some(network) = lte.network {
+            self.lte_network_up_metric
+                .set(network.pdn_up.unwrap_or_default().into());
+        }
+
+        if let Some(radio) = lte.radio {
+            self.lte_operator_metric
+                .with_label_values(&[&radio.plmn.unwrap_or_default().to_string()])
+                .set(radio.associated.unwrap_or_default().into());
+
+            // disabled bands report zeroed measurements
+            for band in radio.bands.unwrap_or_default().iter() {
+                if !band.enabled.unwrap_or_default() {
+                    continue;
+                }
+
+                let name = band.band.unwrap_or_default().to_string();
+
+                self.lte_band_rsrp_metric
+                    .with_label_values(&[&name])
+                    .set(band.rsrp.unwrap_or_default());
+                self.lte_band_rsrq_metric
+                    .with_label_values(&[&name])
+                    .set(band.rsrq.unwrap_or_default());
+                self.lte_band_sinr_metric
+                    .with_label_values(&[&name])
+                    .set(band.sinr.unwrap_or_default());
+                self.lte_band_bandwidth_metric
+                    .with_label_values(&[&name])
+                    .set(band.bandwidth.unwrap_or_default() * LTE_BANDWIDTH_UNIT);
+            }
+        }
+
+        if let Some(tunnel) = lte.tunnel {
+            let lte_connected = tunnel.lte.and_then(|l| l.connected).unwrap_or_default();
+            let xdsl_connected = tunnel.xdsl.and_then(|l| l.connected).unwrap_or_default();
+
+            self.lte_tunnel_connected_metric
+                .with_label_values(&["lte"])
+                .set(lte_connected.into());
+            self.lte_tunnel_connected_metric
+                .with_label_values(&["xdsl"])
+                .set(xdsl_connected.into());
+            self.lte_aggregation_metric
+                .set((lte_connected && xdsl_connected).into());
         }
 
         Ok(())
     }
 
-    fn reset_all(&mut self) {
-        self.bytes_down_metric.set(0);
-        self.bytes_up_metric.set(0);
+    async fn get_connection_logs(
+        &mut self,
+    ) -> Result<Vec<ConnectionLogEntry>, Box<dyn std::error::Error + Send + Sync>> {
+        debug!("fetching connection logs");
+
+        let client = self.get_managed_client().await?;
+        let res = client
+            .get(format!("{}v4/connection/logs/", self.factory.api_url))
+            .send()
+            .await?
+            .json::<FreeboxResponse<Vec<ConnectionLogEntry>>>()
+            .await?;
+
+        if !res.success.unwrap_or(false) {
+            return Err(Box::new(FreeboxResponseError::new(
+                res.msg.unwrap_or_default(),
+            )));
+        }
+
+        // an empty log is given without result
+        Ok(res.result.unwrap_or_default())
+    }
+
+    /// xDSL and FTTH link events are both part of the connection log, short
+    /// drops between two refreshes are counted from there
+    async fn set_connection_logs(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let entries = self.get_connection_logs().await?;
+
+        let mut state = match self.log_state.take() {
+            Some(state) => state,
+            None => logs::load(&self.log_path).await,
+        };
+
+        let changed = state.update(&entries);
+
+        for (link_state, count) in state.transitions.iter() {
+            set_counter(
+                &self
+                    .link_transitions_metric
+                    .with_label_values(&[link_state]),
+                *count,
+            );
+        }
+        self.last_outage_metric
+            .set(state.last_outage.unwrap_or_default());
+        self.last_outage_duration_metric
+            .set(if state.down_since.is_some() {
+                0
+            } else {
+                state.last_outage_duration.unwrap_or_default()
+            });
+
+        let res = if changed {
+            logs::save(&self.log_path, &state).await
+        } else {
+            Ok(())
+        };
+        self.log_state = Some(state);
+
+        res
+    }
+
+    /// Each section is reset once its response was fetched, a failed call keeps
+    /// the values of the last successful refresh instead of exposing zeros
+    fn reset_status(&mut self) {
         self.rate_down_metric.set(0);
         self.rate_up_metric.set(0);
         self.bandwidth_down_metric.set(0);
@@ -823,6 +1438,9 @@ impl<'a> ConnectionMetricMap<'a> {
         self.state_metric.reset();
         self.ipv4_metric.reset();
         self.ipv6_metric.reset();
+    }
+
+    fn reset_conf(&mut self) {
         self.ping_metric.set(0);
         self.is_secure_pass_metric.set(0);
         self.remote_access_port_metric.set(0);
@@ -833,59 +1451,57 @@ impl<'a> ConnectionMetricMap<'a> {
         self.api_remote_access_metric.set(0);
         self.allow_token_request_metric.set(0);
         self.remote_access_ip_metric.reset();
+    }
+
+    fn reset_ipv6_conf(&mut self) {
         self.ipv6_enabled_metric.set(0);
         self.delegations_metric.reset();
+    }
+
+    fn reset_ftth(&mut self) {
         self.sfp_has_power_report_metric.set(0);
         self.sfp_has_signal_metric.set(0);
-        self.sfp_model_metric.reset();
-        self.sfp_vendor_metric.reset();
-        self.sfp_pwr_tx_metric.set(0);
-        self.sfp_pwr_rx_metric.set(0);
         self.link_metric.set(0);
         self.sfp_alim_ok_metric.set(0);
-        self.sfp_serial_metric.reset();
         self.sfp_present_metric.set(0);
-        self.ipv6_enabled_metric.set(0);
-        self.delegations_metric.reset();
-        self.sfp_has_power_report_metric.set(0);
-        self.sfp_has_signal_metric.set(0);
-        self.sfp_model_metric.reset();
-        self.sfp_vendor_metric.reset();
-        self.sfp_pwr_tx_metric.set(0);
-        self.sfp_pwr_rx_metric.set(0);
+        self.sfp_pwr_tx_dbm_metric.reset();
+        self.sfp_pwr_rx_dbm_metric.reset();
+        self.sfp_pwr_threshold_metric.reset();
+        self.pon_state_metric.reset();
+
+        if let Some(legacy_metrics) = &self.ftth_legacy_metrics {
+            legacy_metrics.reset();
+        }
+    }
+
+    fn reset_xdsl(&mut self) {
         self.xdsl_status_uptime.reset();
         self.xdsl_stats_maxrate.reset();
         self.xdsl_stats_rate.reset();
         self.xdsl_stats_snr.reset();
         self.xdsl_stats_attn.reset();
-        self.xdsl_stats_fec.reset();
-        self.xdsl_stats_crc.reset();
-        self.xdsl_stats_hec.reset();
-        self.xdsl_stats_es.reset();
-        self.xdsl_stats_ses.reset();
-        self.xdsl_stats_rxmt.reset();
-        self.xdsl_stats_rxmt_corr.reset();
-        self.xdsl_stats_rxmt_uncorr.reset();
-        self.xdsl_stats_rtx_tx.reset();
-        self.xdsl_stats_rtx_c.reset();
-        self.xdsl_stats_rtx_uc.reset();
+        self.xdsl_info.reset();
+        self.xdsl_stats_phyr.reset();
+        self.xdsl_stats_ginp.reset();
+        self.xdsl_stats_nitro.reset();
+        self.xdsl_stats_rate_margin.reset();
+        self.xdsl_stats_rate_ratio.reset();
     }
-}
-
-#[async_trait]
-impl<'a> MetricMap<'a> for ConnectionMetricMap<'a> {
-    async fn init(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let status = self.get_connection_status().await?;
-        let media = status.media.unwrap_or_default();
 
-        info!("exposing network media metrics: {}", media);
-        self.is_ftth = Some(media.trim().to_lowercase() == "ftth".to_string());
-        Ok(())
+    fn reset_lte(&mut self) {
+        self.lte_enabled_metric.set(0);
+        self.lte_state_metric.reset();
+        self.lte_network_up_metric.set(0);
+        self.lte_operator_metric.reset();
+        self.lte_band_rsrp_metric.reset();
+        self.lte_band_rsrq_metric.reset();
+        self.lte_band_sinr_metric.reset();
+        self.lte_band_bandwidth_metric.reset();
+        self.lte_tunnel_connected_metric.reset();
+        self.lte_aggregation_metric.set(0);
     }
 
-    async fn set(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        self.reset_all();
-
+    async fn set_line(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let status = self.get_connection_status().await?;
         self.set_connection_status(&status).await?;
 
@@ -893,21 +1509,58 @@ impl<'a> MetricMap<'a> for ConnectionMetricMap<'a> {
         self.set_connection_ipv6_conf().await?;
 
         let media = status.media.unwrap_or("unknown".to_string()).to_lowercase();
-        let is_ftth = media == "ftth";
+        let current = ConnectionMedia::from_status(&media);
 
-        if is_ftth != self.is_ftth.unwrap_or(true) {
+        if Some(current) != self.media {
             info!("network media has changed, now exposing metrics: {}", media);
-            self.is_ftth = Some(is_ftth);
+            self.media = Some(current);
         }
 
-        if is_ftth {
-            self.set_connection_ftth_status().await?;
-        } else {
-            self.set_xdsl_status().await?;
+        // the metrics of the other media are left over from a media change
+        match current {
+            ConnectionMedia::Ftth => {
+                self.reset_xdsl();
+                self.set_connection_ftth_status().await?
+            }
+            ConnectionMedia::Xdsl => {
+                self.reset_ftth();
+                self.set_xdsl_status().await?
+            }
+            // the line is down, only the LTE side is left
+            ConnectionMedia::Lte => {
+                self.reset_ftth();
+                self.reset_xdsl();
+            }
         }
 
         Ok(())
     }
 }
 
+#[async_trait]
+impl<'a> MetricMap<'a> for ConnectionMetricMap<'a> {
+    async fn init(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let status = self.get_connection_status().await?;
+        let media = status.media.unwrap_or_default();
+
+        info!("exposing network media metrics: {}", media);
+        self.media = Some(ConnectionMedia::from_status(&media));
+        Ok(())
+    }
+
+    /// The LTE status and the connection log do not depend on the line
+    /// status, a failing API does not stop the outage accounting
+    async fn set(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let line_res = self.set_line().await;
+        let lte_res = if self.has_lte {
+            self.set_lte_status().await
+        } else {
+            Ok(())
+        };
+        let logs_res = self.set_connection_logs().await;
+
+        merge_results(vec![line_res, lte_res, logs_res])
+    }
+}
+
 
@@ -0,0 +1,120 @@
+use std::{collections::BTreeMap, path::Path};
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use super::models::ConnectionLogEntry;
+
+const LINK_EVENT: &str = "link";
+const LINK_DOWN: &str = "down";
+const LINK_UP: &str = "up";
+
+/// Link events already processed and the resulting history, persisted under
+/// the data directory so a restart does not count the log again
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct ConnectionLogState {
+    /// Log ids are incremental, entries up to this one are processed
+    pub last_id: Option<i64>,
+    /// Link transitions per reached state (up, down)
+    pub transitions: BTreeMap<String, i64>,
+    /// Start of the ongoing outage, if the link is still down
+    pub down_since: Option<i64>,
+    pub last_outage: Option<i64>,
+    pub last_outage_duration: Option<i64>,
+}
+
+impl ConnectionLogState {
+    /// Processes the entries not seen yet, returns whether the state changed.
+    /// Log ids restart after a reboot of the box, a log whose ids are all lower
+    /// than the last processed one is a new log, processed from its start
+    pub fn update(&mut self, entries: &[ConnectionLogEntry]) -> bool {
+        let max_id = entries.iter().filter_map(|e| e.id).max();
+
+        if max_id.is_some() && max_id < self.last_id {
+            info!(
+                "connection log ids restarted at {}, processing the new log",
+                max_id.unwrap_or_default()
+            );
+            self.last_id = None;
+        }
+
+        let mut entries: Vec<&ConnectionLogEntry> = entries
+            .iter()
+            .filter(|e| e.id.is_some() && e.id > self.last_id)
+            .collect();
+
+        if entries.is_empty() {
+            return false;
+        }
+
+        entries.sort_by_key(|e| e.id);
+
+        for entry in entries.iter() {
+            self.last_id = entry.id;
+
+            if entry._type.as_deref() != Some(LINK_EVENT) {
+                continue;
+            }
+
+            let state = entry.state.as_deref().unwrap_or_default();
+            let date = entry.date.unwrap_or_default();
+
+            // repeated events belong to the same outage or uptime, only the
+            // events changing the link state are transitions
+            match state {
+                LINK_DOWN => {
+                    if self.down_since.is_some() {
+                        continue;
+                    }
+
+                    self.down_since = Some(date);
+                    self.last_outage = Some(date);
+                }
+                LINK_UP => {
+                    let Some(since) = self.down_since.take() else {
+                        continue;
+                    };
+
+                    self.last_outage_duration = Some((date - since).max(0));
+                }
+                _ => continue,
+            }
+
+            *self.transitions.entry(state.to_string()).or_default() += 1;
+        }
+
+        true
+    }
+}
+
+pub async fn load(path: &Path) -> ConnectionLogState {
+    let content = match tokio::fs::read_to_string(path).await {
+        Ok(r) => r,
+        Err(_) => {
+            info!(
+                "no connection log state found at {}, counting the whole connection log",
+                path.display()
+            );
+            return ConnectionLogState::default();
+        }
+    };
+
+    serde_json::from_str::<ConnectionLogState>(&content).unwrap_or_else(|e| {
+        warn!(
+            "cannot read connection log state {}, starting over: {}",
+            path.display(),
+            e
+        );
+        ConnectionLogState::default()
+    })
+}
+
+pub async fn save(
+    path: &Path,
+    state: &ConnectionLogState,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let content = serde_json::to_string_pretty(state)?;
+    tokio::fs::write(path, content).await?;
+
+    Ok(())
+}
@@ -54,6 +54,32 @@ pub struct ConnectionFtth {
     pub sfp_alim_ok: Option<bool>,
     pub sfp_serial: Option<String>,
     pub sfp_present: Option<bool>,
+    /// PON technology (gpon, epon_10g, xgspon), given by newer firmwares
+    pub link_type: Option<String>,
+    /// PON diagnostics, given by Freebox Ultra 10G-EPON and XGS-PON modules
+    pub pon: Option<PonStatus>,
+}
+
+/// SFP optical power is given in hundredths of dBm
+const SFP_POWER_UNIT: f64 = 100.0;
+
+impl ConnectionFtth {
+    pub fn get_pwr_tx_dbm(&self) -> Option<f64> {
+        self.sfp_pwr_tx.map(|p| p as f64 / SFP_POWER_UNIT)
+    }
+
+    pub fn get_pwr_rx_dbm(&self) -> Option<f64> {
+        self.sfp_pwr_rx.map(|p| p as f64 / SFP_POWER_UNIT)
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct PonStatus {
+    /// ONU activation state, from O1 (initial) to O5 (operation)
+    pub onu_state: Option<String>,
+    pub bip_errors: Option<i64>,
+    pub fec_corrected: Option<i64>,
+    pub fec_uncorrected: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -88,7 +114,76 @@ pub struct XdslStats {
     pub rtx_tx: Option<i32>,
     pub rtx_c: Option<i32>,
     pub rtx_uc: Option<i32>,
-    // pub phyr: Option<bool>,
-    // pub ginp: Option<bool>,
-    // pub nitro: Option<bool>,
-}
\ No newline at end of file
+    pub phyr: Option<bool>,
+    pub ginp: Option<bool>,
+    pub nitro: Option<bool>,
+}
+
+impl XdslStats {
+    /// Rate headroom in kbit/s, what the line could still gain on resync
+    pub fn get_rate_margin(&self) -> i64 {
+        (self.maxrate.unwrap_or_default() - self.rate.unwrap_or_default()).max(0)
+    }
+
+    /// Synchronized rate relative to the maximum attainable one, unknown
+    /// while the line is not synchronized
+    pub fn get_rate_ratio_percent(&self) -> Option<i64> {
+        let maxrate = self.maxrate.filter(|m| *m > 0)?;
+
+        Some(self.rate.unwrap_or_default() * 100 / maxrate)
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct LteConfig {
+    pub enabled: Option<bool>,
+    pub state: Option<String>,
+    pub network: Option<LteNetwork>,
+    pub radio: Option<LteRadio>,
+    pub tunnel: Option<LteTunnel>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct LteNetwork {
+    pub pdn_up: Option<bool>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct LteRadio {
+    pub associated: Option<bool>,
+    pub plmn: Option<i64>,
+    pub bands: Option<Vec<LteBand>>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct LteBand {
+    pub band: Option<i64>,
+    pub enabled: Option<bool>,
+    /// Carrier bandwidth in MHz
+    pub bandwidth: Option<i64>,
+    pub rsrp: Option<i64>,
+    pub rsrq: Option<f64>,
+    pub sinr: Option<f64>,
+}
+
+/// Aggregation tunnel, each link is bonded when connected
+#[derive(Deserialize, Clone, Debug)]
+pub struct LteTunnel {
+    pub lte: Option<LteTunnelLink>,
+    pub xdsl: Option<LteTunnelLink>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct LteTunnelLink {
+    pub connected: Option<bool>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ConnectionLogEntry {
+    pub id: Option<i64>,
+    pub date: Option<i64>,
+    /// Either `link` for the physical link or `conn` for the IP session
+    #[serde(alias = "type")]
+    pub _type: Option<String>,
+    pub state: Option<String>,
+}
@@ -1,6 +1,6 @@
 #[cfg(test)]
 mod tests {
-    use crate::{core::common::transport::FreeboxResponse, mappers::{api_specs_provider::get_specs_data, connection::models::XdslInfo}};
+    use crate::{core::common::transport::FreeboxResponse, mappers::{api_specs_provider::get_specs_data, connection::{logs::ConnectionLogState, models::{ConnectionFtth, ConnectionLogEntry, LteConfig, XdslInfo}, ConnectionMedia, SfpThresholds}}};
     use serde_json::from_str;
     
     #[tokio::test]
@@ -17,4 +17,141 @@ mod tests {
 
         assert!(data.is_ok());
     }
+
+    #[tokio::test]
+    async fn deserialize_api_v4_connection_lte_config() {
+        let json_data = get_specs_data("connection", "api_v4_connection_lte_config-get")
+            .await
+            .unwrap();
+
+        let lte = from_str::<FreeboxResponse<LteConfig>>(&json_data)
+            .unwrap()
+            .result
+            .unwrap();
+        let radio = lte.radio.unwrap();
+        let bands = radio.bands.unwrap();
+
+        assert_eq!(Some(20815), radio.plmn);
+        assert_eq!(Some(-96), bands[0].rsrp);
+        assert_eq!(Some(-11.5), bands[0].rsrq);
+        assert_eq!(Some(13.2), bands[0].sinr);
+        assert_eq!(Some(false), bands[2].enabled);
+        assert_eq!(Some(true), lte.tunnel.unwrap().lte.unwrap().connected);
+    }
+
+    #[test]
+    fn detect_lte_fallback_media() {
+        assert_eq!(ConnectionMedia::Ftth, ConnectionMedia::from_status("FTTH"));
+        assert_eq!(ConnectionMedia::Xdsl, ConnectionMedia::from_status("xdsl"));
+        assert_eq!(ConnectionMedia::Lte, ConnectionMedia::from_status("backup_4g"));
+    }
+
+    #[tokio::test]
+    async fn count_connection_log_transitions_once() {
+        let json_data = get_specs_data("connection", "api_v4_connection_logs-get")
+            .await
+            .unwrap();
+
+        let entries = from_str::<FreeboxResponse<Vec<ConnectionLogEntry>>>(&json_data)
+            .unwrap()
+            .result
+            .unwrap();
+        let mut state = ConnectionLogState::default();
+
+        assert!(state.update(&entries));
+        assert!(!state.update(&entries));
+
+        // the first up event does not leave a known down state
+        assert_eq!(Some(&1), state.transitions.get("up"));
+        assert_eq!(Some(&1), state.transitions.get("down"));
+        assert_eq!(Some(1760780336), state.last_outage);
+        assert_eq!(Some(65), state.last_outage_duration);
+        assert_eq!(None, state.down_since);
+        assert_eq!(Some(118), state.last_id);
+
+        // after a reboot the ids restart below the last processed one
+        assert!(state.update(&entries[3..]));
+        assert_eq!(Some(115), state.last_id);
+        assert_eq!(Some(&1), state.transitions.get("up"));
+        assert_eq!(Some(&2), state.transitions.get("down"));
+        assert!(state.down_since.is_some());
+    }
+
+    #[test]
+    fn count_repeated_link_events_once() {
+        let entries: Vec<ConnectionLogEntry> = from_str(
+            r#"[
+                {"id": 1, "date": 100, "type": "link", "state": "down"},
+                {"id": 2, "date": 110, "type": "link", "state": "down"},
+                {"id": 3, "date": 160, "type": "link", "state": "up"},
+                {"id": 4, "date": 170, "type": "link", "state": "up"}
+            ]"#,
+        )
+        .unwrap();
+        let mut state = ConnectionLogState::default();
+
+        assert!(state.update(&entries));
+
+        assert_eq!(Some(&1), state.transitions.get("down"));
+        assert_eq!(Some(&1), state.transitions.get("up"));
+        assert_eq!(Some(100), state.last_outage);
+        assert_eq!(Some(60), state.last_outage_duration);
+    }
+
+    #[tokio::test]
+    async fn derive_xdsl_line_figures() {
+        for (endpoint, modulation, ratio) in [
+            ("api_v4_connection_xdsl-get", "adsl", 91),
+            ("api_v4_connection_xdsl_vdsl2-get", "vdsl", 74),
+            ("api_v4_connection_xdsl_gfast-get", "gfast", 97),
+        ] {
+            let json_data = get_specs_data("connection", endpoint).await.unwrap();
+            let info = from_str::<FreeboxResponse<XdslInfo>>(&json_data)
+                .unwrap()
+                .result
+                .unwrap();
+            let down = info.down.unwrap();
+
+            assert_eq!(Some(modulation), info.status.unwrap().modulation.as_deref());
+            assert_eq!(Some(ratio), down.get_rate_ratio_percent());
+        }
+
+        let json_data = get_specs_data("connection", "api_v4_connection_xdsl_gfast-get")
+            .await
+            .unwrap();
+        let info = from_str::<FreeboxResponse<XdslInfo>>(&json_data)
+            .unwrap()
+            .result
+            .unwrap();
+        let down = info.down.unwrap();
+
+        assert_eq!(14324, down.get_rate_margin());
+        assert_eq!(Some(true), down.ginp);
+        assert_eq!(None, info.up.unwrap().get_rate_ratio_percent());
+    }
+
+    #[tokio::test]
+    async fn convert_ftth_optical_power() {
+        let mut powers = vec![];
+
+        for endpoint in [
+            "api_v4_connection_ftth-get",
+            "api_v4_connection_ftth_epon10g-get",
+            "api_v4_connection_ftth_xgspon-get",
+        ] {
+            let json_data = get_specs_data("connection", endpoint).await.unwrap();
+            let ftth = from_str::<FreeboxResponse<ConnectionFtth>>(&json_data)
+                .unwrap()
+                .result
+                .unwrap();
+
+            powers.push((ftth.get_pwr_rx_dbm().unwrap(), ftth.pon.is_some()));
+        }
+
+        assert_eq!(vec![(-17.34, false), (-15.62, true), (-29.31, true)], powers);
+
+        // below the default class B+ sensitivity
+        assert!(powers[2].0 < SfpThresholds::default().rx_low);
+        assert_eq!(SfpThresholds::default(), SfpThresholds::from_conf(None));
+    }
 }
\ No newline at end of file
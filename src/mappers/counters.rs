@@ -0,0 +1,37 @@
+use prometheus_exporter::prometheus::IntCounter;
+
+/// Mirrors a cumulative value read from the Freebox API into a counter.
+/// Counters cannot be set, so the difference with the last value is added,
+/// a lower value means the counter was reset on the box side (e.g. reboot)
+pub fn set_counter(counter: &IntCounter, value: i64) {
+    let value = value.max(0) as u64;
+    let current = counter.get();
+
+    if value < current {
+        counter.reset();
+        counter.inc_by(value);
+    } else {
+        counter.inc_by(value - current);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_follow_cumulative_value() {
+        let counter = IntCounter::new("test_bytes_total", "test").unwrap();
+
+        set_counter(&counter, 100);
+        set_counter(&counter, 150);
+        assert_eq!(150, counter.get());
+
+        // box counter was reset
+        set_counter(&counter, 20);
+        assert_eq!(20, counter.get());
+
+        set_counter(&counter, -1);
+        assert_eq!(0, counter.get());
+    }
+}
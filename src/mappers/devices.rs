@@ -0,0 +1,477 @@
+use std::{
+    collections::BTreeMap,
+    net::Ipv4Addr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use prometheus_exporter::prometheus::{register_int_gauge_vec, IntGaugeVec};
+
+use super::{
+    dhcp::DhcpLease,
+    lanbrowser::LanHost,
+    presence::PresenceTracker,
+    series::TrackedVec,
+    switch::SwitchPortStatus,
+    wifi::models::{AccessPoint, Station},
+    MetricMap,
+};
+
+/// Wifi station bitrates are given in 100 kbit/s
+const WIFI_BITRATE_UNIT: i64 = 100_000;
+/// Switch port speeds are given in Mbit/s
+const SWITCH_SPEED_UNIT: i64 = 1_000_000;
+
+#[derive(Clone, Debug)]
+struct Lease {
+    mac: Option<String>,
+    hostname: Option<String>,
+    ip: Option<String>,
+    is_static: bool,
+}
+
+/// Responses fetched by the lan browser, wifi, dhcp and switch maps during the
+/// current refresh, the devices view is joined from them instead of querying
+/// the box a second time. A source stays empty when its map is disabled or
+/// its call failed
+#[derive(Default)]
+pub struct DeviceSources {
+    browser_hosts: Option<Vec<LanHost>>,
+    stations: Option<Vec<(AccessPoint, Vec<Station>)>>,
+    leases: Option<Vec<Lease>>,
+    ports: Option<Vec<SwitchPortStatus>>,
+}
+
+pub type SharedDeviceSources = Arc<Mutex<DeviceSources>>;
+
+impl DeviceSources {
+    pub fn add_browser_hosts(&mut self, hosts: &[LanHost]) {
+        self.browser_hosts
+            .get_or_insert_with(Vec::new)
+            .extend_from_slice(hosts);
+    }
+
+    pub fn add_stations(&mut self, stations: &[Station], ap: &AccessPoint) {
+        self.stations
+            .get_or_insert_with(Vec::new)
+            .push((ap.to_owned(), stations.to_vec()));
+    }
+
+    pub fn add_leases(&mut self, leases: &[Box<dyn DhcpLease>]) {
+        self.leases
+            .get_or_insert_with(Vec::new)
+            .extend(leases.iter().map(|lease| Lease {
+                mac: lease.get_mac(),
+                hostname: lease.get_hostname(),
+                ip: lease.get_ip(),
+                is_static: lease.get_is_static().unwrap_or(false),
+            }));
+    }
+
+    pub fn add_ports(&mut self, ports: &[SwitchPortStatus]) {
+        self.ports
+            .get_or_insert_with(Vec::new)
+            .extend_from_slice(ports);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.browser_hosts.is_none()
+            && self.stations.is_none()
+            && self.leases.is_none()
+            && self.ports.is_none()
+    }
+}
+
+/// What the box APIs know about a device, gathered from every source
+#[derive(Default, Debug)]
+struct Device {
+    hostname: String,
+    ipv4: String,
+    medium: String,
+    band: String,
+    access_point: String,
+    switch_port: String,
+    dhcp: String,
+    vendor: String,
+    connected: bool,
+    signal: Option<i64>,
+    link_speed: Option<i64>,
+}
+
+impl Device {
+    fn set_hostname(&mut self, hostname: Option<&String>) {
+        if let Some(hostname) = hostname.filter(|h| !h.is_empty()) {
+            if self.hostname.is_empty() {
+                self.hostname = hostname.to_owned();
+            }
+        }
+    }
+
+    /// Only the primary IPv4 address is kept, the first one a source gives
+    fn set_ipv4(&mut self, ip: Option<&String>) {
+        if let Some(ip) = ip.filter(|ip| ip.parse::<Ipv4Addr>().is_ok()) {
+            if self.ipv4.is_empty() {
+                self.ipv4 = ip.to_owned();
+            }
+        }
+    }
+}
+
+/// Devices joined by MAC address, sources are merged in the order they are
+/// added, the first non empty hostname wins
+#[derive(Default)]
+struct DeviceTable {
+    devices: BTreeMap<String, Device>,
+}
+
+impl DeviceTable {
+    /// A failed source only leaves its columns empty for this refresh
+    fn from_sources(sources: &DeviceSources) -> Self {
+        let mut table = DeviceTable::default();
+
+        if let Some(hosts) = sources.browser_hosts.as_deref() {
+            table.add_browser_hosts(hosts);
+        }
+
+        for (ap, stations) in sources.stations.as_deref().unwrap_or_default() {
+            table.add_stations(stations, ap);
+        }
+
+        if let Some(leases) = sources.leases.as_deref() {
+            table.add_leases(leases);
+        }
+
+        if let Some(ports) = sources.ports.as_deref() {
+            table.add_ports(ports);
+        }
+
+        table
+    }
+
+    fn get(&mut self, mac: Option<&String>) -> Option<&mut Device> {
+        let mac = mac.filter(|m| !m.is_empty())?.to_lowercase();
+
+        Some(self.devices.entry(mac).or_default())
+    }
+
+    fn add_browser_hosts(&mut self, hosts: &[LanHost]) {
+        for host in hosts.iter() {
+            let mac = host.l2ident.as_ref().and_then(|l| l.id.as_ref());
+            let Some(device) = self.get(mac) else {
+                continue;
+            };
+
+            device.set_hostname(host.primary_name.as_ref());
+            device.connected |= host.active.unwrap_or(false);
+
+            if device.vendor.is_empty() {
+                device.vendor = host.vendor_name.to_owned().unwrap_or_default();
+            }
+
+            for l3 in host.l3connectivities.as_deref().unwrap_or_default() {
+                if l3.active.unwrap_or(false) {
+                    device.set_ipv4(l3.addr.as_ref());
+                }
+            }
+        }
+    }
+
+    fn add_stations(&mut self, stations: &[Station], ap: &AccessPoint) {
+        for station in stations.iter() {
+            let Some(device) = self.get(station.mac.as_ref()) else {
+                continue;
+            };
+
+            device.medium = "wifi".to_string();
+            device.band = ap
+                .config
+                .as_ref()
+                .and_then(|c| c.band.to_owned())
+                .unwrap_or_default();
+            device.access_point = ap.name.to_owned().unwrap_or_default();
+            device.connected = true;
+            device.signal = station.signal.map(|s| s.into());
+            device.link_speed = station
+                .last_rx
+                .as_ref()
+                .and_then(|r| r.bitrate)
+                .map(|b| b as i64 * WIFI_BITRATE_UNIT);
+
+            if let Some(host) = station.host.as_ref() {
+                device.set_hostname(host.primary_name.as_ref());
+
+                if device.vendor.is_empty() {
+                    device.vendor = host.vendor_name.to_owned().unwrap_or_default();
+                }
+
+                for l3 in host.l3connectivities.as_deref().unwrap_or_default() {
+                    if l3.active.unwrap_or(false) {
+                        device.set_ipv4(l3.addr.as_ref());
+                    }
+                }
+            }
+        }
+    }
+
+    fn add_leases(&mut self, leases: &[Lease]) {
+        for lease in leases.iter() {
+            let Some(device) = self.get(lease.mac.as_ref()) else {
+                continue;
+            };
+
+            device.set_hostname(lease.hostname.as_ref());
+            device.set_ipv4(lease.ip.as_ref());
+
+            if lease.is_static {
+                device.dhcp = "static".to_string();
+            } else if device.dhcp.is_empty() {
+                device.dhcp = "dynamic".to_string();
+            }
+        }
+    }
+
+    fn add_ports(&mut self, ports: &[SwitchPortStatus]) {
+        for port in ports.iter() {
+            for host in port.mac_list.as_deref().unwrap_or_default() {
+                let Some(device) = self.get(host.mac.as_ref()) else {
+                    continue;
+                };
+
+                device.set_hostname(host.hostname.as_ref());
+                device.medium = "ethernet".to_string();
+                device.switch_port = port.id.unwrap_or_default().to_string();
+                device.connected = true;
+                device.link_speed = port
+                    .speed
+                    .as_ref()
+                    .and_then(|s| s.parse::<i64>().ok())
+                    .map(|s| s * SWITCH_SPEED_UNIT);
+            }
+        }
+    }
+}
+
+pub struct DevicesMetricMap {
+    sources: SharedDeviceSources,
+    info_gauge: TrackedVec<IntGaugeVec>,
+    connected_gauge: TrackedVec<IntGaugeVec>,
+    signal_gauge: TrackedVec<IntGaugeVec>,
+    link_speed_gauge: TrackedVec<IntGaugeVec>,
+    presence: Option<PresenceTracker>,
+}
+
+impl DevicesMetricMap {
+    /// The sources are filled by the other maps, this map must be registered
+    /// after them so it reads the responses of the same refresh
+    pub fn new(
+        sources: SharedDeviceSources,
+        prefix: String,
+        stale_series_grace: Duration,
+        presence: Option<PresenceTracker>,
+    ) -> Self {
+        let prfx = format!("{prefix}_device");
+
+        Self {
+            sources,
+            info_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_info"),
+                    "Device seen by the box joined by MAC address, always 1",
+                    &[
+                        "mac",
+                        "hostname",
+                        "ipv4",
+                        "medium",
+                        "band",
+                        "access_point",
+                        "switch_port",
+                        "dhcp",
+                        "vendor"
+                    ]
+                )
+                .expect(&format!("cannot create {prfx}_info gauge")),
+                stale_series_grace,
+                &[0],
+            ),
+            connected_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_connected"),
+                    "Device connection state, 1 when connected",
+                    &["mac", "hostname"]
+                )
+                .expect(&format!("cannot create {prfx}_connected gauge")),
+                stale_series_grace,
+                &[0],
+            ),
+            signal_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_signal_dbm"),
+                    "Wifi device signal strength in dBm",
+                    &["mac", "hostname"]
+                )
+                .expect(&format!("cannot create {prfx}_signal_dbm gauge")),
+                stale_series_grace,
+                &[0],
+            ),
+            link_speed_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_link_speed_bits_per_second"),
+                    "Device link speed, wifi receive bitrate or switch port speed",
+                    &["mac", "hostname", "medium"]
+                )
+                .expect(&format!(
+                    "cannot create {prfx}_link_speed_bits_per_second gauge"
+                )),
+                stale_series_grace,
+                &[0],
+            ),
+            presence,
+        }
+    }
+
+    fn set_gauges(&self, table: &DeviceTable) {
+        for (mac, device) in table.devices.iter() {
+            self.info_gauge
+                .with_label_values(&[
+                    mac,
+                    &device.hostname,
+                    &device.ipv4,
+                    &device.medium,
+                    &device.band,
+                    &device.access_point,
+                    &device.switch_port,
+                    &device.dhcp,
+                    &device.vendor,
+                ])
+                .set(1);
+
+            self.connected_gauge
+                .with_label_values(&[mac, &device.hostname])
+                .set(device.connected.into());
+
+            if let Some(signal) = device.signal {
+                self.signal_gauge
+                    .with_label_values(&[mac, &device.hostname])
+                    .set(signal);
+            }
+
+            if let Some(link_speed) = device.link_speed {
+                self.link_speed_gauge
+                    .with_label_values(&[mac, &device.hostname, &device.medium])
+                    .set(link_speed);
+            }
+        }
+    }
+
+    async fn set_all(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        // the sources are taken so the next refresh starts empty
+        let sources = std::mem::take(&mut *self.sources.lock().unwrap());
+        let table = DeviceTable::from_sources(&sources);
+
+        self.set_gauges(&table);
+
+        // without any answer from the box every device would look departed
+        if sources.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(presence) = self.presence.as_mut() {
+            let seen: Vec<(String, String)> = table
+                .devices
+                .iter()
+                .filter(|(_, device)| device.connected)
+                .map(|(mac, device)| (mac.to_owned(), device.hostname.to_owned()))
+                .collect();
+
+            presence.track(&seen).await?;
+        }
+
+        Ok(())
+    }
+
+    fn sweep_all(&self) {
+        self.info_gauge.sweep();
+        self.connected_gauge.sweep();
+        self.signal_gauge.sweep();
+        self.link_speed_gauge.sweep();
+    }
+}
+
+#[async_trait]
+impl<'a> MetricMap<'a> for DevicesMetricMap {
+    async fn init(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+    }
+
+    async fn set(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let res = self.set_all().await;
+
+        // devices gone from every source are removed once their grace expires
+        self.sweep_all();
+
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::from_str;
+
+    use super::*;
+    use crate::mappers::{
+        api_specs_provider::get_result,
+        dhcp::{DynamicDhcpLease, StaticDhcpLease},
+    };
+
+    #[tokio::test]
+    async fn should_join_sources_by_mac() {
+        let aps: Vec<AccessPoint> = get_result("wifi", "api_v2_wifi_ap").await;
+        let stations: Vec<Station> = get_result("wifi", "api_v2_wifi_ap_0_stations-get").await;
+        let statics: Vec<StaticDhcpLease> =
+            get_result("dhcp", "api_v2_dhcp_static_lease-get").await;
+        let dynamics: Vec<DynamicDhcpLease> =
+            get_result("dhcp", "api_v2_dhcp_dynamic_lease-get").await;
+        let ports: Vec<SwitchPortStatus> = from_str(
+            r#"[{"id": 1, "speed": "1000", "mac_list": [{"mac": "00:11:22:33:44:55", "hostname": "nas"}]}]"#,
+        )
+        .unwrap();
+
+        let mut leases: Vec<Box<dyn DhcpLease>> = vec![];
+
+        for lease in dynamics {
+            leases.push(Box::new(lease));
+        }
+
+        for lease in statics {
+            leases.push(Box::new(lease));
+        }
+
+        let mut sources = DeviceSources::default();
+        sources.add_stations(&stations, &aps[0]);
+        sources.add_leases(&leases);
+        sources.add_ports(&ports);
+
+        let table = DeviceTable::from_sources(&sources);
+
+        let mac = stations[0].mac.to_owned().unwrap().to_lowercase();
+
+        let station = table.devices.get(&mac).unwrap();
+        assert_eq!("wifi", station.medium);
+        assert!(station.connected);
+        assert_eq!(Some(110 * WIFI_BITRATE_UNIT), station.link_speed);
+        assert!(station.signal.is_some());
+
+        let nas = table.devices.get("00:11:22:33:44:55").unwrap();
+        assert_eq!("nas", nas.hostname);
+        assert_eq!("ethernet", nas.medium);
+        assert_eq!("1", nas.switch_port);
+        assert_eq!(Some(1_000_000_000), nas.link_speed);
+
+        // the fixture addresses are masked, they are not kept as IPv4 addresses
+        assert!(table
+            .devices
+            .values()
+            .any(|d| d.dhcp == "static" && !d.connected && d.ipv4.is_empty()));
+    }
+}
@@ -4,14 +4,15 @@ use log::debug;
 use prometheus_exporter::prometheus::{register_int_gauge_vec, IntGaugeVec};
 use reqwest::Client;
 use serde::Deserialize;
+use std::time::Duration;
 
 use crate::core::common::http_client_factory::{AuthenticatedHttpClientFactory, ManagedHttpClient};
 use crate::core::common::transport::FreeboxResponse;
 
-use crate::mappers::MetricMap;
+use crate::mappers::{devices::SharedDeviceSources, series::TrackedVec, MetricMap};
 
 #[derive(Debug, Deserialize, Clone)]
-struct StaticDhcpLease {
+pub struct StaticDhcpLease {
     id: Option<String>,
     hostname: Option<String>,
     ip: Option<String>,
@@ -19,7 +20,7 @@ struct StaticDhcpLease {
 }
 
 #[derive(Debug, Deserialize, Clone)]
-struct DynamicDhcpLease {
+pub struct DynamicDhcpLease {
     id: Option<String>,
     hostname: Option<String>,
     ip: Option<String>,
@@ -29,7 +30,7 @@ struct DynamicDhcpLease {
     refresh_time: Option<u64>,
 }
 
-trait DhcpLease: std::fmt::Debug + Send {
+pub trait DhcpLease: std::fmt::Debug + Send {
     fn get_id(&self) -> Option<String>;
     fn get_hostname(&self) -> Option<String>;
     fn get_ip(&self) -> Option<String>;
@@ -111,38 +112,61 @@ impl DhcpLease for DynamicDhcpLease {
 pub struct DhcpMetricMap<'a> {
     factory: &'a AuthenticatedHttpClientFactory<'a>,
     managed_client: Option<ManagedHttpClient>,
-    lease_remaining_gauge: IntGaugeVec,
-    refresh_time_gauge: IntGaugeVec,
-    assign_time_gauge: IntGaugeVec,
+    device_sources: Option<SharedDeviceSources>,
+    lease_remaining_gauge: TrackedVec<IntGaugeVec>,
+    refresh_time_gauge: TrackedVec<IntGaugeVec>,
+    assign_time_gauge: TrackedVec<IntGaugeVec>,
 }
 
 impl<'a> DhcpMetricMap<'a> {
-    pub fn new(factory: &'a AuthenticatedHttpClientFactory<'a>, prefix: String) -> Self {
+    pub fn new(
+        factory: &'a AuthenticatedHttpClientFactory<'a>,
+        prefix: String,
+        stale_series_grace: Duration,
+        device_sources: Option<SharedDeviceSources>,
+    ) -> Self {
         let prfx: String = format!("{prefix}_dhcp");
 
         Self {
             factory,
             managed_client: None,
-            lease_remaining_gauge: register_int_gauge_vec!(
-                format!("{prfx}_lease_remaining",),
-                "Lease remaining time in milliseconds".to_string(),
-                &["id", "hostname", "ip", "mac", "is_static"],
-            )
-            .expect(&format!(
-                "Failed to create gauge for {prfx}_lease_remaining"
-            )),
-            refresh_time_gauge: register_int_gauge_vec!(
-                format!("{prfx}_refresh_time"),
-                "Refresh time in milliseconds".to_string(),
-                &["id", "hostname", "ip", "mac", "is_static"],
-            )
-            .expect(&format!("Failed to create gauge for {prfx}_refresh_time")),
-            assign_time_gauge: register_int_gauge_vec!(
-                format!("{prfx}_assign_time"),
-                "Assign time in milliseconds".to_string(),
-                &["id", "hostname", "ip", "mac", "is_static"],
-            )
-            .expect(&format!("Failed to create gauge for {prfx}_assign_time")),
+            device_sources,
+            lease_remaining_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_lease_remaining_seconds"),
+                    "DHCP lease remaining time in seconds, -1 for static leases",
+                    &["id", "hostname", "ip", "mac", "is_static"],
+                )
+                .expect(&format!(
+                    "Failed to create gauge for {prfx}_lease_remaining_seconds"
+                )),
+                stale_series_grace,
+                &[0],
+            ),
+            refresh_time_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_refresh_timestamp_seconds"),
+                    "DHCP lease last refresh as a unix timestamp, -1 for static leases",
+                    &["id", "hostname", "ip", "mac", "is_static"],
+                )
+                .expect(&format!(
+                    "Failed to create gauge for {prfx}_refresh_timestamp_seconds"
+                )),
+                stale_series_grace,
+                &[0],
+            ),
+            assign_time_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_assign_timestamp_seconds"),
+                    "DHCP lease assignment as a unix timestamp, 0 for static leases",
+                    &["id", "hostname", "ip", "mac", "is_static"],
+                )
+                .expect(&format!(
+                    "Failed to create gauge for {prfx}_assign_timestamp_seconds"
+                )),
+                stale_series_grace,
+                &[0],
+            ),
         }
     }
 
@@ -289,6 +313,10 @@ impl<'a> DhcpMetricMap<'a> {
 
         let leases = leases.unwrap();
 
+        if let Some(sources) = self.device_sources.as_ref() {
+            sources.lock().unwrap().add_leases(&leases);
+        }
+
         for lease in leases {
             self.lease_remaining_gauge
                 .with_label_values(&[
@@ -324,19 +352,22 @@ impl<'a> DhcpMetricMap<'a> {
         Ok(())
     }
 
-    fn reset_all(&self) {
-        self.lease_remaining_gauge.reset();
-        self.refresh_time_gauge.reset();
-        self.assign_time_gauge.reset();
+    fn sweep_all(&self) {
+        self.lease_remaining_gauge.sweep();
+        self.refresh_time_gauge.sweep();
+        self.assign_time_gauge.sweep();
     }
 }
 
 #[async_trait]
 impl<'a> MetricMap<'a> for DhcpMetricMap<'a> {
     async fn set(&mut self) -> Result<(), Box<dyn std::error::Error + Sync + Send>> {
-        self.reset_all();
+        let res = self.set_all().await;
 
-        if let Err(e) = self.set_all().await {
+        // leases missed because of a failed call are kept until their grace expires
+        self.sweep_all();
+
+        if let Err(e) = res {
             return Err(e);
         }
 
@@ -0,0 +1,398 @@
+use std::{collections::HashSet, time::Duration};
+
+use async_trait::async_trait;
+use prometheus_exporter::prometheus::{
+    register_int_counter_vec, register_int_gauge, register_int_gauge_vec, IntCounterVec, IntGauge,
+    IntGaugeVec,
+};
+use serde::Deserialize;
+
+use crate::core::common::{
+    http_client_factory::AuthenticatedHttpClientFactory, permission::PermissionWarning,
+};
+
+use super::{client::MapClient, counters::set_counter, series::TrackedVec, MetricMap};
+
+pub const DEFAULT_MAX_DOWNLOAD_TASKS: u64 = 20;
+pub const DOWNLOADER_PERMISSION: &str = "downloader";
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct DownloadTask {
+    id: Option<i64>,
+    #[serde(rename = "type")]
+    _type: Option<String>,
+    name: Option<String>,
+    status: Option<String>,
+    size: Option<i64>,
+    rx_bytes: Option<i64>,
+    tx_bytes: Option<i64>,
+    rx_rate: Option<i64>,
+    tx_rate: Option<i64>,
+    eta: Option<i64>,
+}
+
+impl DownloadTask {
+    fn get_rate(&self) -> i64 {
+        self.rx_rate.unwrap_or_default() + self.tx_rate.unwrap_or_default()
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct DownloadStats {
+    nb_tasks_stopped: Option<i64>,
+    nb_tasks_queued: Option<i64>,
+    nb_tasks_starting: Option<i64>,
+    nb_tasks_downloading: Option<i64>,
+    nb_tasks_stopping: Option<i64>,
+    nb_tasks_error: Option<i64>,
+    nb_tasks_done: Option<i64>,
+    nb_tasks_checking: Option<i64>,
+    nb_tasks_repairing: Option<i64>,
+    nb_tasks_extracting: Option<i64>,
+    nb_tasks_seeding: Option<i64>,
+    rx_rate: Option<i64>,
+    tx_rate: Option<i64>,
+}
+
+impl DownloadStats {
+    fn get_tasks_per_status(&self) -> [(&str, Option<i64>); 11] {
+        [
+            ("stopped", self.nb_tasks_stopped),
+            ("queued", self.nb_tasks_queued),
+            ("starting", self.nb_tasks_starting),
+            ("downloading", self.nb_tasks_downloading),
+            ("stopping", self.nb_tasks_stopping),
+            ("error", self.nb_tasks_error),
+            ("done", self.nb_tasks_done),
+            ("checking", self.nb_tasks_checking),
+            ("repairing", self.nb_tasks_repairing),
+            ("extracting", self.nb_tasks_extracting),
+            ("seeding", self.nb_tasks_seeding),
+        ]
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct DownloadConfig {
+    throttling: Option<DownloadThrottling>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct DownloadThrottling {
+    mode: Option<String>,
+}
+
+/// Keeps the busiest tasks, so a long queue of idle tasks does not
+/// flood the exposition. Tasks already selected keep their slot while they
+/// are listed, so their series do not come and go as the rates change
+fn get_busiest_tasks(
+    mut tasks: Vec<DownloadTask>,
+    selected: &HashSet<i64>,
+    max_tasks: usize,
+) -> Vec<DownloadTask> {
+    let is_selected = |t: &DownloadTask| t.id.is_some_and(|id| selected.contains(&id));
+
+    tasks.sort_by(|a, b| {
+        is_selected(b)
+            .cmp(&is_selected(a))
+            .then(b.get_rate().cmp(&a.get_rate()))
+            .then(a.id.unwrap_or_default().cmp(&b.id.unwrap_or_default()))
+    });
+    tasks.truncate(max_tasks);
+    tasks
+}
+
+pub struct DownloadsMetricMap<'a> {
+    client: MapClient<'a>,
+    max_tasks: usize,
+    selected_tasks: HashSet<i64>,
+    permission_warning: PermissionWarning,
+    tasks_gauge: IntGaugeVec,
+    rx_rate_gauge: IntGauge,
+    tx_rate_gauge: IntGauge,
+    rx_bytes_gauge: IntGauge,
+    tx_bytes_gauge: IntGauge,
+    throttling_mode_gauge: IntGaugeVec,
+    task_info_gauge: TrackedVec<IntGaugeVec>,
+    task_size_gauge: TrackedVec<IntGaugeVec>,
+    task_rx_rate_gauge: TrackedVec<IntGaugeVec>,
+    task_tx_rate_gauge: TrackedVec<IntGaugeVec>,
+    task_rx_bytes_counter: TrackedVec<IntCounterVec>,
+    task_tx_bytes_counter: TrackedVec<IntCounterVec>,
+    task_eta_gauge: TrackedVec<IntGaugeVec>,
+}
+
+impl<'a> DownloadsMetricMap<'a> {
+    /// At most `max_tasks` tasks get their own series, 0 keeps only the aggregates
+    pub fn new(
+        factory: &'a AuthenticatedHttpClientFactory<'a>,
+        prefix: String,
+        stale_series_grace: Duration,
+        max_tasks: u64,
+    ) -> Self {
+        let prfx = format!("{prefix}_downloads");
+
+        let task_gauge = |name: &str, help: &str| {
+            TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_task_{name}"),
+                    help,
+                    &["id", "name", "type"]
+                )
+                .expect(&format!("cannot create {prfx}_task_{name} gauge")),
+                stale_series_grace,
+                &[0],
+            )
+        };
+
+        let task_counter = |name: &str, help: &str| {
+            TrackedVec::keyed(
+                register_int_counter_vec!(
+                    format!("{prfx}_task_{name}_total"),
+                    help,
+                    &["id", "name", "type"]
+                )
+                .expect(&format!("cannot create {prfx}_task_{name}_total counter")),
+                stale_series_grace,
+                &[0],
+            )
+        };
+
+        Self {
+            client: MapClient::with_permission(factory, DOWNLOADER_PERMISSION),
+            max_tasks: max_tasks as usize,
+            selected_tasks: HashSet::new(),
+            permission_warning: PermissionWarning::default(),
+            tasks_gauge: register_int_gauge_vec!(
+                format!("{prfx}_tasks"),
+                "Number of download tasks per status",
+                &["status"]
+            )
+            .expect(&format!("cannot create {prfx}_tasks gauge")),
+            rx_rate_gauge: register_int_gauge!(
+                format!("{prfx}_rx_rate_bytes_per_second"),
+                "Downloader receive rate in bytes per second"
+            )
+            .expect(&format!(
+                "cannot create {prfx}_rx_rate_bytes_per_second gauge"
+            )),
+            tx_rate_gauge: register_int_gauge!(
+                format!("{prfx}_tx_rate_bytes_per_second"),
+                "Downloader transmit rate in bytes per second"
+            )
+            .expect(&format!(
+                "cannot create {prfx}_tx_rate_bytes_per_second gauge"
+            )),
+            rx_bytes_gauge: register_int_gauge!(
+                format!("{prfx}_rx_bytes"),
+                "Bytes received by the tasks currently listed, drops when a task is removed"
+            )
+            .expect(&format!("cannot create {prfx}_rx_bytes gauge")),
+            tx_bytes_gauge: register_int_gauge!(
+                format!("{prfx}_tx_bytes"),
+                "Bytes transmitted by the tasks currently listed, drops when a task is removed"
+            )
+            .expect(&format!("cannot create {prfx}_tx_bytes gauge")),
+            throttling_mode_gauge: register_int_gauge_vec!(
+                format!("{prfx}_throttling_mode"),
+                "Downloader throttling mode (normal, slow, hibernate, schedule), always 1",
+                &["mode"]
+            )
+            .expect(&format!("cannot create {prfx}_throttling_mode gauge")),
+            task_info_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_task_info"),
+                    "Download task description, always 1",
+                    &["id", "name", "type", "status"]
+                )
+                .expect(&format!("cannot create {prfx}_task_info gauge")),
+                stale_series_grace,
+                &[0],
+            ),
+            task_size_gauge: task_gauge("size_bytes", "Download task size in bytes"),
+            task_rx_rate_gauge: task_gauge(
+                "rx_rate_bytes_per_second",
+                "Download task receive rate in bytes per second",
+            ),
+            task_tx_rate_gauge: task_gauge(
+                "tx_rate_bytes_per_second",
+                "Download task transmit rate in bytes per second",
+            ),
+            task_rx_bytes_counter: task_counter("rx_bytes", "Download task received bytes"),
+            task_tx_bytes_counter: task_counter("tx_bytes", "Download task transmitted bytes"),
+            task_eta_gauge: task_gauge(
+                "eta_seconds",
+                "Download task estimated remaining time in seconds",
+            ),
+        }
+    }
+
+    fn set_task(&self, task: &DownloadTask) {
+        let id = task.id.unwrap_or_default().to_string();
+        let name = task.name.to_owned().unwrap_or_default();
+        let _type = task._type.to_owned().unwrap_or_default();
+        let labels = [id.as_str(), name.as_str(), _type.as_str()];
+
+        self.task_info_gauge
+            .with_label_values(&[
+                &id,
+                &name,
+                &_type,
+                &task.status.to_owned().unwrap_or_default(),
+            ])
+            .set(1);
+        self.task_size_gauge
+            .with_label_values(&labels)
+            .set(task.size.unwrap_or_default());
+        self.task_rx_rate_gauge
+            .with_label_values(&labels)
+            .set(task.rx_rate.unwrap_or_default());
+        self.task_tx_rate_gauge
+            .with_label_values(&labels)
+            .set(task.tx_rate.unwrap_or_default());
+        self.task_eta_gauge
+            .with_label_values(&labels)
+            .set(task.eta.unwrap_or_default());
+
+        set_counter(
+            &self.task_rx_bytes_counter.with_label_values(&labels),
+            task.rx_bytes.unwrap_or_default(),
+        );
+        set_counter(
+            &self.task_tx_bytes_counter.with_label_values(&labels),
+            task.tx_bytes.unwrap_or_default(),
+        );
+    }
+
+    async fn set_all(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let stats: DownloadStats = self.client.get("v4/downloads/stats").await?;
+
+        for (status, count) in stats.get_tasks_per_status() {
+            self.tasks_gauge
+                .with_label_values(&[status])
+                .set(count.unwrap_or_default());
+        }
+
+        self.rx_rate_gauge.set(stats.rx_rate.unwrap_or_default());
+        self.tx_rate_gauge.set(stats.tx_rate.unwrap_or_default());
+
+        let config: DownloadConfig = self.client.get("v4/downloads/config").await?;
+        let mode = config.throttling.and_then(|t| t.mode).unwrap_or_default();
+
+        self.throttling_mode_gauge.reset();
+        self.throttling_mode_gauge
+            .with_label_values(&[&mode])
+            .set(1);
+
+        let tasks: Vec<DownloadTask> = self.client.get("v4/downloads/").await?;
+
+        self.rx_bytes_gauge
+            .set(tasks.iter().map(|t| t.rx_bytes.unwrap_or_default()).sum());
+        self.tx_bytes_gauge
+            .set(tasks.iter().map(|t| t.tx_bytes.unwrap_or_default()).sum());
+
+        let busiest = get_busiest_tasks(tasks, &self.selected_tasks, self.max_tasks);
+
+        for task in busiest.iter() {
+            self.set_task(task);
+        }
+
+        // tasks out of the busiest ones are removed right away, the grace
+        // only covers failed calls
+        self.selected_tasks = busiest.iter().filter_map(|t| t.id).collect();
+        let selected: HashSet<String> = busiest
+            .iter()
+            .map(|t| t.id.unwrap_or_default().to_string())
+            .collect();
+        self.retain_tasks(&selected);
+
+        Ok(())
+    }
+
+    fn retain_tasks(&self, selected: &HashSet<String>) {
+        let keep = |labels: &[String]| selected.contains(&labels[0]);
+
+        self.task_info_gauge.retain(keep);
+        self.task_size_gauge.retain(keep);
+        self.task_rx_rate_gauge.retain(keep);
+        self.task_tx_rate_gauge.retain(keep);
+        self.task_rx_bytes_counter.retain(keep);
+        self.task_tx_bytes_counter.retain(keep);
+        self.task_eta_gauge.retain(keep);
+    }
+
+    fn sweep_all(&self) {
+        self.task_info_gauge.sweep();
+        self.task_size_gauge.sweep();
+        self.task_rx_rate_gauge.sweep();
+        self.task_tx_rate_gauge.sweep();
+        self.task_rx_bytes_counter.sweep();
+        self.task_tx_bytes_counter.sweep();
+        self.task_eta_gauge.sweep();
+    }
+}
+
+#[async_trait]
+impl<'a> MetricMap<'a> for DownloadsMetricMap<'a> {
+    async fn init(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+    }
+
+    async fn set(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let res = self.set_all().await;
+
+        // tasks missed because of a failed call are kept until their grace expires
+        self.sweep_all();
+
+        self.permission_warning.check("downloads", res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mappers::api_specs_provider::get_result;
+
+    #[tokio::test]
+    async fn should_keep_busiest_tasks() {
+        let tasks: Vec<DownloadTask> = get_result("downloads", "api_v4_downloads-get").await;
+        let busiest = get_busiest_tasks(tasks.to_vec(), &HashSet::new(), 2);
+
+        assert_eq!(2, busiest.len());
+        assert_eq!(Some(12), busiest[0].id);
+        assert_eq!(Some(9), busiest[1].id);
+        assert!(get_busiest_tasks(tasks.to_vec(), &HashSet::new(), 0).is_empty());
+
+        // an idle task already selected keeps its slot while it is listed
+        let busiest = get_busiest_tasks(tasks.to_vec(), &HashSet::from([14, 9]), 2);
+        let mut ids: Vec<i64> = busiest.iter().filter_map(|t| t.id).collect();
+        ids.sort();
+
+        assert_eq!(vec![9, 14], ids);
+
+        // a removed task frees its slot for the busiest remaining one
+        let listed: Vec<DownloadTask> = tasks.into_iter().filter(|t| t.id != Some(14)).collect();
+        let busiest = get_busiest_tasks(listed, &HashSet::from([14, 9]), 2);
+
+        assert_eq!(Some(9), busiest[0].id);
+        assert_eq!(Some(12), busiest[1].id);
+    }
+
+    #[tokio::test]
+    async fn should_count_tasks_per_status() {
+        let stats: DownloadStats = get_result("downloads", "api_v4_downloads_stats-get").await;
+        let counts = stats.get_tasks_per_status();
+
+        assert_eq!(
+            3,
+            counts
+                .iter()
+                .map(|(_, c)| c.unwrap_or_default())
+                .sum::<i64>()
+        );
+        assert!(counts.contains(&("seeding", Some(1))));
+
+        let config: DownloadConfig = get_result("downloads", "api_v4_downloads_config-get").await;
+        assert_eq!(Some("schedule"), config.throttling.unwrap().mode.as_deref());
+    }
+}
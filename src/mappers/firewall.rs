@@ -0,0 +1,275 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use prometheus_exporter::prometheus::{
+    register_int_gauge, register_int_gauge_vec, IntGauge, IntGaugeVec,
+};
+use serde::Deserialize;
+
+use crate::core::common::http_client_factory::AuthenticatedHttpClientFactory;
+
+use super::{client::MapClient, series::TrackedVec, MetricMap};
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct PortForwarding {
+    id: Option<i64>,
+    enabled: Option<bool>,
+    ip_proto: Option<String>,
+    wan_port_start: Option<i64>,
+    wan_port_end: Option<i64>,
+    lan_ip: Option<String>,
+    lan_port: Option<i64>,
+    src_ip: Option<String>,
+    hostname: Option<String>,
+    comment: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct IncomingPort {
+    id: Option<String>,
+    enabled: Option<bool>,
+    active: Option<bool>,
+    in_port: Option<i64>,
+    #[serde(rename = "type")]
+    _type: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct DmzConfig {
+    enabled: Option<bool>,
+    ip: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct UpnpRedirection {
+    enabled: Option<bool>,
+    proto: Option<String>,
+    ext_port: Option<i64>,
+    int_ip: Option<String>,
+    int_port: Option<i64>,
+    remote_ip: Option<String>,
+    desc: Option<String>,
+}
+
+/// Mappings applications opened through UPnP IGD, the box lists them
+/// with an enabled flag
+fn get_enabled_upnp_redirections(redirections: &[UpnpRedirection]) -> Vec<&UpnpRedirection> {
+    redirections
+        .iter()
+        .filter(|r| r.enabled.unwrap_or(true))
+        .collect()
+}
+
+pub struct FirewallMetricMap<'a> {
+    client: MapClient<'a>,
+    port_forwarding_gauge: TrackedVec<IntGaugeVec>,
+    incoming_port_gauge: TrackedVec<IntGaugeVec>,
+    dmz_gauge: IntGaugeVec,
+    upnp_redirection_gauge: TrackedVec<IntGaugeVec>,
+    upnp_redirections_gauge: IntGauge,
+}
+
+impl<'a> FirewallMetricMap<'a> {
+    pub fn new(
+        factory: &'a AuthenticatedHttpClientFactory<'a>,
+        prefix: String,
+        stale_series_grace: Duration,
+    ) -> Self {
+        let prfx = format!("{prefix}_firewall");
+
+        Self {
+            client: MapClient::new(factory),
+            port_forwarding_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_port_forwarding"),
+                    "Port forwarding rule, 1 when the rule is enabled",
+                    &[
+                        "id",
+                        "protocol",
+                        "wan_port_start",
+                        "wan_port_end",
+                        "lan_ip",
+                        "lan_port",
+                        "source",
+                        "hostname",
+                        "comment"
+                    ]
+                )
+                .expect(&format!("cannot create {prfx}_port_forwarding gauge")),
+                stale_series_grace,
+                &[0],
+            ),
+            incoming_port_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_incoming_port"),
+                    "Box service port open on the WAN side, 1 when enabled and active",
+                    &["id", "port", "type"]
+                )
+                .expect(&format!("cannot create {prfx}_incoming_port gauge")),
+                stale_series_grace,
+                &[0],
+            ),
+            dmz_gauge: register_int_gauge_vec!(
+                format!("{prfx}_dmz_enabled"),
+                "DMZ state, 1 when every unsolicited WAN traffic goes to the target ip",
+                &["ip"]
+            )
+            .expect(&format!("cannot create {prfx}_dmz_enabled gauge")),
+            upnp_redirection_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_upnp_redirection"),
+                    "Port mapping created by a LAN application through UPnP IGD, always 1",
+                    &[
+                        "protocol",
+                        "wan_port",
+                        "lan_ip",
+                        "lan_port",
+                        "source",
+                        "description"
+                    ]
+                )
+                .expect(&format!("cannot create {prfx}_upnp_redirection gauge")),
+                stale_series_grace,
+                &[0, 1],
+            ),
+            upnp_redirections_gauge: register_int_gauge!(
+                format!("{prfx}_upnp_redirections"),
+                "Number of port mappings created through UPnP IGD"
+            )
+            .expect(&format!("cannot create {prfx}_upnp_redirections gauge")),
+        }
+    }
+
+    async fn set_port_forwardings(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let redirections: Vec<PortForwarding> = self.client.get("v4/fw/redir/").await?;
+
+        for redir in redirections.iter() {
+            self.port_forwarding_gauge
+                .with_label_values(&[
+                    &redir.id.unwrap_or_default().to_string(),
+                    &redir.ip_proto.to_owned().unwrap_or_default(),
+                    &redir.wan_port_start.unwrap_or_default().to_string(),
+                    &redir.wan_port_end.unwrap_or_default().to_string(),
+                    &redir.lan_ip.to_owned().unwrap_or_default(),
+                    &redir.lan_port.unwrap_or_default().to_string(),
+                    &redir.src_ip.to_owned().unwrap_or_default(),
+                    &redir.hostname.to_owned().unwrap_or_default(),
+                    &redir.comment.to_owned().unwrap_or_default(),
+                ])
+                .set(redir.enabled.unwrap_or_default().into());
+        }
+
+        Ok(())
+    }
+
+    async fn set_incoming_ports(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let ports: Vec<IncomingPort> = self.client.get("v4/fw/incoming/").await?;
+
+        for port in ports.iter() {
+            let open = port.enabled.unwrap_or_default() && port.active.unwrap_or_default();
+
+            self.incoming_port_gauge
+                .with_label_values(&[
+                    &port.id.to_owned().unwrap_or_default(),
+                    &port.in_port.unwrap_or_default().to_string(),
+                    &port._type.to_owned().unwrap_or_default(),
+                ])
+                .set(open.into());
+        }
+
+        Ok(())
+    }
+
+    async fn set_dmz(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let dmz: DmzConfig = self.client.get("v4/fw/dmz/").await?;
+
+        self.dmz_gauge.reset();
+        self.dmz_gauge
+            .with_label_values(&[&dmz.ip.unwrap_or_default()])
+            .set(dmz.enabled.unwrap_or_default().into());
+
+        Ok(())
+    }
+
+    async fn set_upnp_redirections(
+        &mut self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let redirections: Vec<UpnpRedirection> = self.client.get("v4/upnpigd/redir/").await?;
+        let enabled = get_enabled_upnp_redirections(&redirections);
+
+        self.upnp_redirections_gauge.set(enabled.len() as i64);
+
+        for redir in enabled.iter() {
+            self.upnp_redirection_gauge
+                .with_label_values(&[
+                    &redir.proto.to_owned().unwrap_or_default(),
+                    &redir.ext_port.unwrap_or_default().to_string(),
+                    &redir.int_ip.to_owned().unwrap_or_default(),
+                    &redir.int_port.unwrap_or_default().to_string(),
+                    &redir.remote_ip.to_owned().unwrap_or_default(),
+                    &redir.desc.to_owned().unwrap_or_default(),
+                ])
+                .set(1);
+        }
+
+        Ok(())
+    }
+
+    async fn set_all(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.set_port_forwardings().await?;
+        self.set_incoming_ports().await?;
+        self.set_dmz().await?;
+        self.set_upnp_redirections().await?;
+
+        Ok(())
+    }
+
+    fn sweep_all(&self) {
+        self.port_forwarding_gauge.sweep();
+        self.incoming_port_gauge.sweep();
+        self.upnp_redirection_gauge.sweep();
+    }
+}
+
+#[async_trait]
+impl<'a> MetricMap<'a> for FirewallMetricMap<'a> {
+    async fn init(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+    }
+
+    async fn set(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let res = self.set_all().await;
+
+        // removed rules and expired mappings are dropped once their grace expires
+        self.sweep_all();
+
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mappers::api_specs_provider::get_result;
+
+    #[tokio::test]
+    async fn should_deserialize_wan_exposure() {
+        let redirections: Vec<PortForwarding> = get_result("firewall", "api_v4_fw_redir-get").await;
+        assert_eq!(Some("tcp"), redirections[0].ip_proto.as_deref());
+        assert_eq!(Some(25575), redirections[1].wan_port_end);
+        assert_eq!(Some(false), redirections[1].enabled);
+
+        let ports: Vec<IncomingPort> = get_result("firewall", "api_v4_fw_incoming-get").await;
+        assert_eq!(Some("bittorrent-main"), ports[0].id.as_deref());
+
+        let dmz: DmzConfig = get_result("firewall", "api_v4_fw_dmz-get").await;
+        assert_eq!(Some(false), dmz.enabled);
+
+        let upnp: Vec<UpnpRedirection> = get_result("firewall", "api_v4_upnpigd_redir-get").await;
+        let enabled = get_enabled_upnp_redirections(&upnp);
+        assert_eq!(2, enabled.len());
+        assert_eq!(Some("Xbox (3074) UDP"), enabled[1].desc.as_deref());
+    }
+}
@@ -0,0 +1,239 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use prometheus_exporter::prometheus::{register_int_gauge_vec, IntGaugeVec};
+use serde::Deserialize;
+
+use crate::core::common::http_client_factory::AuthenticatedHttpClientFactory;
+
+use super::{client::MapClient, series::TrackedVec, MetricMap};
+
+/// Freeplug rates and ethernet speed are given in Mb/s
+const RATE_UNIT: i64 = 1_000_000;
+const LINK_UP: &str = "up";
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct FreeplugNetwork {
+    id: Option<String>,
+    members: Option<Vec<Freeplug>>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Freeplug {
+    id: Option<String>,
+    local: Option<bool>,
+    net_role: Option<String>,
+    eth_port_status: Option<String>,
+    eth_speed: Option<i64>,
+    inactive: Option<i64>,
+    rx_rate: Option<i64>,
+    tx_rate: Option<i64>,
+    has_network: Option<bool>,
+    model: Option<String>,
+}
+
+/// The API gives -1 when a value is not available, the local plug has no
+/// rate nor inactivity
+fn get_available(value: Option<i64>) -> Option<i64> {
+    value.filter(|v| *v >= 0)
+}
+
+pub struct FreeplugMetricMap<'a> {
+    client: MapClient<'a>,
+    network_members_gauge: TrackedVec<IntGaugeVec>,
+    member_info_gauge: TrackedVec<IntGaugeVec>,
+    member_local_gauge: TrackedVec<IntGaugeVec>,
+    member_has_network_gauge: TrackedVec<IntGaugeVec>,
+    member_link_state_gauge: TrackedVec<IntGaugeVec>,
+    member_eth_speed_gauge: TrackedVec<IntGaugeVec>,
+    member_rx_rate_gauge: TrackedVec<IntGaugeVec>,
+    member_tx_rate_gauge: TrackedVec<IntGaugeVec>,
+    member_inactive_gauge: TrackedVec<IntGaugeVec>,
+}
+
+impl<'a> FreeplugMetricMap<'a> {
+    pub fn new(
+        factory: &'a AuthenticatedHttpClientFactory<'a>,
+        prefix: String,
+        stale_series_grace: Duration,
+    ) -> Self {
+        let prfx = format!("{prefix}_freeplug");
+        let member_gauge = |name: &str, help: &str| {
+            TrackedVec::new(
+                register_int_gauge_vec!(format!("{prfx}_member_{name}"), help, &["mac"])
+                    .expect(&format!("cannot create {prfx}_member_{name} gauge")),
+                stale_series_grace,
+            )
+        };
+
+        Self {
+            client: MapClient::new(factory),
+            network_members_gauge: TrackedVec::new(
+                register_int_gauge_vec!(
+                    format!("{prfx}_network_members"),
+                    "Number of freeplugs in the powerline network",
+                    &["network"]
+                )
+                .expect(&format!("cannot create {prfx}_network_members gauge")),
+                stale_series_grace,
+            ),
+            member_info_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_member_info"),
+                    "Freeplug network, model and role (cco coordinator, pco proxy, sta station), always 1",
+                    &["mac", "network", "model", "role"]
+                )
+                .expect(&format!("cannot create {prfx}_member_info gauge")),
+                stale_series_grace,
+                &[0],
+            ),
+            member_local_gauge: member_gauge(
+                "local",
+                "Freeplug is plugged on the box itself",
+            ),
+            member_has_network_gauge: member_gauge(
+                "has_network",
+                "Freeplug has joined the powerline network",
+            ),
+            member_link_state_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_member_link_state"),
+                    "Freeplug ethernet port state (up, down, unknown), 1 when up",
+                    &["mac", "state"]
+                )
+                .expect(&format!("cannot create {prfx}_member_link_state gauge")),
+                stale_series_grace,
+                &[0],
+            ),
+            member_eth_speed_gauge: member_gauge(
+                "ethernet_speed_bits_per_second",
+                "Freeplug ethernet port speed, only when reported",
+            ),
+            member_rx_rate_gauge: member_gauge(
+                "rx_rate_bits_per_second",
+                "Powerline rate from the freeplug to the coordinator",
+            ),
+            member_tx_rate_gauge: member_gauge(
+                "tx_rate_bits_per_second",
+                "Powerline rate from the coordinator to the freeplug",
+            ),
+            member_inactive_gauge: member_gauge(
+                "inactive_seconds",
+                "Seconds since the freeplug was last seen active",
+            ),
+        }
+    }
+
+    fn set_member(&self, network: &str, plug: &Freeplug) {
+        let mac = plug.id.to_owned().unwrap_or_default();
+        let state = plug.eth_port_status.to_owned().unwrap_or_default();
+
+        self.member_info_gauge
+            .with_label_values(&[
+                &mac,
+                network,
+                &plug.model.to_owned().unwrap_or_default(),
+                &plug.net_role.to_owned().unwrap_or_default(),
+            ])
+            .set(1);
+        self.member_local_gauge
+            .with_label_values(&[&mac])
+            .set(plug.local.unwrap_or_default().into());
+        self.member_has_network_gauge
+            .with_label_values(&[&mac])
+            .set(plug.has_network.unwrap_or_default().into());
+        self.member_link_state_gauge
+            .with_label_values(&[&mac, &state])
+            .set((state == LINK_UP).into());
+
+        if let Some(speed) = get_available(plug.eth_speed) {
+            self.member_eth_speed_gauge
+                .with_label_values(&[&mac])
+                .set(speed * RATE_UNIT);
+        }
+
+        if let Some(rate) = get_available(plug.rx_rate) {
+            self.member_rx_rate_gauge
+                .with_label_values(&[&mac])
+                .set(rate * RATE_UNIT);
+        }
+
+        if let Some(rate) = get_available(plug.tx_rate) {
+            self.member_tx_rate_gauge
+                .with_label_values(&[&mac])
+                .set(rate * RATE_UNIT);
+        }
+
+        if let Some(inactive) = get_available(plug.inactive) {
+            self.member_inactive_gauge
+                .with_label_values(&[&mac])
+                .set(inactive);
+        }
+    }
+
+    async fn set_all(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let networks: Vec<FreeplugNetwork> = self.client.get("v4/freeplug/").await?;
+
+        for network in networks.iter() {
+            let id = network.id.to_owned().unwrap_or_default();
+            let members = network.members.as_deref().unwrap_or_default();
+
+            self.network_members_gauge
+                .with_label_values(&[&id])
+                .set(members.len() as i64);
+
+            for plug in members.iter() {
+                self.set_member(&id, plug);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn sweep_all(&self) {
+        self.network_members_gauge.sweep();
+        self.member_info_gauge.sweep();
+        self.member_local_gauge.sweep();
+        self.member_has_network_gauge.sweep();
+        self.member_link_state_gauge.sweep();
+        self.member_eth_speed_gauge.sweep();
+        self.member_rx_rate_gauge.sweep();
+        self.member_tx_rate_gauge.sweep();
+        self.member_inactive_gauge.sweep();
+    }
+}
+
+#[async_trait]
+impl<'a> MetricMap<'a> for FreeplugMetricMap<'a> {
+    async fn init(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+    }
+
+    async fn set(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let res = self.set_all().await;
+
+        // unplugged freeplugs are removed once their grace expires
+        self.sweep_all();
+
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mappers::api_specs_provider::get_result;
+
+    #[tokio::test]
+    async fn should_deserialize_freeplug_networks() {
+        let networks: Vec<FreeplugNetwork> = get_result("freeplug", "api_v4_freeplug-get").await;
+
+        let members = networks[0].members.as_ref().unwrap();
+        assert_eq!(3, members.len());
+        assert_eq!(Some(true), members[0].local);
+        assert_eq!(None, get_available(members[0].rx_rate));
+        assert_eq!(Some("14:0C:76:B3:1A:E8"), members[1].id.as_deref());
+        assert_eq!(Some(112), get_available(members[1].tx_rate));
+        assert_eq!(Some("down"), members[2].eth_port_status.as_deref());
+    }
+}
@@ -0,0 +1,56 @@
+use prometheus_exporter::prometheus::IntGaugeVec;
+
+/// Info metric, a single series always set to 1 carrying metadata as labels.
+/// When a label value changes (e.g. after a firmware upgrade) the previous
+/// series is removed so only the current one is exposed
+pub struct InfoMetric {
+    metric: IntGaugeVec,
+    values: Option<Vec<String>>,
+}
+
+impl InfoMetric {
+    pub fn new(metric: IntGaugeVec) -> Self {
+        Self {
+            metric,
+            values: None,
+        }
+    }
+
+    pub fn set(&mut self, values: &[&str]) {
+        if let Some(current) = &self.values {
+            if current.iter().map(|v| v.as_str()).eq(values.iter().copied()) {
+                return;
+            }
+
+            let current: Vec<&str> = current.iter().map(|v| v.as_str()).collect();
+            let _ = self.metric.remove_label_values(&current);
+        }
+
+        self.metric.with_label_values(values).set(1);
+        self.values = Some(values.iter().map(|v| v.to_string()).collect());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prometheus_exporter::prometheus::{core::Collector, Opts};
+
+    use super::*;
+
+    #[test]
+    fn should_replace_series_on_change() {
+        let vec = IntGaugeVec::new(Opts::new("test_info", "test"), &["version"]).unwrap();
+        let mut info = InfoMetric::new(vec.clone());
+
+        info.set(&["4.8.12"]);
+        info.set(&["4.8.12"]);
+        info.set(&["4.9.14"]);
+
+        let families = vec.collect();
+        let metrics = families[0].get_metric();
+
+        assert_eq!(1, metrics.len());
+        assert_eq!("4.9.14", metrics[0].get_label()[0].get_value());
+        assert_eq!(1.0, metrics[0].get_gauge().get_value());
+    }
+}
@@ -5,7 +5,7 @@ use reqwest::Client;
 use serde::Deserialize;
 
 
-use super::MetricMap;
+use super::{info::InfoMetric, MetricMap};
 use crate::{
     core::common::{
         http_client_factory::{AuthenticatedHttpClientFactory, ManagedHttpClient},
@@ -23,9 +23,9 @@ pub struct LanConfig {
     pub ip: Option<String>,
 }
 
-pub struct LanMetricMap<'a> {
-    factory: &'a AuthenticatedHttpClientFactory<'a>,
-    managed_client: Option<ManagedHttpClient>,
+/// Value-in-label gauges superseded by the LAN info metric,
+/// registered only when legacy metrics are enabled
+struct LanLegacyMetrics {
     name_dns_metric: IntGaugeVec,
     name_mdns_metric: IntGaugeVec,
     name_metric: IntGaugeVec,
@@ -34,44 +34,112 @@ pub struct LanMetricMap<'a> {
     ip_metric: IntGaugeVec,
 }
 
-impl<'a> LanMetricMap<'a> {
-    pub fn new(factory: &'a AuthenticatedHttpClientFactory<'a>, prefix: String) -> Self {
-        let prfx = format!("{prefix}_lan_config");
+impl LanLegacyMetrics {
+    fn new(prfx: &str) -> Self {
         Self {
-            factory,
-            managed_client: None,
             name_dns_metric: register_int_gauge_vec!(
                 format!("{prfx}_name_dns"),
-                format!("{prfx}_name_dns"),
+                "LAN DNS name of the box, always 1",
                 &["name_dns"]
             )
             .expect(&format!("cannot create {prfx}_name_dns gauge")),
             name_mdns_metric: register_int_gauge_vec!(
                 format!("{prfx}_name_mdns"),
-                format!("{prfx}_name_mdns"),
+                "LAN mDNS name of the box, always 1",
                 &["name_mdns"]
             )
             .expect(&format!("cannot create {prfx}_name_mdns gauge")),
             name_metric: register_int_gauge_vec!(
                 format!("{prfx}_name"),
-                format!("{prfx}_name"),
+                "LAN name of the box, always 1",
                 &["name"]
             )
             .expect(&format!("cannot create {prfx}_name gauge")),
             mode_metric: register_int_gauge_vec!(
                 format!("{prfx}_mode"),
-                format!("{prfx}_mode"),
+                "LAN mode (router, bridge), always 1",
                 &["mode"]
             )
             .expect(&format!("cannot create {prfx}_mode gauge")),
             name_netbios_metric: register_int_gauge_vec!(
                 format!("{prfx}_name_netbios"),
-                format!("{prfx}_name_netbios"),
+                "LAN NetBIOS name of the box, always 1",
                 &["name_netbios"]
             )
             .expect(&format!("cannot create {prfx}_name_netbios gauge")),
-            ip_metric: register_int_gauge_vec!(format!("{prfx}_ip"), format!("{prfx}_ip"), &["ip"])
-                .expect(&format!("cannot create {prfx}_ip gauge")),
+            ip_metric: register_int_gauge_vec!(
+                format!("{prfx}_ip"),
+                "LAN IP address of the box, always 1",
+                &["ip"]
+            )
+            .expect(&format!("cannot create {prfx}_ip gauge")),
+        }
+    }
+
+    fn reset(&self) {
+        self.name_dns_metric.reset();
+        self.name_mdns_metric.reset();
+        self.name_metric.reset();
+        self.mode_metric.reset();
+        self.name_netbios_metric.reset();
+        self.ip_metric.reset();
+    }
+
+    fn set(&self, cfg: &LanConfig) {
+        self.name_dns_metric
+            .with_label_values(&[&cfg.name_dns.clone().unwrap_or_default()])
+            .set(cfg.name_dns.is_some().into());
+        self.name_mdns_metric
+            .with_label_values(&[&cfg.name_mdns.clone().unwrap_or_default()])
+            .set(cfg.name_mdns.is_some().into());
+        self.name_metric
+            .with_label_values(&[&cfg.name.clone().unwrap_or_default()])
+            .set(cfg.name.is_some().into());
+        self.name_netbios_metric
+            .with_label_values(&[&cfg.name_netbios.clone().unwrap_or_default()])
+            .set(cfg.name_netbios.is_some().into());
+        self.mode_metric
+            .with_label_values(&[&cfg.mode.clone().unwrap_or_default()])
+            .set(cfg.mode.is_some().into());
+        self.ip_metric
+            .with_label_values(&[&cfg.ip.clone().unwrap_or_default()])
+            .set(cfg.ip.is_some().into());
+    }
+}
+
+pub struct LanMetricMap<'a> {
+    factory: &'a AuthenticatedHttpClientFactory<'a>,
+    managed_client: Option<ManagedHttpClient>,
+    info_metric: InfoMetric,
+    legacy_metrics: Option<LanLegacyMetrics>,
+}
+
+impl<'a> LanMetricMap<'a> {
+    pub fn new(
+        factory: &'a AuthenticatedHttpClientFactory<'a>,
+        prefix: String,
+        legacy_metrics: bool,
+    ) -> Self {
+        let prfx = format!("{prefix}_lan_config");
+        Self {
+            factory,
+            managed_client: None,
+            info_metric: InfoMetric::new(
+                register_int_gauge_vec!(
+                    format!("{prfx}_info"),
+                    "LAN configuration of the box, always 1",
+                    &[
+                        "name",
+                        "name_dns",
+                        "name_mdns",
+                        "name_netbios",
+                        "mode",
+                        "ip"
+                    ]
+                )
+                .expect(&format!("cannot create {prfx}_info gauge")),
+            ),
+            legacy_metrics: legacy_metrics.then(|| LanLegacyMetrics::new(&prfx)),
         }
     }
 
@@ -140,35 +208,26 @@ impl<'a> LanMetricMap<'a> {
             Some(r) => r,
         };
 
-        self.name_dns_metric
-            .with_label_values(&[&cfg.name_dns.clone().unwrap_or_default()])
-            .set(cfg.name_dns.is_some().into());
-        self.name_mdns_metric
-            .with_label_values(&[&cfg.name_mdns.clone().unwrap_or_default()])
-            .set(cfg.name_mdns.is_some().into());
-        self.name_metric
-            .with_label_values(&[&cfg.name.clone().unwrap_or_default()])
-            .set(cfg.name.is_some().into());
-        self.name_netbios_metric
-            .with_label_values(&[&cfg.name_netbios.clone().unwrap_or_default()])
-            .set(cfg.name_netbios.is_some().into());
-        self.mode_metric
-            .with_label_values(&[&cfg.mode.clone().unwrap_or_default()])
-            .set(cfg.mode.is_some().into());
-        self.ip_metric
-            .with_label_values(&[&cfg.ip.clone().unwrap_or_default()])
-            .set(cfg.ip.is_some().into());
+        self.info_metric.set(&[
+            &cfg.name.clone().unwrap_or_default(),
+            &cfg.name_dns.clone().unwrap_or_default(),
+            &cfg.name_mdns.clone().unwrap_or_default(),
+            &cfg.name_netbios.clone().unwrap_or_default(),
+            &cfg.mode.clone().unwrap_or_default(),
+            &cfg.ip.clone().unwrap_or_default(),
+        ]);
+
+        if let Some(legacy_metrics) = &self.legacy_metrics {
+            legacy_metrics.set(&cfg);
+        }
 
         Ok(())
     }
 
     fn reset_all(&mut self) {
-        self.name_dns_metric.reset();
-        self.name_mdns_metric.reset();
-        self.name_metric.reset();
-        self.mode_metric.reset();
-        self.name_netbios_metric.reset();
-        self.ip_metric.reset();
+        if let Some(legacy_metrics) = &self.legacy_metrics {
+            legacy_metrics.reset();
+        }
     }
 }
 
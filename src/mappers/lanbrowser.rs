@@ -1,5 +1,5 @@
 
-use super::MetricMap;
+use super::{devices::SharedDeviceSources, series::TrackedVec, MetricMap};
 use crate::core::common::{
     http_client_factory::{AuthenticatedHttpClientFactory, ManagedHttpClient},
     transport::{FreeboxResponse, FreeboxResponseError},
@@ -9,6 +9,7 @@ use log::{debug, error};
 use prometheus_exporter::prometheus::{register_int_gauge_vec, IntGaugeVec};
 use reqwest::Client;
 use serde::Deserialize;
+use std::time::Duration;
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct LanBrowserInterface {
@@ -19,15 +20,15 @@ pub struct LanBrowserInterface {
 #[derive(Deserialize, Clone, Debug)]
 pub struct LanHost {
     id: Option<String>,
-    primary_name: Option<String>,
+    pub primary_name: Option<String>,
     host_type: Option<String>,
     primary_name_manual: Option<bool>,
-    l2ident: Option<LanHostL2Ident>,
-    vendor_name: Option<String>,
-    active: Option<bool>,
+    pub l2ident: Option<LanHostL2Ident>,
+    pub vendor_name: Option<String>,
+    pub active: Option<bool>,
     last_activity: Option<i64>,
     names: Option<Vec<LanHostName>>,
-    l3connectivities: Option<Vec<LanHostL3Connectivity>>,
+    pub l3connectivities: Option<Vec<LanHostL3Connectivity>>,
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -56,59 +57,84 @@ pub struct LanHostL3Connectivity {
 pub struct LanBrowserMetricMap<'a> {
     factory: &'a AuthenticatedHttpClientFactory<'a>,
     managed_client: Option<ManagedHttpClient>,
-    device_gauge: IntGaugeVec,
-    device_l3_connectivity_gauge: IntGaugeVec,
-    device_last_activity: IntGaugeVec,
-    device_name_gauge: IntGaugeVec,
-    iface_gauge: IntGaugeVec,
+    device_sources: Option<SharedDeviceSources>,
+    device_gauge: TrackedVec<IntGaugeVec>,
+    device_l3_connectivity_gauge: TrackedVec<IntGaugeVec>,
+    device_last_activity: TrackedVec<IntGaugeVec>,
+    device_name_gauge: TrackedVec<IntGaugeVec>,
+    iface_gauge: TrackedVec<IntGaugeVec>,
 }
 
 impl<'a> LanBrowserMetricMap<'a> {
-    pub fn new(factory: &'a AuthenticatedHttpClientFactory<'a>, prefix: String) -> Self {
+    pub fn new(
+        factory: &'a AuthenticatedHttpClientFactory<'a>,
+        prefix: String,
+        stale_series_grace: Duration,
+        device_sources: Option<SharedDeviceSources>,
+    ) -> Self {
         let prfx = format!("{prefix}_lan_browser");
 
         Self {
             factory,
             managed_client: None,
-            device_gauge: register_int_gauge_vec!(
-                format!("{prfx}_device"),
-                "device, 1 for active",
-                &[
-                    "iface",
-                    "primary_name",
-                    "id",
-                    "type",
-                    "primary_name_manual",
-                    "l2ident_id",
-                    "l2ident_type",
-                    "vendor_name"
-                ]
-            )
-            .expect(&format!("cannot create {prfx}_devices gauge")),
-            device_l3_connectivity_gauge: register_int_gauge_vec!(
-                format!("{prfx}_device_l3_connectivity"),
-                "device l3 connectivity, 1 for active",
-                &["ident", "iface", "addr", "name", "af"]
-            )
-            .expect("cannot create {prfx}_device_l3 gauge"),
-            device_last_activity: register_int_gauge_vec!(
-                format!("{prfx}_device_last_activity"),
-                "device last activity timestamp",
-                &["iface", "name"]
-            )
-            .expect(&format!("cannot create {prfx}_device_last_activity gauge")),
-            device_name_gauge: register_int_gauge_vec!(
-                format!("{prfx}_device_name"),
-                "device name",
-                &["name", "source", "ident", "iface"]
-            )
-            .expect(&format!("cannot create {prfx}_name gauge")),
-            iface_gauge: register_int_gauge_vec!(
-                format!("{prfx}_iface_hosts"),
-                "network interfaces",
-                &["name"]
-            )
-            .expect(&format!("cannot create {prfx}_ifaces gauge")),
+            device_sources,
+            device_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_device"),
+                    "LAN host seen by the box, 1 when active",
+                    &[
+                        "iface",
+                        "primary_name",
+                        "id",
+                        "type",
+                        "primary_name_manual",
+                        "l2ident_id",
+                        "l2ident_type",
+                        "vendor_name"
+                    ]
+                )
+                .expect(&format!("cannot create {prfx}_devices gauge")),
+                stale_series_grace,
+                &[0, 2],
+            ),
+            device_l3_connectivity_gauge: TrackedVec::new(
+                register_int_gauge_vec!(
+                    format!("{prfx}_device_l3_connectivity"),
+                    "LAN host layer 3 address, 1 when active",
+                    &["ident", "iface", "addr", "name", "af"]
+                )
+                .expect("cannot create {prfx}_device_l3 gauge"),
+                stale_series_grace,
+            ),
+            device_last_activity: TrackedVec::new(
+                register_int_gauge_vec!(
+                    format!("{prfx}_device_last_activity_timestamp_seconds"),
+                    "LAN host last activity as a unix timestamp",
+                    &["iface", "name"]
+                )
+                .expect(&format!(
+                    "cannot create {prfx}_device_last_activity_timestamp_seconds gauge"
+                )),
+                stale_series_grace,
+            ),
+            device_name_gauge: TrackedVec::new(
+                register_int_gauge_vec!(
+                    format!("{prfx}_device_name"),
+                    "LAN host name and its source, always 1",
+                    &["name", "source", "ident", "iface"]
+                )
+                .expect(&format!("cannot create {prfx}_name gauge")),
+                stale_series_grace,
+            ),
+            iface_gauge: TrackedVec::new(
+                register_int_gauge_vec!(
+                    format!("{prfx}_iface_hosts"),
+                    "Number of hosts seen on the network interface",
+                    &["name"]
+                )
+                .expect(&format!("cannot create {prfx}_ifaces gauge")),
+                stale_series_grace,
+            ),
         }
     }
 
@@ -218,17 +244,15 @@ impl<'a> LanBrowserMetricMap<'a> {
         }
     }
 
-    fn reset_all(&self) {
-        self.device_gauge.reset();
-        self.device_l3_connectivity_gauge.reset();
-        self.device_last_activity.reset();
-        self.device_name_gauge.reset();
-        self.iface_gauge.reset();
+    fn sweep_all(&self) {
+        self.device_gauge.sweep();
+        self.device_l3_connectivity_gauge.sweep();
+        self.device_last_activity.sweep();
+        self.device_name_gauge.sweep();
+        self.iface_gauge.sweep();
     }
 
     async fn set_all(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        self.reset_all();
-
         let ifaces = match self.get_ifaces().await {
             Err(e) => return Err(e),
             Ok(r) => r,
@@ -246,6 +270,10 @@ impl<'a> LanBrowserMetricMap<'a> {
             match self.get_devices(&iface).await {
                 Err(e) => error!("{e:#?}"),
                 Ok(devs) => {
+                    if let Some(sources) = self.device_sources.as_ref() {
+                        sources.lock().unwrap().add_browser_hosts(&devs);
+                    }
+
                     for dev in devs {
                         let l2ident = dev.l2ident.unwrap_or(LanHostL2Ident {
                             id: None,
@@ -314,7 +342,12 @@ impl<'a> MetricMap<'a> for LanBrowserMetricMap<'a> {
     }
 
     async fn set(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        match self.set_all().await {
+        let res = self.set_all().await;
+
+        // series missed because of a failed call are kept until their grace expires
+        self.sweep_all();
+
+        match res {
             Err(e) => return Err(e),
             _ => {}
         };
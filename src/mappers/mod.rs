@@ -1,26 +1,42 @@
 use async_trait::async_trait;
+use calls::{CallsMetricMap, NumberPolicy};
 use chrono::Duration;
-use connection::ConnectionMetricMap;
+use connection::{ConnectionMetricMap, SfpThresholds};
 use lan::LanMetricMap;
 use lanbrowser::LanBrowserMetricMap;
 use log::{error, warn};
+use presence::PresenceTracker;
 use switch::SwitchMetricMap;
 use system::SystemMetricMap;
 
-use crate::{
-    core::{
-        capabilities::Capabilities,
-        common::http_client_factory::AuthenticatedHttpClientFactory,
-        configuration::sections::{ApiConfiguration, CapabilitiesConfiguration, PoliciesConfiguration},
+use crate::core::{
+    capabilities::Capabilities,
+    common::http_client_factory::AuthenticatedHttpClientFactory,
+    configuration::sections::{
+        ApiConfiguration, CapabilitiesConfiguration, ConnectionConfiguration,
+        PoliciesConfiguration, PresenceConfiguration,
     },
 };
 
+pub mod calls;
+pub mod client;
 pub mod connection;
+pub mod counters;
+pub mod devices;
 pub mod dhcp;
+pub mod downloads;
+pub mod firewall;
+pub mod freeplug;
+pub mod info;
 pub mod lan;
 pub mod lanbrowser;
+pub mod parental;
+pub mod presence;
+pub mod series;
+pub mod storage;
 pub mod switch;
 pub mod system;
+pub mod vpn;
 pub mod wifi;
 
 #[async_trait]
@@ -40,14 +56,27 @@ impl<'a> Mapper<'a> {
         caps: Capabilities,
         api_conf: ApiConfiguration,
         policies: Option<PoliciesConfiguration>,
+        connection_conf: Option<ConnectionConfiguration>,
+        presence: Option<PresenceConfiguration>,
+        data_directory: String,
     ) -> Self {
         let mut maps: Vec<Box<dyn MetricMap<'a> + 'a>> = vec![];
+        let stale_series_grace = std::time::Duration::from_secs(
+            policies
+                .as_ref()
+                .and_then(|p| p.stale_series_grace)
+                .unwrap_or(series::DEFAULT_STALE_SERIES_GRACE),
+        );
 
         if let Some(e) = conf.connection {
             if e {
                 maps.push(Box::new(ConnectionMetricMap::new(
                     factory,
                     conf.prefix.to_owned().unwrap(),
+                    caps.lte.unwrap_or(false),
+                    &data_directory,
+                    conf.legacy_metrics.unwrap_or(true),
+                    SfpThresholds::from_conf(connection_conf.as_ref().and_then(|c| c.sfp.as_ref())),
                 )));
             }
         } else {
@@ -60,6 +89,7 @@ impl<'a> Mapper<'a> {
                 maps.push(Box::new(SystemMetricMap::new(
                     factory,
                     conf.prefix.to_owned().unwrap(),
+                    conf.legacy_metrics.unwrap_or(true),
                 )));
             }
         } else {
@@ -73,21 +103,32 @@ impl<'a> Mapper<'a> {
                 maps.push(Box::new(LanMetricMap::new(
                     factory,
                     conf.prefix.to_owned().unwrap(),
+                    conf.legacy_metrics.unwrap_or(true),
                 )));
             }
         } else {
             warn!("LAN metrics are disabled by default, missing entry in the configuration file");
         }
 
-        let network_mode = caps.network_mode.unwrap_or_default();
+        let network_mode = caps.network_mode.clone().unwrap_or_default();
+
+        // filled by the maps below during each refresh, read by the devices map
+        let device_sources = conf
+            .devices
+            .unwrap_or(false)
+            .then(devices::SharedDeviceSources::default);
 
         if let Some(e) = conf.lan_browser {
             if e {
                 if !caps.lan_browser.unwrap_or(false) {
                     warn!("lan_browser is incompatible with detected freebox mode ({}), the option has been disabled", network_mode);
                 } else {
-                    let lan_browser_map =
-                        LanBrowserMetricMap::new(factory, conf.prefix.to_owned().unwrap());
+                    let lan_browser_map = LanBrowserMetricMap::new(
+                        factory,
+                        conf.prefix.to_owned().unwrap(),
+                        stale_series_grace,
+                        device_sources.clone(),
+                    );
                     maps.push(Box::new(lan_browser_map));
                 }
             }
@@ -103,6 +144,8 @@ impl<'a> Mapper<'a> {
                     maps.push(Box::new(SwitchMetricMap::new(
                         factory,
                         conf.prefix.to_owned().unwrap(),
+                        stale_series_grace,
+                        device_sources.clone(),
                     )));
                 }
             }
@@ -118,16 +161,16 @@ impl<'a> Mapper<'a> {
                     warn!("wifi is either disabled on the host or has been explicitly enabled with an incompatible network mode ({}). The option has been automatically disabled", network_mode);
                 } else {
                     // Provide default policies if none specified
-                    let default_policies = PoliciesConfiguration {
-                        unresolved_station_hostnames: Some("ignore".to_string()),
-                    };
+                    let default_policies = PoliciesConfiguration::default();
                     let wifi_policies = policies.as_ref().unwrap_or(&default_policies);
                     
                     let wifi_map = wifi::WifiMetricMap::new(
                         factory,
                         conf.prefix.to_owned().unwrap(),
                         Duration::seconds(api_conf.refresh.unwrap_or(5) as i64),
+                        stale_series_grace,
                         wifi_policies,
+                        device_sources.clone(),
                     );
                     maps.push(Box::new(wifi_map));
                 }
@@ -144,6 +187,8 @@ impl<'a> Mapper<'a> {
                     maps.push(Box::new(dhcp::DhcpMetricMap::new(
                         factory,
                         conf.prefix.to_owned().unwrap(),
+                        stale_series_grace,
+                        device_sources.clone(),
                     )));
                 }
             }
@@ -151,6 +196,137 @@ impl<'a> Mapper<'a> {
             warn!("DHCP metrics are disabled by default, missing entry in the configuration file");
         }
 
+        if let Some(e) = conf.storage {
+            if e {
+                maps.push(Box::new(storage::StorageMetricMap::new(
+                    factory,
+                    conf.prefix.to_owned().unwrap(),
+                    stale_series_grace,
+                    caps.raid.unwrap_or(false),
+                )));
+            }
+        } else {
+            warn!(
+                "Storage metrics are disabled by default, missing entry in the configuration file"
+            );
+        }
+
+        if let Some(e) = conf.downloads {
+            if e {
+                if caps.is_permission_denied(downloads::DOWNLOADER_PERMISSION) {
+                    warn_permission_denied("downloads", downloads::DOWNLOADER_PERMISSION);
+                } else {
+                    maps.push(Box::new(downloads::DownloadsMetricMap::new(
+                        factory,
+                        conf.prefix.to_owned().unwrap(),
+                        stale_series_grace,
+                        policies
+                            .as_ref()
+                            .and_then(|p| p.max_download_tasks)
+                            .unwrap_or(downloads::DEFAULT_MAX_DOWNLOAD_TASKS),
+                    )));
+                }
+            }
+        } else {
+            warn!(
+                "Downloads metrics are disabled by default, missing entry in the configuration file"
+            );
+        }
+
+        if let Some(e) = conf.calls {
+            if e {
+                if caps.is_permission_denied(calls::CALLS_PERMISSION) {
+                    warn_permission_denied("calls", calls::CALLS_PERMISSION);
+                } else {
+                    maps.push(Box::new(CallsMetricMap::new(
+                        factory,
+                        conf.prefix.to_owned().unwrap(),
+                        &data_directory,
+                        NumberPolicy::from_conf(
+                            policies.as_ref().and_then(|p| p.phone_numbers.as_deref()),
+                        ),
+                    )));
+                }
+            }
+        } else {
+            warn!("Calls metrics are disabled by default, missing entry in the configuration file");
+        }
+
+        if let Some(e) = conf.firewall {
+            if e {
+                maps.push(Box::new(firewall::FirewallMetricMap::new(
+                    factory,
+                    conf.prefix.to_owned().unwrap(),
+                    stale_series_grace,
+                )));
+            }
+        } else {
+            warn!(
+                "Firewall metrics are disabled by default, missing entry in the configuration file"
+            );
+        }
+
+        if let Some(e) = conf.vpn {
+            if e {
+                maps.push(Box::new(vpn::VpnMetricMap::new(
+                    factory,
+                    conf.prefix.to_owned().unwrap(),
+                    stale_series_grace,
+                )));
+            }
+        } else {
+            warn!("VPN metrics are disabled by default, missing entry in the configuration file");
+        }
+
+        if let Some(e) = conf.parental {
+            if e {
+                if caps.is_permission_denied(parental::PARENTAL_PERMISSION) {
+                    warn_permission_denied("parental", parental::PARENTAL_PERMISSION);
+                } else {
+                    maps.push(Box::new(parental::ParentalMetricMap::new(
+                        factory,
+                        conf.prefix.to_owned().unwrap(),
+                        stale_series_grace,
+                    )));
+                }
+            }
+        } else {
+            warn!(
+                "Parental control metrics are disabled by default, missing entry in the configuration file"
+            );
+        }
+
+        if let Some(e) = conf.freeplug {
+            if e {
+                maps.push(Box::new(freeplug::FreeplugMetricMap::new(
+                    factory,
+                    conf.prefix.to_owned().unwrap(),
+                    stale_series_grace,
+                )));
+            }
+        } else {
+            warn!(
+                "Freeplug metrics are disabled by default, missing entry in the configuration file"
+            );
+        }
+
+        let presence_conf = presence.filter(|p| p.enabled.unwrap_or(false));
+
+        if let Some(device_sources) = device_sources {
+            let prefix = conf.prefix.to_owned().unwrap();
+            let tracker = presence_conf.map(|p| PresenceTracker::new(&p, &data_directory, &prefix));
+
+            // registered last so it joins the responses of the current refresh
+            maps.push(Box::new(devices::DevicesMetricMap::new(
+                device_sources,
+                prefix,
+                stale_series_grace,
+                tracker,
+            )));
+        } else if presence_conf.is_some() {
+            warn!("presence detection relies on the devices metrics, enable them to detect new and departed devices");
+        }
+
         Self { maps }
     }
 
@@ -185,6 +361,31 @@ impl<'a> Mapper<'a> {
     }
 }
 
+/// Sections of a map are fetched independently so a failing API does not
+/// hide the others, the first error is returned once all of them ran and
+/// the other ones are logged
+pub fn merge_results(
+    results: Vec<Result<(), Box<dyn std::error::Error + Send + Sync>>>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut first = Ok(());
+
+    for res in results {
+        match res {
+            Err(e) if first.is_ok() => first = Err(e),
+            Err(e) => error!("{}", e),
+            Ok(()) => {}
+        }
+    }
+
+    first
+}
+
+/// Maps requiring a permission known to be refused are not registered,
+/// those whose permission is unknown warn at runtime when it is missing
+fn warn_permission_denied(option: &str, permission: &str) {
+    warn!("{option} requires the \"{permission}\" permission, grant it to the application in Freebox OS access management settings, the option has been disabled");
+}
+
 #[cfg(test)]
 mod api_specs_provider {
     /// Get the API specs data from the file system
@@ -195,4 +396,17 @@ mod api_specs_provider {
         let path = format!("src/mappers/specs-data/{}/{}.json", api, endpoint);
         tokio::fs::read_to_string(path).await
     }
+
+    /// Get the result of a successful API response from the file system
+    pub async fn get_result<T: serde::de::DeserializeOwned + Clone>(
+        api: &'static str,
+        endpoint: &'static str,
+    ) -> T {
+        let json_data = get_specs_data(api, endpoint).await.unwrap();
+
+        serde_json::from_str::<crate::core::common::transport::FreeboxResponse<T>>(&json_data)
+            .unwrap()
+            .result
+            .unwrap()
+    }
 }
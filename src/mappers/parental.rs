@@ -0,0 +1,160 @@
+use std::{collections::HashMap, time::Duration};
+
+use async_trait::async_trait;
+use prometheus_exporter::prometheus::{register_int_gauge_vec, IntGaugeVec};
+use serde::Deserialize;
+
+use crate::core::common::{
+    http_client_factory::AuthenticatedHttpClientFactory, permission::PermissionWarning,
+};
+
+use super::{client::MapClient, series::TrackedVec, MetricMap};
+
+pub const PARENTAL_PERMISSION: &str = "parental";
+const ALLOWED_MODE: &str = "allowed";
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Profile {
+    id: Option<i64>,
+    name: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct NetworkControl {
+    profile_id: Option<i64>,
+    current_mode: Option<String>,
+    next_change: Option<i64>,
+    macs: Option<Vec<String>>,
+}
+
+pub struct ParentalMetricMap<'a> {
+    client: MapClient<'a>,
+    permission_warning: PermissionWarning,
+    profile_state_gauge: TrackedVec<IntGaugeVec>,
+    profile_next_change_gauge: TrackedVec<IntGaugeVec>,
+    profile_devices_gauge: TrackedVec<IntGaugeVec>,
+}
+
+impl<'a> ParentalMetricMap<'a> {
+    pub fn new(
+        factory: &'a AuthenticatedHttpClientFactory<'a>,
+        prefix: String,
+        stale_series_grace: Duration,
+    ) -> Self {
+        let prfx = format!("{prefix}_parental");
+
+        Self {
+            client: MapClient::with_permission(factory, PARENTAL_PERMISSION),
+            permission_warning: PermissionWarning::default(),
+            profile_state_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_profile_state"),
+                    "Profile network access (allowed, denied, webonly), 1 when access is allowed",
+                    &["profile_id", "profile", "state"]
+                )
+                .expect(&format!("cannot create {prfx}_profile_state gauge")),
+                stale_series_grace,
+                &[0],
+            ),
+            profile_next_change_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_profile_next_change_timestamp_seconds"),
+                    "Profile next scheduled access change as a unix timestamp, 0 when none",
+                    &["profile_id", "profile"]
+                )
+                .expect(&format!(
+                    "cannot create {prfx}_profile_next_change_timestamp_seconds gauge"
+                )),
+                stale_series_grace,
+                &[0],
+            ),
+            profile_devices_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_profile_devices"),
+                    "Number of devices attached to the profile",
+                    &["profile_id", "profile"]
+                )
+                .expect(&format!("cannot create {prfx}_profile_devices gauge")),
+                stale_series_grace,
+                &[0],
+            ),
+        }
+    }
+
+    fn set_profiles(&self, profiles: &[Profile], controls: &[NetworkControl]) {
+        let names: HashMap<i64, String> = profiles
+            .iter()
+            .filter_map(|p| Some((p.id?, p.name.to_owned().unwrap_or_default())))
+            .collect();
+
+        for control in controls.iter() {
+            let Some(id) = control.profile_id else {
+                continue;
+            };
+
+            let profile_id = id.to_string();
+            let profile = names.get(&id).cloned().unwrap_or_default();
+            let state = control.current_mode.to_owned().unwrap_or_default();
+
+            self.profile_state_gauge
+                .with_label_values(&[&profile_id, &profile, &state])
+                .set((state == ALLOWED_MODE).into());
+            self.profile_next_change_gauge
+                .with_label_values(&[&profile_id, &profile])
+                .set(control.next_change.unwrap_or_default());
+            self.profile_devices_gauge
+                .with_label_values(&[&profile_id, &profile])
+                .set(control.macs.as_ref().map_or(0, |m| m.len() as i64));
+        }
+    }
+
+    async fn set_all(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let profiles: Vec<Profile> = self.client.get("v4/profile/").await?;
+        let controls: Vec<NetworkControl> = self.client.get("v4/network_control/").await?;
+
+        self.set_profiles(&profiles, &controls);
+
+        Ok(())
+    }
+
+    fn sweep_all(&self) {
+        self.profile_state_gauge.sweep();
+        self.profile_next_change_gauge.sweep();
+        self.profile_devices_gauge.sweep();
+    }
+}
+
+#[async_trait]
+impl<'a> MetricMap<'a> for ParentalMetricMap<'a> {
+    async fn init(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+    }
+
+    async fn set(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let res = self.set_all().await;
+
+        // deleted profiles are removed once their grace expires
+        self.sweep_all();
+
+        self.permission_warning.check("parental", res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mappers::api_specs_provider::get_result;
+
+    #[tokio::test]
+    async fn should_deserialize_profiles() {
+        let profiles: Vec<Profile> = get_result("parental", "api_v4_profile-get").await;
+        assert_eq!(Some("Kids"), profiles[0].name.as_deref());
+
+        let controls: Vec<NetworkControl> =
+            get_result("parental", "api_v4_network_control-get").await;
+        assert_eq!(Some(1), controls[0].profile_id);
+        assert_eq!(Some("denied"), controls[0].current_mode.as_deref());
+        assert_eq!(2, controls[0].macs.as_ref().unwrap().len());
+        assert_eq!(Some(1760814000), controls[0].next_change);
+    }
+}
@@ -0,0 +1,385 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use chrono::Utc;
+use log::{info, warn};
+use prometheus_exporter::prometheus::{
+    register_int_counter, register_int_gauge_vec, IntCounter, IntGaugeVec,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::core::{configuration::sections::PresenceConfiguration, webhook::Webhook};
+
+pub const DEFAULT_ABSENCE_THRESHOLD: u64 = 86400;
+pub const DEFAULT_RETENTION: u64 = 2592000;
+const STATE_FILE: &str = "presence.json";
+/// Seconds between two saves of the state when only the last seen times moved
+const LAST_SEEN_SAVE_INTERVAL: i64 = 300;
+
+const NEW_DEVICE_EVENT: &str = "new_device";
+const DEPARTED_DEVICE_EVENT: &str = "device_departed";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct KnownDevice {
+    hostname: String,
+    first_seen: i64,
+    last_seen: i64,
+    departed: bool,
+}
+
+/// MAC addresses seen on the LAN, persisted under the data directory
+#[derive(Serialize, Deserialize, Default, Debug)]
+struct PresenceState {
+    devices: BTreeMap<String, KnownDevice>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct PresenceEvent {
+    pub event: String,
+    pub mac: String,
+    pub hostname: String,
+    pub first_seen: i64,
+    pub last_seen: i64,
+}
+
+/// What an update did to the state, besides moving the last seen times
+#[derive(Default, Debug)]
+struct PresenceUpdate {
+    events: Vec<PresenceEvent>,
+    /// (MAC address, hostname) of the first seen series to remove,
+    /// the device was renamed or forgotten
+    removed: Vec<(String, String)>,
+    /// MAC addresses of the first seen series to write
+    written: Vec<String>,
+    changed: bool,
+}
+
+impl PresenceState {
+    /// Records the connected devices and forgets the devices departed for longer
+    /// than the retention. On a baseline run the devices are recorded without
+    /// raising events
+    fn update(
+        &mut self,
+        seen: &[(String, String)],
+        now: i64,
+        absence_threshold: i64,
+        retention: i64,
+        baseline: bool,
+    ) -> PresenceUpdate {
+        let mut update = PresenceUpdate::default();
+
+        for (mac, hostname) in seen.iter() {
+            match self.devices.get_mut(mac) {
+                Some(device) => {
+                    device.last_seen = now;
+                    update.changed |= device.departed;
+                    device.departed = false;
+
+                    if !hostname.is_empty() && *hostname != device.hostname {
+                        update
+                            .removed
+                            .push((mac.to_owned(), device.hostname.to_owned()));
+                        update.written.push(mac.to_owned());
+                        update.changed = true;
+
+                        device.hostname = hostname.to_owned();
+                    }
+                }
+                None => {
+                    let device = KnownDevice {
+                        hostname: hostname.to_owned(),
+                        first_seen: now,
+                        last_seen: now,
+                        departed: false,
+                    };
+
+                    if !baseline {
+                        update
+                            .events
+                            .push(get_event(NEW_DEVICE_EVENT, mac, &device));
+                    }
+
+                    update.written.push(mac.to_owned());
+                    update.changed = true;
+
+                    self.devices.insert(mac.to_owned(), device);
+                }
+            }
+        }
+
+        self.devices.retain(|mac, device| {
+            if device.departed && now - device.last_seen > retention {
+                update
+                    .removed
+                    .push((mac.to_owned(), device.hostname.to_owned()));
+                update.changed = true;
+
+                return false;
+            }
+
+            if !device.departed && now - device.last_seen > absence_threshold {
+                device.departed = true;
+                update
+                    .events
+                    .push(get_event(DEPARTED_DEVICE_EVENT, mac, device));
+                update.changed = true;
+            }
+
+            true
+        });
+
+        update
+    }
+}
+
+fn get_event(event: &str, mac: &str, device: &KnownDevice) -> PresenceEvent {
+    PresenceEvent {
+        event: event.to_string(),
+        mac: mac.to_string(),
+        hostname: device.hostname.to_owned(),
+        first_seen: device.first_seen,
+        last_seen: device.last_seen,
+    }
+}
+
+/// Detects devices appearing for the first time and known devices absent
+/// for longer than the threshold, the events are counted and optionally posted
+/// to a webhook
+pub struct PresenceTracker {
+    path: PathBuf,
+    absence_threshold: i64,
+    retention: i64,
+    webhook: Option<Webhook>,
+    state: Option<PresenceState>,
+    saved_at: i64,
+    new_devices_counter: IntCounter,
+    departed_devices_counter: IntCounter,
+    first_seen_gauge: IntGaugeVec,
+}
+
+impl PresenceTracker {
+    pub fn new(conf: &PresenceConfiguration, data_directory: &str, prefix: &str) -> Self {
+        Self {
+            path: PathBuf::from(data_directory).join(STATE_FILE),
+            absence_threshold: conf.absence_threshold.unwrap_or(DEFAULT_ABSENCE_THRESHOLD) as i64,
+            retention: conf.retention.unwrap_or(DEFAULT_RETENTION) as i64,
+            webhook: conf
+                .webhook_url
+                .to_owned()
+                .map(|url| Webhook::new(url, conf.webhook_timeout)),
+            state: None,
+            saved_at: 0,
+            new_devices_counter: register_int_counter!(
+                format!("{prefix}_presence_new_devices_total"),
+                "Devices seen for the first time"
+            )
+            .expect(&format!(
+                "cannot create {prefix}_presence_new_devices_total counter"
+            )),
+            departed_devices_counter: register_int_counter!(
+                format!("{prefix}_presence_departed_devices_total"),
+                "Known devices absent for longer than the threshold"
+            )
+            .expect(&format!(
+                "cannot create {prefix}_presence_departed_devices_total counter"
+            )),
+            first_seen_gauge: register_int_gauge_vec!(
+                format!("{prefix}_device_first_seen_timestamp_seconds"),
+                "Device first appearance as a unix timestamp",
+                &["mac", "hostname"]
+            )
+            .expect(&format!(
+                "cannot create {prefix}_device_first_seen_timestamp_seconds gauge"
+            )),
+        }
+    }
+
+    /// Without a state file every device currently connected is recorded
+    /// as a baseline, so the first run does not report the whole LAN as new
+    async fn load(&self) -> (PresenceState, bool) {
+        let content = match tokio::fs::read_to_string(&self.path).await {
+            Ok(r) => r,
+            Err(_) => {
+                info!(
+                    "no presence state found at {}, recording current devices as known",
+                    self.path.display()
+                );
+                return (PresenceState::default(), true);
+            }
+        };
+
+        match serde_json::from_str::<PresenceState>(&content) {
+            Ok(state) => (state, false),
+            Err(e) => {
+                warn!(
+                    "cannot read presence state {}, starting over: {}",
+                    self.path.display(),
+                    e
+                );
+                (PresenceState::default(), true)
+            }
+        }
+    }
+
+    async fn save(
+        &self,
+        state: &PresenceState,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let content = serde_json::to_string_pretty(state)?;
+        tokio::fs::write(&self.path, content).await?;
+
+        Ok(())
+    }
+
+    /// Tracks the devices currently connected, given as (MAC address, hostname) pairs
+    pub async fn track(
+        &mut self,
+        seen: &[(String, String)],
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let (mut state, loaded) = match self.state.take() {
+            Some(state) => (state, None),
+            None => {
+                let (state, baseline) = self.load().await;
+                (state, Some(baseline))
+            }
+        };
+
+        let now = Utc::now().timestamp();
+        let update = state.update(
+            seen,
+            now,
+            self.absence_threshold,
+            self.retention,
+            loaded.unwrap_or(false),
+        );
+
+        for (mac, hostname) in update.removed.iter() {
+            let _ = self.first_seen_gauge.remove_label_values(&[mac, hostname]);
+        }
+
+        // the series of every known device are written once, then only the changes
+        let written: Vec<&String> = match loaded {
+            Some(_) => state.devices.keys().collect(),
+            None => update.written.iter().collect(),
+        };
+
+        for mac in written {
+            if let Some(device) = state.devices.get(mac) {
+                self.first_seen_gauge
+                    .with_label_values(&[mac, &device.hostname])
+                    .set(device.first_seen);
+            }
+        }
+
+        // the last seen times alone are saved from time to time
+        let res = if update.changed || now - self.saved_at >= LAST_SEEN_SAVE_INTERVAL {
+            self.saved_at = now;
+            self.save(&state).await
+        } else {
+            Ok(())
+        };
+        self.state = Some(state);
+
+        for event in update.events.iter() {
+            info!("{}: {} ({})", event.event, event.mac, event.hostname);
+
+            match event.event.as_str() {
+                NEW_DEVICE_EVENT => self.new_devices_counter.inc(),
+                _ => self.departed_devices_counter.inc(),
+            }
+
+            if let Some(webhook) = self.webhook.as_ref() {
+                if let Err(e) = webhook.post(event).await {
+                    warn!("cannot post {} webhook: {}", event.event, e);
+                }
+            }
+        }
+
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seen(devices: &[(&str, &str)]) -> Vec<(String, String)> {
+        devices
+            .iter()
+            .map(|(mac, hostname)| (mac.to_string(), hostname.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn should_report_new_and_departed_devices() {
+        let mut state = PresenceState::default();
+
+        let update = state.update(&seen(&[("aa:01", "phone")]), 1000, 60, 600, true);
+        assert!(update.events.is_empty());
+        assert!(update.changed);
+
+        let update = state.update(
+            &seen(&[("aa:01", "phone"), ("aa:02", "")]),
+            1030,
+            60,
+            600,
+            false,
+        );
+        assert_eq!(1, update.events.len());
+        assert_eq!(NEW_DEVICE_EVENT, update.events[0].event);
+        assert_eq!("aa:02", update.events[0].mac);
+        assert_eq!(1030, update.events[0].first_seen);
+
+        let update = state.update(&seen(&[("aa:02", "")]), 1061, 60, 600, false);
+        assert!(update.events.is_empty());
+        assert!(!update.changed);
+
+        let update = state.update(&seen(&[("aa:02", "")]), 1100, 60, 600, false);
+        assert_eq!(1, update.events.len());
+        assert_eq!(DEPARTED_DEVICE_EVENT, update.events[0].event);
+        assert_eq!("aa:01", update.events[0].mac);
+        assert_eq!(1030, update.events[0].last_seen);
+
+        let update = state.update(&seen(&[("aa:02", "")]), 1200, 60, 600, false);
+        assert!(update.events.is_empty());
+        assert!(!update.changed);
+
+        let update = state.update(
+            &seen(&[("aa:01", "phone"), ("aa:02", "")]),
+            1230,
+            60,
+            600,
+            false,
+        );
+        assert!(update.events.is_empty());
+        assert!(update.changed);
+        assert!(!state.devices.get("aa:01").unwrap().departed);
+    }
+
+    #[test]
+    fn should_forget_devices_departed_beyond_retention() {
+        let mut state = PresenceState::default();
+
+        state.update(
+            &seen(&[("aa:01", "phone"), ("aa:02", "")]),
+            1000,
+            60,
+            600,
+            true,
+        );
+
+        let update = state.update(&seen(&[("aa:02", "laptop")]), 1100, 60, 600, false);
+        assert_eq!(vec![("aa:02".to_string(), "".to_string())], update.removed);
+        assert_eq!(vec!["aa:02".to_string()], update.written);
+
+        let update = state.update(&seen(&[("aa:02", "laptop")]), 1700, 60, 600, false);
+        assert!(update.events.is_empty());
+        assert_eq!(
+            vec![("aa:01".to_string(), "phone".to_string())],
+            update.removed
+        );
+        assert!(!state.devices.contains_key("aa:01"));
+
+        let update = state.update(&seen(&[("aa:01", "phone")]), 1800, 60, 600, false);
+        assert_eq!(NEW_DEVICE_EVENT, update.events[0].event);
+    }
+}
@@ -0,0 +1,187 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use prometheus_exporter::prometheus::core::{MetricVec, MetricVecBuilder};
+
+pub const DEFAULT_STALE_SERIES_GRACE: u64 = 300;
+
+pub trait SeriesVec {
+    type Metric;
+
+    fn with_label_values(&self, vals: &[&str]) -> Self::Metric;
+    fn remove_label_values(&self, vals: &[&str]);
+}
+
+impl<T: MetricVecBuilder> SeriesVec for MetricVec<T> {
+    type Metric = T::M;
+
+    fn with_label_values(&self, vals: &[&str]) -> Self::Metric {
+        MetricVec::with_label_values(self, vals)
+    }
+
+    fn remove_label_values(&self, vals: &[&str]) {
+        let _ = MetricVec::remove_label_values(self, vals);
+    }
+}
+
+/// Metric vec remembering when each label set was last written.
+/// Instead of resetting the whole vec on every refresh, series that were not
+/// seen for longer than the grace period are removed by `sweep`, so a failed
+/// API call does not make them disappear for a scrape while departed devices
+/// do not linger forever
+pub struct TrackedVec<V: SeriesVec> {
+    vec: V,
+    grace: Duration,
+    key: Vec<usize>,
+    last_seen: Mutex<HashMap<Vec<String>, Instant>>,
+}
+
+impl<V: SeriesVec> TrackedVec<V> {
+    pub fn new(vec: V, grace: Duration) -> Self {
+        Self::keyed(vec, grace, &[])
+    }
+
+    /// Label sets sharing the values at the `key` indexes describe the same
+    /// entity (e.g. station MAC address), writing a new label set for it
+    /// removes the previous one right away instead of waiting for the grace
+    pub fn keyed(vec: V, grace: Duration, key: &[usize]) -> Self {
+        Self {
+            vec,
+            grace,
+            key: key.to_vec(),
+            last_seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_label_values(&self, vals: &[&str]) -> V::Metric {
+        let labels: Vec<String> = vals.iter().map(|v| v.to_string()).collect();
+        let mut last_seen = self.last_seen.lock().unwrap();
+
+        if !self.key.is_empty() && !last_seen.contains_key(&labels) {
+            last_seen.retain(|seen, _| {
+                let same_entity = self.key.iter().all(|i| seen.get(*i) == labels.get(*i));
+
+                if same_entity {
+                    let seen: Vec<&str> = seen.iter().map(|v| v.as_str()).collect();
+                    self.vec.remove_label_values(&seen);
+                }
+
+                !same_entity
+            });
+        }
+
+        last_seen.insert(labels, Instant::now());
+
+        self.vec.with_label_values(vals)
+    }
+
+    /// Removes right away the series whose labels do not satisfy `keep`,
+    /// for entities known to be gone without waiting for the grace
+    pub fn retain(&self, keep: impl Fn(&[String]) -> bool) {
+        let mut last_seen = self.last_seen.lock().unwrap();
+
+        last_seen.retain(|labels, _| {
+            if keep(labels) {
+                return true;
+            }
+
+            let labels: Vec<&str> = labels.iter().map(|v| v.as_str()).collect();
+            self.vec.remove_label_values(&labels);
+
+            false
+        });
+    }
+
+    /// Removes the series which were not written during the grace period
+    pub fn sweep(&self) {
+        self.sweep_at(Instant::now());
+    }
+
+    fn sweep_at(&self, now: Instant) {
+        let mut last_seen = self.last_seen.lock().unwrap();
+
+        last_seen.retain(|labels, seen| {
+            if now.saturating_duration_since(*seen) <= self.grace {
+                return true;
+            }
+
+            let labels: Vec<&str> = labels.iter().map(|v| v.as_str()).collect();
+            self.vec.remove_label_values(&labels);
+
+            false
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prometheus_exporter::prometheus::{core::Collector, IntGaugeVec, Opts};
+
+    use super::*;
+
+    fn get_series(vec: &IntGaugeVec) -> Vec<Vec<String>> {
+        vec.collect()[0]
+            .get_metric()
+            .iter()
+            .map(|m| {
+                m.get_label()
+                    .iter()
+                    .map(|l| l.get_value().to_string())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_remove_series_after_grace() {
+        let vec = IntGaugeVec::new(Opts::new("test_tracked", "test"), &["mac"]).unwrap();
+        let tracked = TrackedVec::new(vec.clone(), Duration::from_secs(60));
+
+        tracked.with_label_values(&["00:01"]).set(1);
+        tracked.with_label_values(&["00:02"]).set(1);
+
+        tracked.sweep();
+        assert_eq!(2, get_series(&vec).len());
+
+        tracked.sweep_at(Instant::now() + Duration::from_secs(61));
+        assert!(vec.collect()[0].get_metric().is_empty());
+    }
+
+    #[test]
+    fn should_replace_series_of_same_entity() {
+        let vec = IntGaugeVec::new(Opts::new("test_keyed", "test"), &["hostname", "mac"]).unwrap();
+        let tracked = TrackedVec::keyed(vec.clone(), Duration::from_secs(60), &[1]);
+
+        tracked.with_label_values(&["phone", "00:01"]).set(1);
+        tracked.with_label_values(&["laptop", "00:02"]).set(1);
+        tracked
+            .with_label_values(&["renamed-phone", "00:01"])
+            .set(1);
+
+        let mut series = get_series(&vec);
+        series.sort();
+
+        assert_eq!(
+            vec![
+                vec!["laptop".to_string(), "00:02".to_string()],
+                vec!["renamed-phone".to_string(), "00:01".to_string()],
+            ],
+            series
+        );
+    }
+
+    #[test]
+    fn should_retain_kept_series_only() {
+        let vec = IntGaugeVec::new(Opts::new("test_retained", "test"), &["id"]).unwrap();
+        let tracked = TrackedVec::new(vec.clone(), Duration::from_secs(60));
+
+        tracked.with_label_values(&["1"]).set(1);
+        tracked.with_label_values(&["2"]).set(1);
+        tracked.retain(|labels| labels[0] == "2");
+
+        assert_eq!(vec![vec!["2".to_string()]], get_series(&vec));
+    }
+}
@@ -0,0 +1,558 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use prometheus_exporter::prometheus::{
+    register_int_counter_vec, register_int_gauge_vec, IntCounterVec, IntGaugeVec,
+};
+use serde::Deserialize;
+
+use crate::core::common::http_client_factory::AuthenticatedHttpClientFactory;
+
+use super::{
+    client::MapClient, counters::set_counter, merge_results, series::TrackedVec, MetricMap,
+};
+
+const DISK_ENABLED_STATE: &str = "enabled";
+const PARTITION_MOUNTED_STATE: &str = "mounted";
+const RAID_RUNNING_STATE: &str = "running";
+const RAID_ACTIVE_ROLE: &str = "active";
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Disk {
+    id: Option<i64>,
+    #[serde(rename = "type")]
+    _type: Option<String>,
+    state: Option<String>,
+    connector: Option<i64>,
+    model: Option<String>,
+    serial: Option<String>,
+    firmware: Option<String>,
+    table_type: Option<String>,
+    spinning: Option<bool>,
+    temp: Option<i64>,
+    total_bytes: Option<i64>,
+    read_requests: Option<i64>,
+    read_error_requests: Option<i64>,
+    write_requests: Option<i64>,
+    write_error_requests: Option<i64>,
+}
+
+impl Disk {
+    /// The API exposes no raw SMART attributes, a disk is deemed healthy
+    /// while the box keeps it enabled and no request failed
+    fn is_healthy(&self) -> bool {
+        self.state.as_deref() == Some(DISK_ENABLED_STATE)
+            && self.read_error_requests.unwrap_or_default() == 0
+            && self.write_error_requests.unwrap_or_default() == 0
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Partition {
+    id: Option<i64>,
+    disk_id: Option<i64>,
+    label: Option<String>,
+    fstype: Option<String>,
+    state: Option<String>,
+    fsck_result: Option<String>,
+    total_bytes: Option<i64>,
+    used_bytes: Option<i64>,
+    free_bytes: Option<i64>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct RaidArray {
+    id: Option<i64>,
+    name: Option<String>,
+    state: Option<String>,
+    level: Option<String>,
+    sync_action: Option<String>,
+    sync_completed_pos: Option<i64>,
+    sync_completed_end: Option<i64>,
+    degraded: Option<bool>,
+    members: Option<Vec<RaidMember>>,
+}
+
+impl RaidArray {
+    /// Sync, resync or recovery progress, 100 when no operation is running
+    fn get_sync_progress(&self) -> i64 {
+        match (self.sync_completed_pos, self.sync_completed_end) {
+            (Some(pos), Some(end)) if end > 0 => (pos * 100 / end).clamp(0, 100),
+            _ => 100,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct RaidMember {
+    id: Option<i64>,
+    role: Option<String>,
+    disk: Option<RaidMemberDisk>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct RaidMemberDisk {
+    id: Option<i64>,
+    state: Option<String>,
+}
+
+pub struct StorageMetricMap<'a> {
+    client: MapClient<'a>,
+    raid: bool,
+    disk_info_gauge: TrackedVec<IntGaugeVec>,
+    disk_state_gauge: TrackedVec<IntGaugeVec>,
+    disk_healthy_gauge: TrackedVec<IntGaugeVec>,
+    disk_temperature_gauge: TrackedVec<IntGaugeVec>,
+    disk_spinning_gauge: TrackedVec<IntGaugeVec>,
+    disk_total_bytes_gauge: TrackedVec<IntGaugeVec>,
+    disk_read_requests_counter: TrackedVec<IntCounterVec>,
+    disk_read_error_requests_counter: TrackedVec<IntCounterVec>,
+    disk_write_requests_counter: TrackedVec<IntCounterVec>,
+    disk_write_error_requests_counter: TrackedVec<IntCounterVec>,
+    partition_info_gauge: TrackedVec<IntGaugeVec>,
+    partition_state_gauge: TrackedVec<IntGaugeVec>,
+    partition_total_bytes_gauge: TrackedVec<IntGaugeVec>,
+    partition_used_bytes_gauge: TrackedVec<IntGaugeVec>,
+    partition_free_bytes_gauge: TrackedVec<IntGaugeVec>,
+    raid_info_gauge: TrackedVec<IntGaugeVec>,
+    raid_state_gauge: TrackedVec<IntGaugeVec>,
+    raid_degraded_gauge: TrackedVec<IntGaugeVec>,
+    raid_sync_progress_gauge: TrackedVec<IntGaugeVec>,
+    raid_member_state_gauge: TrackedVec<IntGaugeVec>,
+}
+
+impl<'a> StorageMetricMap<'a> {
+    /// RAID arrays are only read on boards supporting them
+    pub fn new(
+        factory: &'a AuthenticatedHttpClientFactory<'a>,
+        prefix: String,
+        stale_series_grace: Duration,
+        raid: bool,
+    ) -> Self {
+        let prfx = format!("{prefix}_storage");
+
+        let disk_gauge = |name: &str, help: &str| {
+            TrackedVec::new(
+                register_int_gauge_vec!(format!("{prfx}_disk_{name}"), help, &["disk"])
+                    .expect(&format!("cannot create {prfx}_disk_{name} gauge")),
+                stale_series_grace,
+            )
+        };
+
+        let disk_counter = |name: &str, help: &str| {
+            TrackedVec::new(
+                register_int_counter_vec!(format!("{prfx}_disk_{name}_total"), help, &["disk"])
+                    .expect(&format!("cannot create {prfx}_disk_{name}_total counter")),
+                stale_series_grace,
+            )
+        };
+
+        let partition_gauge = |name: &str, help: &str| {
+            TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_partition_{name}"),
+                    help,
+                    &["partition", "disk", "label"]
+                )
+                .expect(&format!("cannot create {prfx}_partition_{name} gauge")),
+                stale_series_grace,
+                &[0],
+            )
+        };
+
+        Self {
+            client: MapClient::new(factory),
+            raid,
+            disk_info_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_disk_info"),
+                    "Disk description, always 1",
+                    &[
+                        "disk",
+                        "type",
+                        "connector",
+                        "model",
+                        "serial",
+                        "firmware",
+                        "table_type"
+                    ]
+                )
+                .expect(&format!("cannot create {prfx}_disk_info gauge")),
+                stale_series_grace,
+                &[0],
+            ),
+            disk_state_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_disk_state"),
+                    "Disk state, 1 when the disk is enabled",
+                    &["disk", "state"]
+                )
+                .expect(&format!("cannot create {prfx}_disk_state gauge")),
+                stale_series_grace,
+                &[0],
+            ),
+            disk_healthy_gauge: disk_gauge(
+                "healthy",
+                "Disk health, 1 when the disk is enabled without read or write errors",
+            ),
+            disk_temperature_gauge: disk_gauge(
+                "temperature_celsius",
+                "Disk temperature in degrees Celsius, only for disks reporting it",
+            ),
+            disk_spinning_gauge: disk_gauge("spinning", "Disk spinning status, 1 when spinning"),
+            disk_total_bytes_gauge: disk_gauge("total_bytes", "Disk size in bytes"),
+            disk_read_requests_counter: disk_counter("read_requests", "Disk read requests"),
+            disk_read_error_requests_counter: disk_counter(
+                "read_error_requests",
+                "Disk read requests that failed",
+            ),
+            disk_write_requests_counter: disk_counter("write_requests", "Disk write requests"),
+            disk_write_error_requests_counter: disk_counter(
+                "write_error_requests",
+                "Disk write requests that failed",
+            ),
+            partition_info_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_partition_info"),
+                    "Partition description, always 1",
+                    &["partition", "disk", "label", "fstype", "fsck_result"]
+                )
+                .expect(&format!("cannot create {prfx}_partition_info gauge")),
+                stale_series_grace,
+                &[0],
+            ),
+            partition_state_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_partition_state"),
+                    "Partition filesystem state, 1 when the partition is mounted",
+                    &["partition", "state"]
+                )
+                .expect(&format!("cannot create {prfx}_partition_state gauge")),
+                stale_series_grace,
+                &[0],
+            ),
+            partition_total_bytes_gauge: partition_gauge("total_bytes", "Partition size in bytes"),
+            partition_used_bytes_gauge: partition_gauge(
+                "used_bytes",
+                "Partition used space in bytes",
+            ),
+            partition_free_bytes_gauge: partition_gauge(
+                "free_bytes",
+                "Partition free space in bytes",
+            ),
+            raid_info_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_raid_info"),
+                    "RAID array description, always 1",
+                    &["array", "name", "level"]
+                )
+                .expect(&format!("cannot create {prfx}_raid_info gauge")),
+                stale_series_grace,
+                &[0],
+            ),
+            raid_state_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_raid_state"),
+                    "RAID array state, 1 when the array is running",
+                    &["array", "state"]
+                )
+                .expect(&format!("cannot create {prfx}_raid_state gauge")),
+                stale_series_grace,
+                &[0],
+            ),
+            raid_degraded_gauge: TrackedVec::new(
+                register_int_gauge_vec!(
+                    format!("{prfx}_raid_degraded"),
+                    "RAID array redundancy, 1 when the array is degraded",
+                    &["array"]
+                )
+                .expect(&format!("cannot create {prfx}_raid_degraded gauge")),
+                stale_series_grace,
+            ),
+            raid_sync_progress_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_raid_sync_progress_percent"),
+                    "RAID array sync, resync or recovery progress, 100 when idle",
+                    &["array", "action"]
+                )
+                .expect(&format!(
+                    "cannot create {prfx}_raid_sync_progress_percent gauge"
+                )),
+                stale_series_grace,
+                &[0],
+            ),
+            raid_member_state_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_raid_member_state"),
+                    "RAID array member state, 1 when the member is active",
+                    &["array", "member", "disk", "role", "disk_state"]
+                )
+                .expect(&format!("cannot create {prfx}_raid_member_state gauge")),
+                stale_series_grace,
+                &[0, 1],
+            ),
+        }
+    }
+
+    fn set_disk(&self, disk: &Disk) {
+        let id = disk.id.unwrap_or_default().to_string();
+        let labels = [id.as_str()];
+
+        self.disk_info_gauge
+            .with_label_values(&[
+                &id,
+                &disk._type.to_owned().unwrap_or_default(),
+                &disk.connector.unwrap_or_default().to_string(),
+                &disk.model.to_owned().unwrap_or_default(),
+                &disk.serial.to_owned().unwrap_or_default(),
+                &disk.firmware.to_owned().unwrap_or_default(),
+                &disk.table_type.to_owned().unwrap_or_default(),
+            ])
+            .set(1);
+
+        let state = disk.state.to_owned().unwrap_or_default();
+        self.disk_state_gauge
+            .with_label_values(&[&id, &state])
+            .set((state == DISK_ENABLED_STATE).into());
+
+        self.disk_healthy_gauge
+            .with_label_values(&labels)
+            .set(disk.is_healthy().into());
+
+        if let Some(temp) = disk.temp {
+            self.disk_temperature_gauge
+                .with_label_values(&labels)
+                .set(temp);
+        }
+
+        self.disk_spinning_gauge
+            .with_label_values(&labels)
+            .set(disk.spinning.unwrap_or_default().into());
+        self.disk_total_bytes_gauge
+            .with_label_values(&labels)
+            .set(disk.total_bytes.unwrap_or_default());
+
+        set_counter(
+            &self.disk_read_requests_counter.with_label_values(&labels),
+            disk.read_requests.unwrap_or_default(),
+        );
+        set_counter(
+            &self
+                .disk_read_error_requests_counter
+                .with_label_values(&labels),
+            disk.read_error_requests.unwrap_or_default(),
+        );
+        set_counter(
+            &self.disk_write_requests_counter.with_label_values(&labels),
+            disk.write_requests.unwrap_or_default(),
+        );
+        set_counter(
+            &self
+                .disk_write_error_requests_counter
+                .with_label_values(&labels),
+            disk.write_error_requests.unwrap_or_default(),
+        );
+    }
+
+    fn set_partition(&self, partition: &Partition) {
+        let id = partition.id.unwrap_or_default().to_string();
+        let disk = partition.disk_id.unwrap_or_default().to_string();
+        let label = partition.label.to_owned().unwrap_or_default();
+        let labels = [id.as_str(), disk.as_str(), label.as_str()];
+
+        self.partition_info_gauge
+            .with_label_values(&[
+                &id,
+                &disk,
+                &label,
+                &partition.fstype.to_owned().unwrap_or_default(),
+                &partition.fsck_result.to_owned().unwrap_or_default(),
+            ])
+            .set(1);
+
+        let state = partition.state.to_owned().unwrap_or_default();
+        self.partition_state_gauge
+            .with_label_values(&[&id, &state])
+            .set((state == PARTITION_MOUNTED_STATE).into());
+
+        self.partition_total_bytes_gauge
+            .with_label_values(&labels)
+            .set(partition.total_bytes.unwrap_or_default());
+        self.partition_used_bytes_gauge
+            .with_label_values(&labels)
+            .set(partition.used_bytes.unwrap_or_default());
+        self.partition_free_bytes_gauge
+            .with_label_values(&labels)
+            .set(partition.free_bytes.unwrap_or_default());
+    }
+
+    fn set_raid(&self, array: &RaidArray) {
+        let id = array.id.unwrap_or_default().to_string();
+
+        self.raid_info_gauge
+            .with_label_values(&[
+                &id,
+                &array.name.to_owned().unwrap_or_default(),
+                &array.level.to_owned().unwrap_or_default(),
+            ])
+            .set(1);
+
+        let state = array.state.to_owned().unwrap_or_default();
+        self.raid_state_gauge
+            .with_label_values(&[&id, &state])
+            .set((state == RAID_RUNNING_STATE).into());
+
+        self.raid_degraded_gauge
+            .with_label_values(&[&id])
+            .set(array.degraded.unwrap_or_default().into());
+
+        self.raid_sync_progress_gauge
+            .with_label_values(&[&id, &array.sync_action.to_owned().unwrap_or_default()])
+            .set(array.get_sync_progress());
+
+        for member in array.members.as_deref().unwrap_or_default() {
+            let role = member.role.to_owned().unwrap_or_default();
+            let disk = member.disk.as_ref();
+
+            self.raid_member_state_gauge
+                .with_label_values(&[
+                    &id,
+                    &member.id.unwrap_or_default().to_string(),
+                    &disk
+                        .and_then(|d| d.id)
+                        .map(|d| d.to_string())
+                        .unwrap_or_default(),
+                    &role,
+                    &disk.and_then(|d| d.state.to_owned()).unwrap_or_default(),
+                ])
+                .set((role == RAID_ACTIVE_ROLE).into());
+        }
+    }
+
+    async fn set_disks(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let disks: Vec<Disk> = self.client.get("v4/storage/disk/").await?;
+
+        for disk in disks.iter() {
+            self.set_disk(disk);
+        }
+
+        Ok(())
+    }
+
+    async fn set_partitions(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let partitions: Vec<Partition> = self.client.get("v4/storage/partition/").await?;
+
+        for partition in partitions.iter() {
+            self.set_partition(partition);
+        }
+
+        Ok(())
+    }
+
+    async fn set_raids(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if !self.raid {
+            return Ok(());
+        }
+
+        let arrays: Vec<RaidArray> = self.client.get("v4/storage/raid/").await?;
+
+        for array in arrays.iter() {
+            self.set_raid(array);
+        }
+
+        Ok(())
+    }
+
+    /// Disks, partitions and RAID arrays come from distinct APIs, a failing
+    /// one does not hide the others
+    async fn set_all(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let disks_res = self.set_disks().await;
+        let partitions_res = self.set_partitions().await;
+        let raids_res = self.set_raids().await;
+
+        merge_results(vec![disks_res, partitions_res, raids_res])
+    }
+
+    fn sweep_all(&self) {
+        self.disk_info_gauge.sweep();
+        self.disk_state_gauge.sweep();
+        self.disk_healthy_gauge.sweep();
+        self.disk_temperature_gauge.sweep();
+        self.disk_spinning_gauge.sweep();
+        self.disk_total_bytes_gauge.sweep();
+        self.disk_read_requests_counter.sweep();
+        self.disk_read_error_requests_counter.sweep();
+        self.disk_write_requests_counter.sweep();
+        self.disk_write_error_requests_counter.sweep();
+        self.partition_info_gauge.sweep();
+        self.partition_state_gauge.sweep();
+        self.partition_total_bytes_gauge.sweep();
+        self.partition_used_bytes_gauge.sweep();
+        self.partition_free_bytes_gauge.sweep();
+        self.raid_info_gauge.sweep();
+        self.raid_state_gauge.sweep();
+        self.raid_degraded_gauge.sweep();
+        self.raid_sync_progress_gauge.sweep();
+        self.raid_member_state_gauge.sweep();
+    }
+}
+
+#[async_trait]
+impl<'a> MetricMap<'a> for StorageMetricMap<'a> {
+    async fn init(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+    }
+
+    async fn set(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let res = self.set_all().await;
+
+        // unplugged disks are removed once their grace expires
+        self.sweep_all();
+
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mappers::api_specs_provider::get_result;
+
+    #[tokio::test]
+    async fn should_derive_disk_health() {
+        let disks: Vec<Disk> = get_result("storage", "api_v4_storage_disk-get").await;
+
+        assert_eq!(2, disks.len());
+        assert!(disks[0].is_healthy());
+        assert_eq!(Some(41), disks[0].temp);
+        assert!(!disks[1].is_healthy());
+    }
+
+    #[tokio::test]
+    async fn should_deserialize_partitions() {
+        let partitions: Vec<Partition> =
+            get_result("storage", "api_v4_storage_partition-get").await;
+
+        let partition = &partitions[0];
+        assert_eq!(Some(0), partition.disk_id);
+        assert_eq!(Some(PARTITION_MOUNTED_STATE), partition.state.as_deref());
+        assert!(
+            partition.used_bytes.unwrap() + partition.free_bytes.unwrap()
+                <= partition.total_bytes.unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn should_report_raid_recovery() {
+        let arrays: Vec<RaidArray> = get_result("storage", "api_v4_storage_raid-get").await;
+
+        let array = &arrays[0];
+        assert_eq!(Some(true), array.degraded);
+        assert_eq!(Some("recover"), array.sync_action.as_deref());
+        assert_eq!(25, array.get_sync_progress());
+
+        let members = array.members.as_ref().unwrap();
+        assert_eq!(Some(RAID_ACTIVE_ROLE), members[0].role.as_deref());
+        assert_eq!(Some(1), members[1].disk.as_ref().unwrap().id);
+    }
+}
@@ -1,24 +1,27 @@
 use async_trait::async_trait;
 use lazy_static::lazy_static;
 use log::debug;
-use prometheus_exporter::prometheus::{register_int_gauge_vec, IntGaugeVec};
+use prometheus_exporter::prometheus::{
+    register_int_counter_vec, register_int_gauge_vec, IntCounterVec, IntGaugeVec,
+};
 use regex::Regex;
 use reqwest::Client;
 use serde::Deserialize;
+use std::time::Duration;
 
 use crate::core::common::{
     http_client_factory::{AuthenticatedHttpClientFactory, ManagedHttpClient},
     transport::{FreeboxResponse, FreeboxResponseError},
 };
 
-use super::MetricMap;
+use super::{counters::set_counter, devices::SharedDeviceSources, series::TrackedVec, MetricMap};
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct SwitchPortStatus {
-    id: Option<i16>,
+    pub id: Option<i16>,
     link: Option<String>,
-    speed: Option<String>,
-    mac_list: Option<Vec<SwitchPortHost>>,
+    pub speed: Option<String>,
+    pub mac_list: Option<Vec<SwitchPortHost>>,
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -57,274 +60,319 @@ pub struct SwitchPortStats {
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct SwitchPortHost {
-    mac: Option<String>,
-    hostname: Option<String>,
+    pub mac: Option<String>,
+    pub hostname: Option<String>,
 }
 
 pub struct SwitchMetricMap<'a> {
     factory: &'a AuthenticatedHttpClientFactory<'a>,
     managed_client: Option<ManagedHttpClient>,
+    device_sources: Option<SharedDeviceSources>,
     rx_packets_rate_gauge: IntGaugeVec,
-    rx_good_bytes_gauge: IntGaugeVec,
-    rx_oversize_packets_gauge: IntGaugeVec,
-    rx_unicast_packets_gauge: IntGaugeVec,
+    rx_good_bytes_counter: IntCounterVec,
+    rx_oversize_packets_counter: IntCounterVec,
+    rx_unicast_packets_counter: IntCounterVec,
     tx_bytes_rate_gauge: IntGaugeVec,
-    tx_unicast_packets_gauge: IntGaugeVec,
+    tx_unicast_packets_counter: IntCounterVec,
     rx_bytes_rate_gauge: IntGaugeVec,
-    tx_packets_gauge: IntGaugeVec,
-    tx_collisions_gauge: IntGaugeVec,
+    tx_packets_counter: IntCounterVec,
+    tx_collisions_counter: IntCounterVec,
     tx_packets_rate_gauge: IntGaugeVec,
-    tx_fcs_gauge: IntGaugeVec,
-    tx_bytes_gauge: IntGaugeVec,
-    rx_jabber_packets_gauge: IntGaugeVec,
-    tx_single_gauge: IntGaugeVec,
-    tx_excessive_gauge: IntGaugeVec,
-    rx_pause_gauge: IntGaugeVec,
-    rx_multicast_packets_gauge: IntGaugeVec,
-    tx_pause_gauge: IntGaugeVec,
-    rx_good_packets_gauge: IntGaugeVec,
-    rx_broadcast_packets_gauge: IntGaugeVec,
-    tx_multiple_gauge: IntGaugeVec,
-    tx_deferred_gauge: IntGaugeVec,
-    tx_late_gauge: IntGaugeVec,
-    tx_multicast_packets_gauge: IntGaugeVec,
-    rx_fcs_packets_gauge: IntGaugeVec,
-    tx_broadcast_packets_gauge: IntGaugeVec,
-    rx_err_packets_gauge: IntGaugeVec,
-    rx_fragments_packets_gauge: IntGaugeVec,
-    rx_bad_bytes_gauge: IntGaugeVec,
-    rx_undersize_packets_gauge: IntGaugeVec,
+    tx_fcs_counter: IntCounterVec,
+    tx_bytes_counter: IntCounterVec,
+    rx_jabber_packets_counter: IntCounterVec,
+    tx_single_counter: IntCounterVec,
+    tx_excessive_counter: IntCounterVec,
+    rx_pause_counter: IntCounterVec,
+    rx_multicast_packets_counter: IntCounterVec,
+    tx_pause_counter: IntCounterVec,
+    rx_good_packets_counter: IntCounterVec,
+    rx_broadcast_packets_counter: IntCounterVec,
+    tx_multiple_counter: IntCounterVec,
+    tx_deferred_counter: IntCounterVec,
+    tx_late_counter: IntCounterVec,
+    tx_multicast_packets_counter: IntCounterVec,
+    rx_fcs_packets_counter: IntCounterVec,
+    tx_broadcast_packets_counter: IntCounterVec,
+    rx_err_packets_counter: IntCounterVec,
+    rx_fragments_packets_counter: IntCounterVec,
+    rx_bad_bytes_counter: IntCounterVec,
+    rx_undersize_packets_counter: IntCounterVec,
     port_status_gauge: IntGaugeVec,
     port_speed_gauge: IntGaugeVec,
-    port_mac_list_gauge: IntGaugeVec,
+    port_mac_list_gauge: TrackedVec<IntGaugeVec>,
 }
 
 impl<'a> SwitchMetricMap<'a> {
-    pub fn new(factory: &'a AuthenticatedHttpClientFactory<'a>, prefix: String) -> Self {
+    pub fn new(
+        factory: &'a AuthenticatedHttpClientFactory<'a>,
+        prefix: String,
+        stale_series_grace: Duration,
+        device_sources: Option<SharedDeviceSources>,
+    ) -> Self {
         let prfx: String = format!("{prefix}_switch");
         let stats_prfx: String = format!("{prfx}_stats");
 
         Self {
             factory,
             managed_client: None,
+            device_sources,
             rx_packets_rate_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_rx_packets_rate"),
-                "rx packet rate",
+                format!("{stats_prfx}_rx_packets_per_second"),
+                "Received frames per second",
                 &["port"]
             )
             .expect(&format!("cannot create {stats_prfx}_rx_packet_rate gauge")),
-            rx_good_bytes_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_rx_good_bytes"),
-                "rx good bytes",
+            rx_good_bytes_counter: register_int_counter_vec!(
+                format!("{stats_prfx}_rx_good_bytes_total"),
+                "Bytes received in good frames",
                 &["port"]
             )
-            .expect(&format!("cannot create {stats_prfx}_rx_good_bytes gauge")),
-            rx_oversize_packets_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_rx_oversize_packets"),
-                "rx oversize packets",
+            .expect(&format!(
+                "cannot create {stats_prfx}_rx_good_bytes_total counter"
+            )),
+            rx_oversize_packets_counter: register_int_counter_vec!(
+                format!("{stats_prfx}_rx_oversize_packets_total"),
+                "Received frames larger than the maximum size",
                 &["port"]
             )
             .expect(&format!(
-                "cannot create {stats_prfx}_rx_oversize_packets gauge"
+                "cannot create {stats_prfx}_rx_oversize_packets_total counter"
             )),
-            rx_unicast_packets_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_rx_unicast_packets"),
-                "rx unicast packets",
+            rx_unicast_packets_counter: register_int_counter_vec!(
+                format!("{stats_prfx}_rx_unicast_packets_total"),
+                "Received unicast frames",
                 &["port"]
             )
             .expect(&format!(
-                "cannot create {stats_prfx}_rx_unicast_packets gauge"
+                "cannot create {stats_prfx}_rx_unicast_packets_total counter"
             )),
             tx_bytes_rate_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_tx_bytes_rate"),
-                "tx bytes rate",
+                format!("{stats_prfx}_tx_bytes_per_second"),
+                "Transmitted bytes per second",
                 &["port"]
             )
-            .expect(&format!("cannot create {stats_prfx}_tx_bytes_rate gauge")),
-            tx_unicast_packets_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_tx_unicast_packets"),
-                "tx unicast packets",
+            .expect(&format!(
+                "cannot create {stats_prfx}_tx_bytes_per_second gauge"
+            )),
+            tx_unicast_packets_counter: register_int_counter_vec!(
+                format!("{stats_prfx}_tx_unicast_packets_total"),
+                "Transmitted unicast frames",
                 &["port"]
             )
             .expect(&format!(
-                "cannot create {stats_prfx}_tx_unicast_packets gauge"
+                "cannot create {stats_prfx}_tx_unicast_packets_total counter"
             )),
             rx_bytes_rate_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_rx_bytes_rate"),
-                "rx bytes rate",
+                format!("{stats_prfx}_rx_bytes_per_second"),
+                "Received bytes per second",
                 &["port"]
             )
-            .expect(&format!("cannot create {stats_prfx}_rx_bytes_rate gauge")),
-            tx_packets_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_tx_packets"),
-                "tx packets",
+            .expect(&format!(
+                "cannot create {stats_prfx}_rx_bytes_per_second gauge"
+            )),
+            tx_packets_counter: register_int_counter_vec!(
+                format!("{stats_prfx}_tx_packets_total"),
+                "Transmitted frames",
                 &["port"]
             )
-            .expect(&format!("cannot create {stats_prfx}_tx_packets gauge")),
-            tx_collisions_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_tx_collisions"),
-                "tx collisions",
+            .expect(&format!(
+                "cannot create {stats_prfx}_tx_packets_total counter"
+            )),
+            tx_collisions_counter: register_int_counter_vec!(
+                format!("{stats_prfx}_tx_collisions_total"),
+                "Transmit collisions",
                 &["port"]
             )
-            .expect(&format!("cannot create {stats_prfx}_tx_collisions gauge")),
+            .expect(&format!(
+                "cannot create {stats_prfx}_tx_collisions_total counter"
+            )),
             tx_packets_rate_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_tx_packets_rate"),
-                "tx packets rate",
+                format!("{stats_prfx}_tx_packets_per_second"),
+                "Transmitted frames per second",
                 &["port"]
             )
-            .expect(&format!("cannot create {stats_prfx}_tx_packets_rate gauge")),
-            tx_fcs_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_tx_fcs"),
-                "tx fcs",
+            .expect(&format!(
+                "cannot create {stats_prfx}_tx_packets_per_second gauge"
+            )),
+            tx_fcs_counter: register_int_counter_vec!(
+                format!("{stats_prfx}_tx_fcs_total"),
+                "Transmitted frames with a frame check sequence error",
                 &["port"]
             )
-            .expect(&format!("cannot create {stats_prfx}_tx_fcs gauge")),
-            tx_bytes_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_tx_bytes"),
-                "tx bytes",
+            .expect(&format!("cannot create {stats_prfx}_tx_fcs_total counter")),
+            tx_bytes_counter: register_int_counter_vec!(
+                format!("{stats_prfx}_tx_bytes_total"),
+                "Transmitted bytes",
                 &["port"]
             )
-            .expect(&format!("cannot create {stats_prfx}_tx_bytes gauge")),
-            rx_jabber_packets_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_rx_jabber_packets"),
-                "rx jabber packets",
+            .expect(&format!(
+                "cannot create {stats_prfx}_tx_bytes_total counter"
+            )),
+            rx_jabber_packets_counter: register_int_counter_vec!(
+                format!("{stats_prfx}_rx_jabber_packets_total"),
+                "Received jabber frames",
                 &["port"]
             )
             .expect(&format!(
-                "cannot create {stats_prfx}_rx_jabber_packets gauge"
+                "cannot create {stats_prfx}_rx_jabber_packets_total counter"
             )),
-            tx_single_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_tx_single"),
-                "tx single",
+            tx_single_counter: register_int_counter_vec!(
+                format!("{stats_prfx}_tx_single_total"),
+                "Frames transmitted after a single collision",
                 &["port"]
             )
-            .expect(&format!("cannot create {stats_prfx}_tx_single gauge")),
-            tx_excessive_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_tx_excessive"),
-                "tx excessive",
+            .expect(&format!(
+                "cannot create {stats_prfx}_tx_single_total counter"
+            )),
+            tx_excessive_counter: register_int_counter_vec!(
+                format!("{stats_prfx}_tx_excessive_total"),
+                "Frames dropped after excessive collisions",
                 &["port"]
             )
-            .expect(&format!("cannot create {stats_prfx}_tx_excessive gauge")),
-            rx_pause_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_rx_pause"),
-                "rx pause",
+            .expect(&format!(
+                "cannot create {stats_prfx}_tx_excessive_total counter"
+            )),
+            rx_pause_counter: register_int_counter_vec!(
+                format!("{stats_prfx}_rx_pause_total"),
+                "Received pause frames",
                 &["port"]
             )
-            .expect(&format!("cannot create {stats_prfx}_rx_pause gauge")),
-            rx_multicast_packets_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_rx_multicast_packets"),
-                "rx multicast packets",
+            .expect(&format!(
+                "cannot create {stats_prfx}_rx_pause_total counter"
+            )),
+            rx_multicast_packets_counter: register_int_counter_vec!(
+                format!("{stats_prfx}_rx_multicast_packets_total"),
+                "Received multicast frames",
                 &["port"]
             )
             .expect(&format!(
-                "cannot create {stats_prfx}_rx_multicast_packets gauge"
+                "cannot create {stats_prfx}_rx_multicast_packets_total counter"
             )),
-            tx_pause_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_tx_pause"),
-                "tx pause",
+            tx_pause_counter: register_int_counter_vec!(
+                format!("{stats_prfx}_tx_pause_total"),
+                "Transmitted pause frames",
                 &["port"]
             )
-            .expect(&format!("cannot create {stats_prfx}_tx_pause gauge")),
-            rx_good_packets_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_rx_good_packets"),
-                "tx good packets",
+            .expect(&format!(
+                "cannot create {stats_prfx}_tx_pause_total counter"
+            )),
+            rx_good_packets_counter: register_int_counter_vec!(
+                format!("{stats_prfx}_rx_good_packets_total"),
+                "Received good frames",
                 &["port"]
             )
-            .expect(&format!("cannot create {stats_prfx}_rx_good_packets gauge")),
-            rx_broadcast_packets_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_rx_broadcast_packets"),
-                "rx broadcast packets",
+            .expect(&format!(
+                "cannot create {stats_prfx}_rx_good_packets_total counter"
+            )),
+            rx_broadcast_packets_counter: register_int_counter_vec!(
+                format!("{stats_prfx}_rx_broadcast_packets_total"),
+                "Received broadcast frames",
                 &["port"]
             )
             .expect(&format!(
-                "cannot create {stats_prfx}_rx_broadcast_packets gauge"
+                "cannot create {stats_prfx}_rx_broadcast_packets_total counter"
             )),
-            tx_multiple_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_tx_multiple"),
-                "tx multiple",
+            tx_multiple_counter: register_int_counter_vec!(
+                format!("{stats_prfx}_tx_multiple_total"),
+                "Frames transmitted after multiple collisions",
                 &["port"]
             )
-            .expect(&format!("cannot create {stats_prfx}_tx_multiple gauge")),
-            tx_deferred_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_tx_deferred"),
-                "tx deferred",
+            .expect(&format!(
+                "cannot create {stats_prfx}_tx_multiple_total counter"
+            )),
+            tx_deferred_counter: register_int_counter_vec!(
+                format!("{stats_prfx}_tx_deferred_total"),
+                "Frames whose transmission was deferred",
                 &["port"]
             )
-            .expect(&format!("cannot create {stats_prfx}_tx_deferred gauge")),
-            tx_late_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_tx_late"),
-                "tx late",
+            .expect(&format!(
+                "cannot create {stats_prfx}_tx_deferred_total counter"
+            )),
+            tx_late_counter: register_int_counter_vec!(
+                format!("{stats_prfx}_tx_late_total"),
+                "Late transmit collisions",
                 &["port"]
             )
-            .expect(&format!("cannot create {stats_prfx}_tx_late gauge")),
-            tx_multicast_packets_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_tx_multicast_packets"),
-                "tx multicast packets",
+            .expect(&format!("cannot create {stats_prfx}_tx_late_total counter")),
+            tx_multicast_packets_counter: register_int_counter_vec!(
+                format!("{stats_prfx}_tx_multicast_packets_total"),
+                "Transmitted multicast frames",
                 &["port"]
             )
             .expect(&format!(
-                "cannot create {stats_prfx}_tx_multicast_packets gauge"
+                "cannot create {stats_prfx}_tx_multicast_packets_total counter"
             )),
-            rx_fcs_packets_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_rx_fcs_packets"),
-                "rx fcs packets",
+            rx_fcs_packets_counter: register_int_counter_vec!(
+                format!("{stats_prfx}_rx_fcs_packets_total"),
+                "Received frames with a frame check sequence error",
                 &["port"]
             )
-            .expect(&format!("cannot create {stats_prfx}_rx_fcs_packets gauge")),
-            tx_broadcast_packets_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_tx_broadcast_packets"),
-                "tx broadcast packets",
+            .expect(&format!(
+                "cannot create {stats_prfx}_rx_fcs_packets_total counter"
+            )),
+            tx_broadcast_packets_counter: register_int_counter_vec!(
+                format!("{stats_prfx}_tx_broadcast_packets_total"),
+                "Transmitted broadcast frames",
                 &["port"]
             )
             .expect(&format!(
-                "cannot create {stats_prfx}_tx_broadcast_packets gauge"
+                "cannot create {stats_prfx}_tx_broadcast_packets_total counter"
             )),
-            rx_err_packets_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_rx_err_packets"),
-                "rx err packets",
+            rx_err_packets_counter: register_int_counter_vec!(
+                format!("{stats_prfx}_rx_err_packets_total"),
+                "Received frames with errors",
                 &["port"]
             )
-            .expect(&format!("cannot create {stats_prfx}_rx_err_packets gauge")),
-            rx_fragments_packets_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_rx_fragments_packets"),
-                "rx fragments packets",
+            .expect(&format!(
+                "cannot create {stats_prfx}_rx_err_packets_total counter"
+            )),
+            rx_fragments_packets_counter: register_int_counter_vec!(
+                format!("{stats_prfx}_rx_fragments_packets_total"),
+                "Received fragment frames",
                 &["port"]
             )
             .expect(&format!(
-                "cannot create {stats_prfx}_rx_fragments_packets gauge"
+                "cannot create {stats_prfx}_rx_fragments_packets_total counter"
             )),
-            rx_bad_bytes_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_rx_bad_bytes"),
-                "rx bad bytes",
+            rx_bad_bytes_counter: register_int_counter_vec!(
+                format!("{stats_prfx}_rx_bad_bytes_total"),
+                "Bytes received in bad frames",
                 &["port"]
             )
-            .expect(&format!("cannot create {stats_prfx}_rx_bad_bytes gauge")),
-            rx_undersize_packets_gauge: register_int_gauge_vec!(
-                format!("{stats_prfx}_rx_undersize_packets"),
-                "rx undersize packets",
+            .expect(&format!(
+                "cannot create {stats_prfx}_rx_bad_bytes_total counter"
+            )),
+            rx_undersize_packets_counter: register_int_counter_vec!(
+                format!("{stats_prfx}_rx_undersize_packets_total"),
+                "Received frames smaller than the minimum size",
                 &["port"]
             )
             .expect(&format!(
-                "cannot create {stats_prfx}_rx_undersize_packets gauge"
+                "cannot create {stats_prfx}_rx_undersize_packets_total counter"
             )),
             port_status_gauge: register_int_gauge_vec!(
                 format!("{prfx}_port_status"),
-                "port status, 1 for link up",
+                "Switch port link status, 1 when the link is up",
                 &["port"]
             )
             .expect(&format!("cannot create {prfx}_port_status gauge")),
             port_speed_gauge: register_int_gauge_vec!(
                 format!("{prfx}_port_speed"),
-                "port status speed",
+                "Switch port link speed in Mbit/s",
                 &["port"]
             )
             .expect(&format!("cannot create {prfx}_port_speed gauge")),
-            port_mac_list_gauge: register_int_gauge_vec!(
-                format!("{prfx}_port_mac_list"),
-                "port mac list, always 1",
-                &["port", "mac", "hostname"]
-            )
-            .expect(&format!("cannot create {prfx}_port_mac_list gauge")),
+            port_mac_list_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_port_mac_list"),
+                    "Host seen behind the switch port, always 1",
+                    &["port", "mac", "hostname"]
+                )
+                .expect(&format!("cannot create {prfx}_port_mac_list gauge")),
+                stale_series_grace,
+                &[0, 1],
+            ),
         }
     }
 
@@ -380,9 +428,8 @@ impl<'a> SwitchMetricMap<'a> {
 
     async fn get_ports_status(
         &mut self,
-        body: &str
-    ) -> Result<Vec<SwitchPortStatus>, Box<dyn std::error::Error + Send + Sync>> {      
-
+        body: &str,
+    ) -> Result<Vec<SwitchPortStatus>, Box<dyn std::error::Error + Send + Sync>> {
         let fixed_body = SwitchMetricMap::handle_malformed_mac_list(&body)?;
 
         let res = match serde_json::from_str::<FreeboxResponse<Vec<SwitchPortStatus>>>(&fixed_body)
@@ -442,10 +489,9 @@ impl<'a> SwitchMetricMap<'a> {
 
     async fn get_port_stats(
         &mut self,
-        body: &str, 
+        body: &str,
         port_id: &i16,
     ) -> Result<SwitchPortStats, Box<dyn std::error::Error + Send + Sync>> {
-
         let res = match serde_json::from_str::<FreeboxResponse<SwitchPortStats>>(body) {
             Err(e) => return Err(Box::new(e)),
             Ok(r) => r,
@@ -468,45 +514,7 @@ impl<'a> SwitchMetricMap<'a> {
         }
     }
 
-    fn reset_all(&self) {
-        self.rx_packets_rate_gauge.reset();
-        self.rx_good_bytes_gauge.reset();
-        self.rx_oversize_packets_gauge.reset();
-        self.rx_unicast_packets_gauge.reset();
-        self.tx_bytes_rate_gauge.reset();
-        self.tx_unicast_packets_gauge.reset();
-        self.rx_bytes_rate_gauge.reset();
-        self.tx_packets_gauge.reset();
-        self.tx_collisions_gauge.reset();
-        self.tx_packets_rate_gauge.reset();
-        self.tx_fcs_gauge.reset();
-        self.tx_bytes_gauge.reset();
-        self.rx_jabber_packets_gauge.reset();
-        self.tx_single_gauge.reset();
-        self.tx_excessive_gauge.reset();
-        self.rx_pause_gauge.reset();
-        self.rx_multicast_packets_gauge.reset();
-        self.tx_pause_gauge.reset();
-        self.rx_good_packets_gauge.reset();
-        self.rx_broadcast_packets_gauge.reset();
-        self.tx_multiple_gauge.reset();
-        self.tx_deferred_gauge.reset();
-        self.tx_late_gauge.reset();
-        self.tx_multicast_packets_gauge.reset();
-        self.rx_fcs_packets_gauge.reset();
-        self.tx_broadcast_packets_gauge.reset();
-        self.rx_err_packets_gauge.reset();
-        self.rx_fragments_packets_gauge.reset();
-        self.rx_bad_bytes_gauge.reset();
-        self.rx_undersize_packets_gauge.reset();
-        self.port_status_gauge.reset();
-        self.port_speed_gauge.reset();
-        self.port_mac_list_gauge.reset();
-    }
-
     async fn set_all(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        self.reset_all();
-
         let body_status = self.get_ports_status_json().await;
 
         if body_status.is_err() {
@@ -515,18 +523,20 @@ impl<'a> SwitchMetricMap<'a> {
             )));
         }
 
-        let body_status = body_status.unwrap(); 
+        let body_status = body_status.unwrap();
 
         let port_statuses = match self.get_ports_status(&body_status).await {
             Err(e) => return Err(e),
             Ok(r) => r,
         };
 
+        if let Some(sources) = self.device_sources.as_ref() {
+            sources.lock().unwrap().add_ports(&port_statuses);
+        }
+
         for port_status in port_statuses {
-            
-            let body_stats = self.get_port_stats_json(&port_status)
-                .await;
-            
+            let body_stats = self.get_port_stats_json(&port_status).await;
+
             if body_stats.is_err() {
                 return Err(Box::new(FreeboxResponseError::new(
                     "v4/switch/port/{}/stats failed".to_string(),
@@ -535,149 +545,190 @@ impl<'a> SwitchMetricMap<'a> {
 
             let body_stats = body_stats.unwrap();
 
-            let stats = match self.get_port_stats(&body_stats, port_status.id.as_ref().unwrap()).await {
+            let stats = match self
+                .get_port_stats(&body_stats, port_status.id.as_ref().unwrap())
+                .await
+            {
                 Err(e) => return Err(e),
                 Ok(r) => r,
             };
 
+            let port = port_status.id.unwrap_or_default().to_string();
+
             self.rx_packets_rate_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
+                .with_label_values(&[&port])
                 .set(stats.rx_packets_rate.unwrap_or_default());
 
-            self.rx_good_bytes_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
-                .set(stats.rx_good_bytes.unwrap_or_default());
+            set_counter(
+                &self.rx_good_bytes_counter.with_label_values(&[&port]),
+                stats.rx_good_bytes.unwrap_or_default(),
+            );
 
-            self.rx_oversize_packets_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
-                .set(stats.rx_oversize_packets.unwrap_or_default());
+            set_counter(
+                &self.rx_oversize_packets_counter.with_label_values(&[&port]),
+                stats.rx_oversize_packets.unwrap_or_default(),
+            );
 
-            self.rx_unicast_packets_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
-                .set(stats.rx_unicast_packets.unwrap_or_default());
+            set_counter(
+                &self.rx_unicast_packets_counter.with_label_values(&[&port]),
+                stats.rx_unicast_packets.unwrap_or_default(),
+            );
 
             self.tx_bytes_rate_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
+                .with_label_values(&[&port])
                 .set(stats.tx_bytes_rate.unwrap_or_default());
 
-            self.tx_unicast_packets_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
-                .set(stats.tx_unicast_packets.unwrap_or_default());
+            set_counter(
+                &self.tx_unicast_packets_counter.with_label_values(&[&port]),
+                stats.tx_unicast_packets.unwrap_or_default(),
+            );
 
             self.rx_bytes_rate_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
+                .with_label_values(&[&port])
                 .set(stats.rx_bytes_rate.unwrap_or_default());
 
-            self.tx_packets_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
-                .set(stats.tx_packets.unwrap_or_default());
+            set_counter(
+                &self.tx_packets_counter.with_label_values(&[&port]),
+                stats.tx_packets.unwrap_or_default(),
+            );
 
-            self.tx_collisions_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
-                .set(stats.tx_collisions.unwrap_or_default());
+            set_counter(
+                &self.tx_collisions_counter.with_label_values(&[&port]),
+                stats.tx_collisions.unwrap_or_default(),
+            );
 
             self.tx_packets_rate_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
+                .with_label_values(&[&port])
                 .set(stats.tx_packets_rate.unwrap_or_default());
 
-            self.tx_fcs_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
-                .set(stats.tx_fcs.unwrap_or_default());
-
-            self.tx_bytes_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
-                .set(stats.tx_bytes.unwrap_or_default());
-
-            self.rx_jabber_packets_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
-                .set(stats.rx_jabber_packets.unwrap_or_default());
-
-            self.tx_single_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
-                .set(stats.tx_single.unwrap_or_default());
-
-            self.tx_excessive_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
-                .set(stats.tx_excessive.unwrap_or_default());
-
-            self.rx_pause_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
-                .set(stats.rx_pause.unwrap_or_default());
-
-            self.rx_multicast_packets_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
-                .set(stats.rx_multicast_packets.unwrap_or_default());
-
-            self.tx_pause_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
-                .set(stats.tx_pause.unwrap_or_default());
-
-            self.rx_good_packets_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
-                .set(stats.rx_good_packets.unwrap_or_default());
-
-            self.rx_broadcast_packets_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
-                .set(stats.rx_broadcast_packets.unwrap_or_default());
-
-            self.tx_multiple_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
-                .set(stats.tx_multiple.unwrap_or_default());
-
-            self.tx_deferred_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
-                .set(stats.tx_deferred.unwrap_or_default());
-
-            self.tx_late_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
-                .set(stats.tx_late.unwrap_or_default());
-
-            self.tx_multicast_packets_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
-                .set(stats.tx_multicast_packets.unwrap_or_default());
-
-            self.rx_fcs_packets_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
-                .set(stats.rx_fcs_packets.unwrap_or_default());
-
-            self.tx_broadcast_packets_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
-                .set(stats.tx_broadcast_packets.unwrap_or_default());
-
-            self.rx_err_packets_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
-                .set(stats.rx_err_packets.unwrap_or_default());
-
-            self.rx_fragments_packets_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
-                .set(stats.rx_fragments_packets.unwrap_or_default());
-
-            self.rx_bad_bytes_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
-                .set(stats.rx_bad_bytes.unwrap_or_default());
-
-            self.rx_undersize_packets_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
-                .set(stats.rx_undersize_packets.unwrap_or_default());
+            set_counter(
+                &self.tx_fcs_counter.with_label_values(&[&port]),
+                stats.tx_fcs.unwrap_or_default(),
+            );
+
+            set_counter(
+                &self.tx_bytes_counter.with_label_values(&[&port]),
+                stats.tx_bytes.unwrap_or_default(),
+            );
+
+            set_counter(
+                &self.rx_jabber_packets_counter.with_label_values(&[&port]),
+                stats.rx_jabber_packets.unwrap_or_default(),
+            );
+
+            set_counter(
+                &self.tx_single_counter.with_label_values(&[&port]),
+                stats.tx_single.unwrap_or_default(),
+            );
+
+            set_counter(
+                &self.tx_excessive_counter.with_label_values(&[&port]),
+                stats.tx_excessive.unwrap_or_default(),
+            );
+
+            set_counter(
+                &self.rx_pause_counter.with_label_values(&[&port]),
+                stats.rx_pause.unwrap_or_default(),
+            );
+
+            set_counter(
+                &self
+                    .rx_multicast_packets_counter
+                    .with_label_values(&[&port]),
+                stats.rx_multicast_packets.unwrap_or_default(),
+            );
+
+            set_counter(
+                &self.tx_pause_counter.with_label_values(&[&port]),
+                stats.tx_pause.unwrap_or_default(),
+            );
+
+            set_counter(
+                &self.rx_good_packets_counter.with_label_values(&[&port]),
+                stats.rx_good_packets.unwrap_or_default(),
+            );
+
+            set_counter(
+                &self
+                    .rx_broadcast_packets_counter
+                    .with_label_values(&[&port]),
+                stats.rx_broadcast_packets.unwrap_or_default(),
+            );
+
+            set_counter(
+                &self.tx_multiple_counter.with_label_values(&[&port]),
+                stats.tx_multiple.unwrap_or_default(),
+            );
+
+            set_counter(
+                &self.tx_deferred_counter.with_label_values(&[&port]),
+                stats.tx_deferred.unwrap_or_default(),
+            );
+
+            set_counter(
+                &self.tx_late_counter.with_label_values(&[&port]),
+                stats.tx_late.unwrap_or_default(),
+            );
+
+            set_counter(
+                &self
+                    .tx_multicast_packets_counter
+                    .with_label_values(&[&port]),
+                stats.tx_multicast_packets.unwrap_or_default(),
+            );
+
+            set_counter(
+                &self.rx_fcs_packets_counter.with_label_values(&[&port]),
+                stats.rx_fcs_packets.unwrap_or_default(),
+            );
+
+            set_counter(
+                &self
+                    .tx_broadcast_packets_counter
+                    .with_label_values(&[&port]),
+                stats.tx_broadcast_packets.unwrap_or_default(),
+            );
+
+            set_counter(
+                &self.rx_err_packets_counter.with_label_values(&[&port]),
+                stats.rx_err_packets.unwrap_or_default(),
+            );
+
+            set_counter(
+                &self
+                    .rx_fragments_packets_counter
+                    .with_label_values(&[&port]),
+                stats.rx_fragments_packets.unwrap_or_default(),
+            );
+
+            set_counter(
+                &self.rx_bad_bytes_counter.with_label_values(&[&port]),
+                stats.rx_bad_bytes.unwrap_or_default(),
+            );
+
+            set_counter(
+                &self
+                    .rx_undersize_packets_counter
+                    .with_label_values(&[&port]),
+                stats.rx_undersize_packets.unwrap_or_default(),
+            );
 
             self.port_status_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
+                .with_label_values(&[&port])
                 .set((port_status.link.unwrap_or_default() == "up").into());
 
-            self.port_speed_gauge
-                .with_label_values(&[&port_status.id.unwrap_or_default().to_string()])
-                .set(
-                    port_status
-                        .speed
-                        .unwrap_or("0".to_string())
-                        .parse::<i64>()
-                        .unwrap_or(0),
-                );
+            self.port_speed_gauge.with_label_values(&[&port]).set(
+                port_status
+                    .speed
+                    .unwrap_or("0".to_string())
+                    .parse::<i64>()
+                    .unwrap_or(0),
+            );
 
             for host in port_status.mac_list.to_owned().unwrap_or_default() {
                 self.port_mac_list_gauge
                     .with_label_values(&[
-                        &port_status.id.unwrap_or_default().to_string(),
+                        &port,
                         &host.mac.unwrap_or_default(),
                         &host.hostname.unwrap_or_default(),
                     ])
@@ -695,7 +746,12 @@ impl<'a> MetricMap<'a> for SwitchMetricMap<'a> {
     }
 
     async fn set(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        if let Err(e) = self.set_all().await {
+        let res = self.set_all().await;
+
+        // ports are fixed, only the hosts seen behind them come and go
+        self.port_mac_list_gauge.sweep();
+
+        if let Err(e) = res {
             return Err(e);
         }
 
@@ -703,8 +759,6 @@ impl<'a> MetricMap<'a> for SwitchMetricMap<'a> {
     }
 }
 
-
-
 lazy_static! {
     // for performance reasons, we compile the regex only once
     static ref REG_MAC: Regex = Regex::new(r#""mac_list"[^\[]+\{\s{0,}}"#).unwrap();
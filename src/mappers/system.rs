@@ -7,7 +7,7 @@ use reqwest::Client;
 use serde::Deserialize;
 
 
-use super::MetricMap;
+use super::{info::InfoMetric, MetricMap};
 use crate::core::common::{
     http_client_factory::{AuthenticatedHttpClientFactory, ManagedHttpClient},
     transport::{FreeboxResponse, FreeboxResponseError},
@@ -43,155 +43,286 @@ pub struct SystemConfig {
     pub firmware_version: Option<String>,
 }
 
-pub struct SystemMetricMap<'a> {
-    factory: &'a AuthenticatedHttpClientFactory<'a>,
-    managed_client: Option<ManagedHttpClient>,
+/// Fetches the system configuration outside of the system metric map,
+/// e.g. to describe the box in exported resources
+pub async fn get_system_config(
+    factory: &AuthenticatedHttpClientFactory<'_>,
+) -> Result<SystemConfig, Box<dyn std::error::Error + Send + Sync>> {
+    debug!("fetching system config");
+
+    let client = factory.create_managed_client().await?;
+    let res = client
+        .get()?
+        .get(format!("{}v4/system", factory.api_url))
+        .send()
+        .await?
+        .json::<FreeboxResponse<SystemConfig>>()
+        .await?;
+
+    if !res.success.unwrap_or(false) {
+        return Err(Box::new(FreeboxResponseError::new(
+            res.msg.unwrap_or_default(),
+        )));
+    }
+
+    res.result.ok_or_else(|| {
+        Box::new(FreeboxResponseError::new(
+            "v4/system response was empty".to_string(),
+        )) as Box<dyn std::error::Error + Send + Sync>
+    })
+}
+
+/// Value-in-label gauges superseded by the system info metric,
+/// registered only when legacy metrics are enabled
+struct SystemLegacyMetrics {
     mac_metric: IntGaugeVec,
     box_flavor_metric: IntGaugeVec,
     box_model_name_metric: IntGaugeVec,
     device_name_metric: IntGaugeVec,
     api_version_metric: IntGaugeVec,
-    temp_hdd_metric: IntGauge,
-    temp_t1_metric: IntGauge,
-    temp_t2_metric: IntGauge,
-    temp_cpu_metric: IntGaugeVec, // New: CPU temperature with core label
-    // Legacy metrics for backward compatibility
-    temp_cpub_metric: IntGauge,
-    temp_cpum_metric: IntGauge,
-    disk_status_metric: IntGaugeVec,
-    box_authenticated_metric: IntGauge,
     board_name_metric: IntGaugeVec,
-    fan_rpm_metric: IntGauge,
-    temp_sw_metric: IntGauge,
-    uptime_val_metric: IntGauge,
-    user_main_storage_metric: IntGaugeVec,
     serial_metric: IntGaugeVec,
     firmware_version_metric: IntGaugeVec,
 }
 
-impl<'a> SystemMetricMap<'a> {
-    pub fn new(factory: &'a AuthenticatedHttpClientFactory<'a>, prefix: String) -> Self {
+impl SystemLegacyMetrics {
+    fn new(prefix: &str) -> Self {
         Self {
-            factory,
-            managed_client: None,
             mac_metric: register_int_gauge_vec!(
                 format!("{prefix}_system_mac"),
-                format!("{prefix}_system_mac"),
+                "Box MAC address, always 1",
                 &["mac"]
             )
             .expect(&format!("cannot create {prefix}_system_mac gauge")),
             box_flavor_metric: register_int_gauge_vec!(
                 format!("{prefix}_system_box_flavor"),
-                format!("{prefix}_system_box_flavor"),
+                "Box flavor (full, light), always 1",
                 &["box_flavor"]
             )
             .expect(&format!("cannot create {prefix}_system_box_flavor gauge")),
             box_model_name_metric: register_int_gauge_vec!(
                 format!("{prefix}_system_box_model_name"),
-                format!("{prefix}_system_box_model_name"),
+                "Box model name, always 1",
                 &["model_name"]
             )
-            .expect(&format!("cannot create {prefix}_system_box_model_name gauge")),
+            .expect(&format!(
+                "cannot create {prefix}_system_box_model_name gauge"
+            )),
             device_name_metric: register_int_gauge_vec!(
                 format!("{prefix}_system_device_name"),
-                format!("{prefix}_system_device_name"),
+                "Box device name, always 1",
                 &["device_name"]
             )
             .expect(&format!("cannot create {prefix}_system_device_name gauge")),
             api_version_metric: register_int_gauge_vec!(
                 format!("{prefix}_system_api_version"),
-                format!("{prefix}_system_api_version"),
+                "Box API version, always 1",
                 &["api_version"]
             )
             .expect(&format!("cannot create {prefix}_system_api_version gauge")),
+            board_name_metric: register_int_gauge_vec!(
+                format!("{prefix}_system_board_name"),
+                "Box board name, always 1",
+                &["board_name"]
+            )
+            .expect(&format!("cannot create {prefix}_system_board_name gauge")),
+            serial_metric: register_int_gauge_vec!(
+                format!("{prefix}_system_serial"),
+                "Box serial number, always 1",
+                &["serial"]
+            )
+            .expect(&format!("cannot create {prefix}_system_serial gauge")),
+            firmware_version_metric: register_int_gauge_vec!(
+                format!("{prefix}_system_firmware_version"),
+                "Box firmware version, always 1",
+                &["firmware_version"]
+            )
+            .expect(&format!(
+                "cannot create {prefix}_system_firmware_version gauge"
+            )),
+        }
+    }
+
+    fn reset(&self) {
+        self.mac_metric.reset();
+        self.box_flavor_metric.reset();
+        self.box_model_name_metric.reset();
+        self.device_name_metric.reset();
+        self.api_version_metric.reset();
+        self.board_name_metric.reset();
+        self.serial_metric.reset();
+        self.firmware_version_metric.reset();
+    }
+
+    fn set(&self, sys_cnf: &SystemConfig) {
+        self.mac_metric
+            .with_label_values(&[&sys_cnf.mac.clone().unwrap_or_default()])
+            .set(1);
+        self.box_flavor_metric
+            .with_label_values(&[&sys_cnf.box_flavor.clone().unwrap_or_default()])
+            .set(1);
+
+        if let Some(model_name) = &sys_cnf.box_model_name {
+            self.box_model_name_metric
+                .with_label_values(&[model_name])
+                .set(1);
+        }
+
+        if let Some(device_name) = &sys_cnf.device_name {
+            self.device_name_metric
+                .with_label_values(&[device_name])
+                .set(1);
+        }
+
+        if let Some(api_version) = &sys_cnf.api_version {
+            self.api_version_metric
+                .with_label_values(&[api_version])
+                .set(1);
+        }
+
+        self.board_name_metric
+            .with_label_values(&[&sys_cnf.board_name.clone().unwrap_or_default()])
+            .set(1);
+        self.serial_metric
+            .with_label_values(&[&sys_cnf.serial.clone().unwrap_or_default()])
+            .set(1);
+        self.firmware_version_metric
+            .with_label_values(&[&sys_cnf.firmware_version.clone().unwrap_or_default()])
+            .set(1);
+    }
+}
+
+pub struct SystemMetricMap<'a> {
+    factory: &'a AuthenticatedHttpClientFactory<'a>,
+    managed_client: Option<ManagedHttpClient>,
+    info_metric: InfoMetric,
+    legacy_metrics: Option<SystemLegacyMetrics>,
+    temp_hdd_metric: IntGauge,
+    temp_t1_metric: IntGauge,
+    temp_t2_metric: IntGauge,
+    temp_cpu_metric: IntGaugeVec, // New: CPU temperature with core label
+    // Legacy metrics for backward compatibility
+    temp_cpub_metric: IntGauge,
+    temp_cpum_metric: IntGauge,
+    disk_status_metric: IntGaugeVec,
+    box_authenticated_metric: IntGauge,
+    fan_rpm_metric: IntGauge,
+    temp_sw_metric: IntGauge,
+    uptime_val_metric: IntGauge,
+    user_main_storage_metric: IntGaugeVec,
+}
+
+impl<'a> SystemMetricMap<'a> {
+    pub fn new(
+        factory: &'a AuthenticatedHttpClientFactory<'a>,
+        prefix: String,
+        legacy_metrics: bool,
+    ) -> Self {
+        Self {
+            factory,
+            managed_client: None,
+            info_metric: InfoMetric::new(
+                register_int_gauge_vec!(
+                    format!("{prefix}_system_info"),
+                    "Box identity and firmware, always 1",
+                    &[
+                        "mac",
+                        "serial",
+                        "firmware_version",
+                        "board_name",
+                        "box_flavor",
+                        "model_name",
+                        "device_name",
+                        "api_version"
+                    ]
+                )
+                .expect(&format!("cannot create {prefix}_system_info gauge")),
+            ),
+            legacy_metrics: legacy_metrics.then(|| SystemLegacyMetrics::new(&prefix)),
             temp_hdd_metric: register_int_gauge!(
-                format!("{prefix}_system_temp_hdd"),
-                format!("{prefix}_system_temp_hdd")
+                format!("{prefix}_system_temp_hdd_celsius"),
+                "Internal hard drive temperature in degrees celsius"
             )
-            .expect(&format!("cannot create {prefix}_system_temp_hdd gauge")),
+            .expect(&format!(
+                "cannot create {prefix}_system_temp_hdd_celsius gauge"
+            )),
             temp_t1_metric: register_int_gauge!(
-                format!("{prefix}_system_temp_t1"),
-                format!("{prefix}_system_temp_t1")
+                format!("{prefix}_system_temp_t1_celsius"),
+                "Temperature sensor T1 in degrees celsius"
             )
-            .expect(&format!("cannot create {prefix}_system_temp_t1 gauge")),
+            .expect(&format!(
+                "cannot create {prefix}_system_temp_t1_celsius gauge"
+            )),
             temp_t2_metric: register_int_gauge!(
-                format!("{prefix}_system_temp_t2"),
-                format!("{prefix}_system_temp_t2")
+                format!("{prefix}_system_temp_t2_celsius"),
+                "Temperature sensor T2 in degrees celsius"
             )
-            .expect(&format!("cannot create {prefix}_system_temp_t2 gauge")),
+            .expect(&format!(
+                "cannot create {prefix}_system_temp_t2_celsius gauge"
+            )),
             temp_cpu_metric: register_int_gauge_vec!(
-                format!("{prefix}_system_temp_cpu"),
-                format!("{prefix}_system_temp_cpu CPU core temperature"),
+                format!("{prefix}_system_temp_cpu_celsius"),
+                "CPU core temperature in degrees celsius",
                 &["core"]
             )
-            .expect(&format!("cannot create {prefix}_system_temp_cpu gauge")),
+            .expect(&format!(
+                "cannot create {prefix}_system_temp_cpu_celsius gauge"
+            )),
             // Legacy metrics for backward compatibility
             temp_cpub_metric: register_int_gauge!(
-                format!("{prefix}_system_temp_cpub"),
-                format!("{prefix}_system_temp_cpub")
+                format!("{prefix}_system_temp_cpub_celsius"),
+                "CPU B temperature in degrees celsius"
             )
-            .expect(&format!("cannot create {prefix}_system_temp_cpub gauge")),
+            .expect(&format!(
+                "cannot create {prefix}_system_temp_cpub_celsius gauge"
+            )),
             temp_cpum_metric: register_int_gauge!(
-                format!("{prefix}_system_temp_cpum"),
-                format!("{prefix}_system_temp_cpum")
+                format!("{prefix}_system_temp_cpum_celsius"),
+                "CPU M temperature in degrees celsius"
             )
-            .expect(&format!("cannot create {prefix}_system_temp_cpum gauge")),
+            .expect(&format!(
+                "cannot create {prefix}_system_temp_cpum_celsius gauge"
+            )),
             disk_status_metric: register_int_gauge_vec!(
                 format!("{prefix}_system_disk_status"),
-                format!("{prefix}_system_disk_status"),
+                "Internal disk status, 1 when reported",
                 &["disk_status"]
             )
             .expect(&format!("cannot create {prefix}_system_disk_status gauge")),
             box_authenticated_metric: register_int_gauge!(
                 format!("{prefix}_system_box_authenticated"),
-                format!("{prefix}_system_box_authenticated")
+                "1 when the box is authenticated by the operator"
             )
             .expect(&format!(
                 "cannot create {prefix}_system_box_authenticated gauge"
             )),
-            board_name_metric: register_int_gauge_vec!(
-                format!("{prefix}_system_board_name"),
-                format!("{prefix}_system_board_name"),
-                &["board_name"]
-            )
-            .expect(&format!("cannot create {prefix}_system_board_name gauge")),
             fan_rpm_metric: register_int_gauge!(
                 format!("{prefix}_system_fan_rpm"),
-                format!("{prefix}_system_fan_rpm")
+                "Fan speed in revolutions per minute"
             )
             .expect(&format!("cannot create {prefix}_system_fan_rpm gauge")),
             temp_sw_metric: register_int_gauge!(
-                format!("{prefix}_system_temp_sw"),
-                format!("{prefix}_system_temp_sw")
+                format!("{prefix}_system_temp_sw_celsius"),
+                "Switch temperature in degrees celsius"
             )
-            .expect(&format!("cannot create {prefix}_system_temp_sw gauge")),
+            .expect(&format!(
+                "cannot create {prefix}_system_temp_sw_celsius gauge"
+            )),
             uptime_val_metric: register_int_gauge!(
-                format!("{prefix}_system_uptime_val"),
-                format!("{prefix}_system_uptime_val")
+                format!("{prefix}_system_uptime_seconds"),
+                "Box uptime in seconds"
             )
-            .expect(&format!("cannot create {prefix}_system_uptime_val gauge")),
+            .expect(&format!(
+                "cannot create {prefix}_system_uptime_seconds gauge"
+            )),
             user_main_storage_metric: register_int_gauge_vec!(
                 format!("{prefix}_system_user_main_storage"),
-                format!("{prefix}_system_user_main_storage"),
+                "Main storage used for user files, 1 when reported",
                 &["user_main_storage"]
             )
             .expect(&format!(
                 "cannot create {prefix}_system_user_main_storage gauge"
             )),
-            serial_metric: register_int_gauge_vec!(
-                format!("{prefix}_system_serial"),
-                format!("{prefix}_system_serial"),
-                &["serial"]
-            )
-            .expect(&format!("cannot create {prefix}_system_serial gauge")),
-            firmware_version_metric: register_int_gauge_vec!(
-                format!("{prefix}_system_firmware_version"),
-                format!("{prefix}_system_firmware_version"),
-                &["firmware_version"]
-            )
-            .expect(&format!(
-                "cannot create {prefix}_system_firmware_version gauge"
-            )),
         }
     }
 
@@ -228,11 +359,9 @@ impl<'a> SystemMetricMap<'a> {
     }
 
     fn reset_all(&mut self) {
-        self.mac_metric.reset();
-        self.box_flavor_metric.reset();
-        self.box_model_name_metric.reset();
-        self.device_name_metric.reset();
-        self.api_version_metric.reset();
+        if let Some(legacy_metrics) = &self.legacy_metrics {
+            legacy_metrics.reset();
+        }
         self.temp_hdd_metric.set(0);
         self.temp_t1_metric.set(0);
         self.temp_t2_metric.set(0);
@@ -242,13 +371,10 @@ impl<'a> SystemMetricMap<'a> {
         self.temp_cpum_metric.set(0);
         self.disk_status_metric.reset();
         self.box_authenticated_metric.set(0);
-        self.board_name_metric.reset();
         self.fan_rpm_metric.set(0);
         self.temp_sw_metric.set(0);
         self.uptime_val_metric.set(0);
         self.user_main_storage_metric.reset();
-        self.serial_metric.reset();
-        self.firmware_version_metric.reset();
     }
 
     async fn set_system_config(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
@@ -284,32 +410,21 @@ impl<'a> SystemMetricMap<'a> {
             Some(r) => r,
         };
 
-        self.mac_metric
-            .with_label_values(&[&sys_cnf.mac.clone().unwrap_or_default()])
-            .set(1);
-        self.box_flavor_metric
-            .with_label_values(&[&sys_cnf.box_flavor.clone().unwrap_or_default()])
-            .set(1);
-        
-        // Set new metrics if available
-        if let Some(model_name) = &sys_cnf.box_model_name {
-            self.box_model_name_metric
-                .with_label_values(&[model_name])
-                .set(1);
+        self.info_metric.set(&[
+            &sys_cnf.mac.clone().unwrap_or_default(),
+            &sys_cnf.serial.clone().unwrap_or_default(),
+            &sys_cnf.firmware_version.clone().unwrap_or_default(),
+            &sys_cnf.board_name.clone().unwrap_or_default(),
+            &sys_cnf.box_flavor.clone().unwrap_or_default(),
+            &sys_cnf.box_model_name.clone().unwrap_or_default(),
+            &sys_cnf.device_name.clone().unwrap_or_default(),
+            &sys_cnf.api_version.clone().unwrap_or_default(),
+        ]);
+
+        if let Some(legacy_metrics) = &self.legacy_metrics {
+            legacy_metrics.set(&sys_cnf);
         }
-        
-        if let Some(device_name) = &sys_cnf.device_name {
-            self.device_name_metric
-                .with_label_values(&[device_name])
-                .set(1);
-        }
-        
-        if let Some(api_version) = &sys_cnf.api_version {
-            self.api_version_metric
-                .with_label_values(&[api_version])
-                .set(1);
-        }
-        
+
         // Set HDD temperature if available
         self.temp_hdd_metric.set(sys_cnf.temp_hdd.unwrap_or_default());
         
@@ -325,9 +440,6 @@ impl<'a> SystemMetricMap<'a> {
             .set(sys_cnf.disk_status.is_some().into());
         self.box_authenticated_metric
             .set(sys_cnf.box_authenticated.unwrap_or_default().into());
-        self.board_name_metric
-            .with_label_values(&[&sys_cnf.board_name.clone().unwrap_or_default()])
-            .set(1);
         self.fan_rpm_metric.set(sys_cnf.fan_rpm.unwrap_or_default());
         self.temp_sw_metric.set(sys_cnf.temp_sw.unwrap_or_default());
         self.uptime_val_metric
@@ -335,12 +447,6 @@ impl<'a> SystemMetricMap<'a> {
         self.user_main_storage_metric
             .with_label_values(&[&sys_cnf.user_main_storage.clone().unwrap_or_default()])
             .set(sys_cnf.user_main_storage.is_some().into());
-        self.serial_metric
-            .with_label_values(&[&sys_cnf.serial.clone().unwrap_or_default()])
-            .set(1);
-        self.firmware_version_metric
-            .with_label_values(&[&sys_cnf.firmware_version.clone().unwrap_or_default()])
-            .set(1);
         Ok(())
     }
 
@@ -0,0 +1,361 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use prometheus_exporter::prometheus::{
+    register_int_counter, register_int_counter_vec, register_int_gauge, register_int_gauge_vec,
+    IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
+};
+use serde::Deserialize;
+
+use crate::core::common::http_client_factory::AuthenticatedHttpClientFactory;
+
+use super::{
+    client::MapClient, counters::set_counter, merge_results, series::TrackedVec, MetricMap,
+};
+
+const SERVER_STARTED_STATE: &str = "started";
+const CLIENT_UP_STATE: &str = "up";
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct VpnServer {
+    name: Option<String>,
+    #[serde(rename = "type")]
+    _type: Option<String>,
+    state: Option<String>,
+    connection_count: Option<i64>,
+    auth_connection_count: Option<i64>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct VpnConnection {
+    id: Option<String>,
+    vpn: Option<String>,
+    user: Option<String>,
+    authenticated: Option<bool>,
+    auth_time: Option<i64>,
+    src_ip: Option<String>,
+    local_ip: Option<String>,
+    rx_bytes: Option<i64>,
+    tx_bytes: Option<i64>,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct VpnClientStatus {
+    enabled: Option<bool>,
+    active_vpn_description: Option<String>,
+    #[serde(rename = "type")]
+    _type: Option<String>,
+    state: Option<String>,
+    last_up: Option<i64>,
+    stats: Option<VpnClientStats>,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct VpnClientStats {
+    rate_up: Option<i64>,
+    rate_down: Option<i64>,
+    bytes_up: Option<i64>,
+    bytes_down: Option<i64>,
+}
+
+pub struct VpnMetricMap<'a> {
+    client: MapClient<'a>,
+    server_state_gauge: TrackedVec<IntGaugeVec>,
+    server_connections_gauge: TrackedVec<IntGaugeVec>,
+    server_authenticated_connections_gauge: TrackedVec<IntGaugeVec>,
+    connection_authenticated_gauge: TrackedVec<IntGaugeVec>,
+    connection_auth_time_gauge: TrackedVec<IntGaugeVec>,
+    connection_rx_bytes_counter: TrackedVec<IntCounterVec>,
+    connection_tx_bytes_counter: TrackedVec<IntCounterVec>,
+    client_state_gauge: IntGaugeVec,
+    client_last_up_gauge: IntGauge,
+    client_rx_rate_gauge: IntGauge,
+    client_tx_rate_gauge: IntGauge,
+    client_rx_bytes_counter: IntCounter,
+    client_tx_bytes_counter: IntCounter,
+}
+
+impl<'a> VpnMetricMap<'a> {
+    pub fn new(
+        factory: &'a AuthenticatedHttpClientFactory<'a>,
+        prefix: String,
+        stale_series_grace: Duration,
+    ) -> Self {
+        let prfx = format!("{prefix}_vpn");
+
+        let server_gauge = |name: &str, help: &str| {
+            TrackedVec::new(
+                register_int_gauge_vec!(format!("{prfx}_server_{name}"), help, &["server"])
+                    .expect(&format!("cannot create {prfx}_server_{name} gauge")),
+                stale_series_grace,
+            )
+        };
+
+        let connection_counter = |name: &str, help: &str| {
+            TrackedVec::keyed(
+                register_int_counter_vec!(
+                    format!("{prfx}_connection_{name}_total"),
+                    help,
+                    &["id", "server", "user"]
+                )
+                .expect(&format!(
+                    "cannot create {prfx}_connection_{name}_total counter"
+                )),
+                stale_series_grace,
+                &[0],
+            )
+        };
+
+        Self {
+            client: MapClient::new(factory),
+            server_state_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_server_state"),
+                    "VPN server state, 1 when the server is started",
+                    &["server", "type", "state"]
+                )
+                .expect(&format!("cannot create {prfx}_server_state gauge")),
+                stale_series_grace,
+                &[0],
+            ),
+            server_connections_gauge: server_gauge(
+                "connections",
+                "Number of connections to the VPN server",
+            ),
+            server_authenticated_connections_gauge: server_gauge(
+                "authenticated_connections",
+                "Number of authenticated connections to the VPN server",
+            ),
+            connection_authenticated_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_connection_authenticated"),
+                    "VPN server connection, 1 when the user is authenticated",
+                    &["id", "server", "user", "src_ip", "local_ip"]
+                )
+                .expect(&format!(
+                    "cannot create {prfx}_connection_authenticated gauge"
+                )),
+                stale_series_grace,
+                &[0],
+            ),
+            connection_auth_time_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_connection_auth_timestamp_seconds"),
+                    "VPN server connection authentication as a unix timestamp",
+                    &["id", "server", "user"]
+                )
+                .expect(&format!(
+                    "cannot create {prfx}_connection_auth_timestamp_seconds gauge"
+                )),
+                stale_series_grace,
+                &[0],
+            ),
+            connection_rx_bytes_counter: connection_counter(
+                "rx_bytes",
+                "Bytes received from the VPN server connection",
+            ),
+            connection_tx_bytes_counter: connection_counter(
+                "tx_bytes",
+                "Bytes transmitted to the VPN server connection",
+            ),
+            client_state_gauge: register_int_gauge_vec!(
+                format!("{prfx}_client_state"),
+                "VPN client state, 1 when the tunnel is up",
+                &["vpn", "type", "state"]
+            )
+            .expect(&format!("cannot create {prfx}_client_state gauge")),
+            client_last_up_gauge: register_int_gauge!(
+                format!("{prfx}_client_last_up_timestamp_seconds"),
+                "VPN client tunnel last up as a unix timestamp"
+            )
+            .expect(&format!(
+                "cannot create {prfx}_client_last_up_timestamp_seconds gauge"
+            )),
+            client_rx_rate_gauge: register_int_gauge!(
+                format!("{prfx}_client_rx_rate_bytes_per_second"),
+                "VPN client receive rate in bytes per second"
+            )
+            .expect(&format!(
+                "cannot create {prfx}_client_rx_rate_bytes_per_second gauge"
+            )),
+            client_tx_rate_gauge: register_int_gauge!(
+                format!("{prfx}_client_tx_rate_bytes_per_second"),
+                "VPN client transmit rate in bytes per second"
+            )
+            .expect(&format!(
+                "cannot create {prfx}_client_tx_rate_bytes_per_second gauge"
+            )),
+            client_rx_bytes_counter: register_int_counter!(
+                format!("{prfx}_client_rx_bytes_total"),
+                "Bytes received through the VPN client tunnel"
+            )
+            .expect(&format!(
+                "cannot create {prfx}_client_rx_bytes_total counter"
+            )),
+            client_tx_bytes_counter: register_int_counter!(
+                format!("{prfx}_client_tx_bytes_total"),
+                "Bytes transmitted through the VPN client tunnel"
+            )
+            .expect(&format!(
+                "cannot create {prfx}_client_tx_bytes_total counter"
+            )),
+        }
+    }
+
+    async fn set_servers(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let servers: Vec<VpnServer> = self.client.get("v4/vpn/").await?;
+
+        for server in servers.iter() {
+            let name = server.name.to_owned().unwrap_or_default();
+            let state = server.state.to_owned().unwrap_or_default();
+
+            self.server_state_gauge
+                .with_label_values(&[&name, &server._type.to_owned().unwrap_or_default(), &state])
+                .set((state == SERVER_STARTED_STATE).into());
+            self.server_connections_gauge
+                .with_label_values(&[&name])
+                .set(server.connection_count.unwrap_or_default());
+            self.server_authenticated_connections_gauge
+                .with_label_values(&[&name])
+                .set(server.auth_connection_count.unwrap_or_default());
+        }
+
+        Ok(())
+    }
+
+    async fn set_connections(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let connections: Vec<VpnConnection> = self.client.get("v4/vpn/connection/").await?;
+
+        for connection in connections.iter() {
+            let id = connection.id.to_owned().unwrap_or_default();
+            let server = connection.vpn.to_owned().unwrap_or_default();
+            let user = connection.user.to_owned().unwrap_or_default();
+            let labels = [id.as_str(), server.as_str(), user.as_str()];
+
+            self.connection_authenticated_gauge
+                .with_label_values(&[
+                    &id,
+                    &server,
+                    &user,
+                    &connection.src_ip.to_owned().unwrap_or_default(),
+                    &connection.local_ip.to_owned().unwrap_or_default(),
+                ])
+                .set(connection.authenticated.unwrap_or_default().into());
+            self.connection_auth_time_gauge
+                .with_label_values(&labels)
+                .set(connection.auth_time.unwrap_or_default());
+
+            set_counter(
+                &self.connection_rx_bytes_counter.with_label_values(&labels),
+                connection.rx_bytes.unwrap_or_default(),
+            );
+            set_counter(
+                &self.connection_tx_bytes_counter.with_label_values(&labels),
+                connection.tx_bytes.unwrap_or_default(),
+            );
+        }
+
+        Ok(())
+    }
+
+    async fn set_client(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let status: VpnClientStatus = self.client.get("v4/vpn_client/status").await?;
+
+        self.client_state_gauge.reset();
+
+        if !status.enabled.unwrap_or_default() {
+            return Ok(());
+        }
+
+        let state = status.state.to_owned().unwrap_or_default();
+        self.client_state_gauge
+            .with_label_values(&[
+                &status.active_vpn_description.unwrap_or_default(),
+                &status._type.unwrap_or_default(),
+                &state,
+            ])
+            .set((state == CLIENT_UP_STATE).into());
+
+        self.client_last_up_gauge
+            .set(status.last_up.unwrap_or_default());
+
+        let stats = status.stats.unwrap_or_default();
+        self.client_rx_rate_gauge
+            .set(stats.rate_down.unwrap_or_default());
+        self.client_tx_rate_gauge
+            .set(stats.rate_up.unwrap_or_default());
+
+        set_counter(
+            &self.client_rx_bytes_counter,
+            stats.bytes_down.unwrap_or_default(),
+        );
+        set_counter(
+            &self.client_tx_bytes_counter,
+            stats.bytes_up.unwrap_or_default(),
+        );
+
+        Ok(())
+    }
+
+    /// Servers, connections and the client come from distinct APIs, a failing
+    /// one does not hide the others
+    async fn set_all(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let servers_res = self.set_servers().await;
+        let connections_res = self.set_connections().await;
+        let client_res = self.set_client().await;
+
+        merge_results(vec![servers_res, connections_res, client_res])
+    }
+
+    fn sweep_all(&self) {
+        self.server_state_gauge.sweep();
+        self.server_connections_gauge.sweep();
+        self.server_authenticated_connections_gauge.sweep();
+        self.connection_authenticated_gauge.sweep();
+        self.connection_auth_time_gauge.sweep();
+        self.connection_rx_bytes_counter.sweep();
+        self.connection_tx_bytes_counter.sweep();
+    }
+}
+
+#[async_trait]
+impl<'a> MetricMap<'a> for VpnMetricMap<'a> {
+    async fn init(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(())
+    }
+
+    async fn set(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let res = self.set_all().await;
+
+        // closed sessions are removed once their grace expires
+        self.sweep_all();
+
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mappers::api_specs_provider::get_result;
+
+    #[tokio::test]
+    async fn should_deserialize_vpn_sessions() {
+        let servers: Vec<VpnServer> = get_result("vpn", "api_v4_vpn-get").await;
+        assert_eq!(
+            2,
+            servers
+                .iter()
+                .filter(|s| s.state.as_deref() == Some(SERVER_STARTED_STATE))
+                .count()
+        );
+
+        let connections: Vec<VpnConnection> = get_result("vpn", "api_v4_vpn_connection-get").await;
+        assert_eq!(Some("wireguard"), connections[1].vpn.as_deref());
+        assert_eq!(Some(1760785412), connections[0].auth_time);
+
+        let status: VpnClientStatus = get_result("vpn", "api_v4_vpn_client_status-get").await;
+        assert_eq!(Some(CLIENT_UP_STATE), status.state.as_deref());
+        assert_eq!(Some(314572800), status.stats.unwrap().bytes_down);
+    }
+}
@@ -1,13 +1,20 @@
 
-use std::usize;
+use std::{collections::HashSet, usize};
 
 use async_trait::async_trait;
 use chrono::Duration;
 use log::debug;
-use models::{AccessPoint, ChannelSurveyHistory, ChannelUsage, NeighborsAccessPoint, Station};
-use prometheus_exporter::prometheus::{register_int_gauge_vec, IntGaugeVec};
+use models::{
+    AccessPoint, Bss, ChannelSurveyHistory, ChannelUsage, CustomKey, NeighborsAccessPoint, Station,
+};
+use prometheus_exporter::prometheus::{
+    register_int_counter_vec, register_int_gauge_vec, IntCounterVec, IntGaugeVec,
+};
 use reqwest::Client;
-use utils::{calculate_avg_channel_survey_history, get_recent_channel_entries};
+use utils::{
+    calculate_avg_channel_survey_history, get_channel_width_mhz, get_guest_bssids, get_guest_macs,
+    get_recent_channel_entries,
+};
 
 use crate::{
     core::{
@@ -20,45 +27,76 @@ use crate::{
     mappers::wifi::models::WifiConfig,
 };
 
-use super::MetricMap;
-
+use super::{counters::set_counter, devices::SharedDeviceSources, series::TrackedVec, MetricMap};
 
 pub mod models;
 pub mod unittests;
 pub mod utils;
 
+/// Labels shared by the per station metrics
+const STATION_LABELS: [&str; 6] = ["primary_name", "ipv4", "ap_name", "band", "ap_id", "mac"];
+/// Indexes of `ap_id` and `mac` in the per station labels, identifying a station
+const STATION_KEY: [usize; 2] = [4, 5];
+/// Indexes of `ap_id` and `mac` in the station active labels
+const STATION_ACTIVE_KEY: [usize; 2] = [3, 4];
+/// Labels of the station guest flag, joined to the station metrics by `ap_id` and `mac`
+const STATION_GUEST_LABELS: [&str; 2] = ["ap_id", "mac"];
+/// Labels shared by the per access point configuration metrics
+const AP_LABELS: [&str; 3] = ["ap_id", "ap_name", "band"];
+/// Labels shared by the per BSS metrics
+const BSS_LABELS: [&str; 3] = ["bssid", "ap_id", "ssid"];
+/// Labels shared by the per custom key metrics
+const CUSTOM_KEY_LABELS: [&str; 3] = ["id", "description", "access_type"];
+const AP_ACTIVE_STATE: &str = "active";
+
 pub struct WifiMetricMap<'a> {
     factory: &'a AuthenticatedHttpClientFactory<'a>,
     managed_client: Option<ManagedHttpClient>,
+    device_sources: Option<SharedDeviceSources>,
     history_ttl: Duration,
     unresolved_hostname_policy: String,
+    guest_bssids: HashSet<String>,
+    guest_macs: HashSet<String>,
     busy_percent_gauge: IntGaugeVec,
     tx_percent_gauge: IntGaugeVec,
     rx_percent_gauge: IntGaugeVec,
     rx_bss_percent: IntGaugeVec,
-    station_active_gauge: IntGaugeVec,
-    station_rx_bitrate_gauge: IntGaugeVec,
-    station_rx_mcs_gauge: IntGaugeVec,
-    station_rx_shortgi_gauge: IntGaugeVec,
-    station_rx_vht_mcs_gauge: IntGaugeVec,
-    station_rx_width_gauge: IntGaugeVec,
-    station_rx_bytes_gauge: IntGaugeVec,
-    station_rx_rate_gauge: IntGaugeVec,
-    station_tx_bitrate_gauge: IntGaugeVec,
-    station_tx_mcs_gauge: IntGaugeVec,
-    station_tx_shortgi_gauge: IntGaugeVec,
-    station_tx_vht_mcs_gauge: IntGaugeVec,
-    station_tx_width_gauge: IntGaugeVec,
-    station_tx_bytes_gauge: IntGaugeVec,
-    station_tx_rate_gauge: IntGaugeVec,
-    station_signal_gauge: IntGaugeVec,
-    station_inactive_gauge: IntGaugeVec,
-    station_state_gauge: IntGaugeVec,
-    station_flags_gauge: IntGaugeVec,
-    station_last_activity_gauge: IntGaugeVec,
-    station_last_time_reachable_gauge: IntGaugeVec,
-    neighbors_access_point_gauge: IntGaugeVec,
-    channel_usage_gauge: IntGaugeVec,
+    station_active_gauge: TrackedVec<IntGaugeVec>,
+    station_rx_bitrate_gauge: TrackedVec<IntGaugeVec>,
+    station_rx_mcs_gauge: TrackedVec<IntGaugeVec>,
+    station_rx_shortgi_gauge: TrackedVec<IntGaugeVec>,
+    station_rx_vht_mcs_gauge: TrackedVec<IntGaugeVec>,
+    station_rx_width_gauge: TrackedVec<IntGaugeVec>,
+    station_rx_bytes_counter: TrackedVec<IntCounterVec>,
+    station_rx_rate_gauge: TrackedVec<IntGaugeVec>,
+    station_tx_bitrate_gauge: TrackedVec<IntGaugeVec>,
+    station_tx_mcs_gauge: TrackedVec<IntGaugeVec>,
+    station_tx_shortgi_gauge: TrackedVec<IntGaugeVec>,
+    station_tx_vht_mcs_gauge: TrackedVec<IntGaugeVec>,
+    station_tx_width_gauge: TrackedVec<IntGaugeVec>,
+    station_tx_bytes_counter: TrackedVec<IntCounterVec>,
+    station_tx_rate_gauge: TrackedVec<IntGaugeVec>,
+    station_signal_gauge: TrackedVec<IntGaugeVec>,
+    station_inactive_gauge: TrackedVec<IntGaugeVec>,
+    station_state_gauge: TrackedVec<IntGaugeVec>,
+    station_flags_gauge: TrackedVec<IntGaugeVec>,
+    station_last_activity_gauge: TrackedVec<IntGaugeVec>,
+    station_last_time_reachable_gauge: TrackedVec<IntGaugeVec>,
+    station_guest_gauge: TrackedVec<IntGaugeVec>,
+    neighbors_access_point_gauge: TrackedVec<IntGaugeVec>,
+    channel_usage_gauge: TrackedVec<IntGaugeVec>,
+    ap_up_gauge: TrackedVec<IntGaugeVec>,
+    ap_primary_channel_gauge: TrackedVec<IntGaugeVec>,
+    ap_secondary_channel_gauge: TrackedVec<IntGaugeVec>,
+    ap_channel_width_gauge: TrackedVec<IntGaugeVec>,
+    ap_dfs_enabled_gauge: TrackedVec<IntGaugeVec>,
+    ap_dfs_cac_remaining_gauge: TrackedVec<IntGaugeVec>,
+    bss_up_gauge: TrackedVec<IntGaugeVec>,
+    bss_stations_gauge: TrackedVec<IntGaugeVec>,
+    bss_authorized_stations_gauge: TrackedVec<IntGaugeVec>,
+    custom_key_remaining_gauge: TrackedVec<IntGaugeVec>,
+    custom_key_max_use_gauge: TrackedVec<IntGaugeVec>,
+    custom_key_users_gauge: TrackedVec<IntGaugeVec>,
 }
 
 impl<'a> WifiMetricMap<'a> {
@@ -66,7 +104,9 @@ impl<'a> WifiMetricMap<'a> {
         factory: &'a AuthenticatedHttpClientFactory<'a>,
         prefix: String,
         history_ttl: Duration,
+        stale_series_grace: std::time::Duration,
         policies: &PoliciesConfiguration,
+        device_sources: Option<SharedDeviceSources>,
     ) -> Self {
         let prfx: String = format!("{prefix}_wifi");
         let unresolved_hostname_policy = policies
@@ -77,211 +117,455 @@ impl<'a> WifiMetricMap<'a> {
         Self {
             factory,
             managed_client: None,
+            device_sources,
             history_ttl,
             unresolved_hostname_policy,
+            guest_bssids: HashSet::new(),
+            guest_macs: HashSet::new(),
             busy_percent_gauge: register_int_gauge_vec!(
                 format!("{prfx}_busy_percent"),
-                format!("{prfx}_busy_percent"),
+                "Percentage of time the channel was busy, averaged over the history ttl",
                 &["ap", "name", "band"]
             )
             .expect(&format!("cannot create {prfx}_busy_percent gauge")),
             rx_bss_percent: register_int_gauge_vec!(
                 format!("{prfx}_rx_bss_percent"),
-                format!("{prfx}_rx_bss_percent"),
+                "Percentage of time receiving frames from the access point BSS, averaged over the history ttl",
                 &["ap", "name", "band"]
             )
             .expect(&format!("cannot create {prfx}_rx_bss_percent gauge")),
             rx_percent_gauge: register_int_gauge_vec!(
                 format!("{prfx}_rx_percent"),
-                format!("{prfx}_rx_percent"),
+                "Percentage of time receiving, averaged over the history ttl",
                 &["ap", "name", "band"]
             )
             .expect(&format!("cannot create {prfx}_rx_percent gauge")),
             tx_percent_gauge: register_int_gauge_vec!(
                 format!("{prfx}_tx_percent"),
-                format!("{prfx}_tx_percent"),
+                "Percentage of time transmitting, averaged over the history ttl",
                 &["ap", "name", "band"]
             )
             .expect(&format!("cannot create {prfx}_tx_percent gauge")),
-            station_active_gauge: register_int_gauge_vec!(
-                format!("{prfx}_station_active"),
-                format!("{prfx}_station_active 1 for active"),
-                &[
-                    "primary_name",
-                    "ap_name",
-                    "band",
-                    "ap_id",
-                    "mac",
-                    "vendor_name"
-                ]
-            )
-            .expect(&format!("cannot create {prfx}_station_mac gauge")),
-            station_rx_bitrate_gauge: register_int_gauge_vec!(
-                format!("{prfx}_station_rx_bitrate"),
-                format!("{prfx}_station_rx_bitrate"),
-                &["primary_name", "ipv4", "ap_name", "band", "ap_id", "mac"]
-            )
-            .expect(&format!("cannot create {prfx}_station_rx_bitrate gauge")),
-            station_rx_mcs_gauge: register_int_gauge_vec!(
-                format!("{prfx}_station_rx_mcs"),
-                format!("{prfx}_station_rx_mcs"),
-                &["primary_name", "ipv4", "ap_name", "band", "ap_id", "mac"]
-            )
-            .expect(&format!("cannot create {prfx}_station_rx_mcs gauge")),
-            station_rx_shortgi_gauge: register_int_gauge_vec!(
-                format!("{prfx}_station_rx_shortgi"),
-                format!("{prfx}_station_rx_shortgi 1 for shortgi enabled"),
-                &["primary_name", "ipv4", "ap_name", "band", "ap_id", "mac"]
-            )
-            .expect(&format!("cannot create {prfx}_station_rx_shortgi gauge")),
-            station_rx_vht_mcs_gauge: register_int_gauge_vec!(
-                format!("{prfx}_station_rx_vht_mcs"),
-                format!("{prfx}_station_rx_vht_mcs"),
-                &["primary_name", "ipv4", "ap_name", "band", "ap_id", "mac"]
-            )
-            .expect(&format!("cannot create {prfx}_station_rx_vht_mcs gauge")),
-            station_rx_width_gauge: register_int_gauge_vec!(
-                format!("{prfx}_station_rx_width"),
-                format!("{prfx}_station_rx_width"),
-                &["primary_name", "ipv4", "ap_name", "band", "ap_id", "mac"]
-            )
-            .expect(&format!("cannot create {prfx}_station_rx_width gauge")),
-            station_rx_bytes_gauge: register_int_gauge_vec!(
-                format!("{prfx}_station_rx_bytes"),
-                format!("{prfx}_station_rx_bytes"),
-                &["primary_name", "ipv4", "ap_name", "band", "ap_id", "mac"]
-            )
-            .expect(&format!("cannot create {prfx}_station_rx_bytes gauge")),
-            station_rx_rate_gauge: register_int_gauge_vec!(
-                format!("{prfx}_station_rx_rate"),
-                format!("{prfx}_station_rx_rate"),
-                &["primary_name", "ipv4", "ap_name", "band", "ap_id", "mac"]
-            )
-            .expect(&format!("cannot create {prfx}_station_rx_rate gauge")),
-            station_tx_bitrate_gauge: register_int_gauge_vec!(
-                format!("{prfx}_station_tx_bitrate"),
-                format!("{prfx}_station_tx_bitrate"),
-                &["primary_name", "ipv4", "ap_name", "band", "ap_id", "mac"]
-            )
-            .expect(&format!("cannot create {prfx}_station_tx_bitrate gauge")),
-            station_tx_mcs_gauge: register_int_gauge_vec!(
-                format!("{prfx}_station_tx_mcs"),
-                format!("{prfx}_station_tx_mcs"),
-                &["primary_name", "ipv4", "ap_name", "band", "ap_id", "mac"]
-            )
-            .expect(&format!("cannot create {prfx}_station_tx_mcs gauge")),
-            station_tx_shortgi_gauge: register_int_gauge_vec!(
-                format!("{prfx}_station_tx_shortgi"),
-                format!("{prfx}_station_tx_shortgi 1 for shortgi enabled"),
-                &["primary_name", "ipv4", "ap_name", "band", "ap_id", "mac"]
-            )
-            .expect(&format!("cannot create {prfx}_station_tx_shortgi gauge")),
-            station_tx_vht_mcs_gauge: register_int_gauge_vec!(
-                format!("{prfx}_station_tx_vht_mcs"),
-                format!("{prfx}_station_tx_vht_mcs"),
-                &["primary_name", "ipv4", "ap_name", "band", "ap_id", "mac"]
-            )
-            .expect(&format!("cannot create {prfx}_station_tx_vht_mcs gauge")),
-            station_tx_width_gauge: register_int_gauge_vec!(
-                format!("{prfx}_station_tx_width"),
-                format!("{prfx}_station_tx_width"),
-                &["primary_name", "ipv4", "ap_name", "band", "ap_id", "mac"]
-            )
-            .expect(&format!("cannot create {prfx}_station_tx_width gauge")),
-            station_tx_bytes_gauge: register_int_gauge_vec!(
-                format!("{prfx}_station_tx_bytes"),
-                format!("{prfx}_station_tx_bytes"),
-                &["primary_name", "ipv4", "ap_name", "band", "ap_id", "mac"]
-            )
-            .expect(&format!("cannot create {prfx}_station_tx_bytes gauge")),
-            station_tx_rate_gauge: register_int_gauge_vec!(
-                format!("{prfx}_station_tx_rate"),
-                format!("{prfx}_station_tx_rate"),
-                &["primary_name", "ipv4", "ap_name", "band", "ap_id", "mac"]
-            )
-            .expect(&format!("cannot create {prfx}_station_tx_rate gauge")),
-            station_signal_gauge: register_int_gauge_vec!(
-                format!("{prfx}_station_signal"),
-                format!("{prfx}_station_signal"),
-                &["primary_name", "ipv4", "ap_name", "band", "ap_id", "mac"]
-            )
-            .expect(&format!("cannot create {prfx}_station_signal gauge")),
-            station_inactive_gauge: register_int_gauge_vec!(
-                format!("{prfx}_station_inactive"),
-                format!("{prfx}_station_inactive"),
-                &["primary_name", "ipv4", "ap_name", "band", "ap_id", "mac"]
-            )
-            .expect(&format!("cannot create {prfx}_station_inactive gauge")),
-            station_state_gauge: register_int_gauge_vec!(
-                format!("{prfx}_station_state"),
-                format!("{prfx}_station_state"),
-                &[
-                    "primary_name",
-                    "ipv4",
-                    "ap_name",
-                    "band",
-                    "ap_id",
-                    "mac",
-                    "state"
-                ]
-            )
-            .expect(&format!("cannot create {prfx}_station_state gauge")),
-            station_flags_gauge: register_int_gauge_vec!(
-                format!("{prfx}_station_flags"),
-                format!("{prfx}_station_flags"),
-                &[
-                    "primary_name",
-                    "ipv4",
-                    "ap_name",
-                    "band",
-                    "ap_id",
-                    "mac",
-                    "vht",
-                    "legacy",
-                    "authorized",
-                    "ht"
-                ]
-            )
-            .expect(&format!("cannot create {prfx}_station_vht gauge")),
-            station_last_activity_gauge: register_int_gauge_vec!(
-                format!("{prfx}_station_last_activity"),
-                format!("{prfx}_station_last_activity"),
-                &["primary_name", "ipv4", "ap_name", "band", "ap_id", "mac"]
-            )
-            .expect(&format!("cannot create {prfx}_station_last_activity gauge")),
-            station_last_time_reachable_gauge: register_int_gauge_vec!(
-                format!("{prfx}_station_last_time_reachable"),
-                format!("{prfx}_station_last_time_reachable"),
-                &["primary_name", "ipv4", "ap_name", "band", "ap_id", "mac"]
-            )
-            .expect(&format!(
-                "cannot create {prfx}_station_last_time_reachable gauge"
-            )),
-            neighbors_access_point_gauge: register_int_gauge_vec!(
-                format!("{prfx}_neighbors_access_point"),
-                format!("{prfx}_neighbors_access_point signal strength"),
-                &[
-                    "channel",
-                    "ssid",
-                    "bssid",
-                    "band",
-                    "vht",
-                    "legacy",
-                    "he",
-                    "ht",
-                    "eht",
-                    "secondary_channel"
-                ]
-            )
-            .expect(&format!(
-                "cannot create {prfx}_neighbors_access_point gauge"
-            )),
-            channel_usage_gauge: register_int_gauge_vec!(
-                format!("{prfx}_channel_usage"),
-                format!("{prfx}_channel_usage noise level"),
-                &["band", "channel", "rx_busy_percent"]
-            )
-            .expect(&format!("cannot create {prfx}_channel_usage gauge")),
+            station_active_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_station_active"),
+                    "Wifi station, 1 when the host is active",
+                    &[
+                        "primary_name",
+                        "ap_name",
+                        "band",
+                        "ap_id",
+                        "mac",
+                        "vendor_name"
+                    ]
+                )
+                .expect(&format!("cannot create {prfx}_station_mac gauge")),
+                stale_series_grace,
+                &STATION_ACTIVE_KEY,
+            ),
+            station_rx_bitrate_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_station_rx_bitrate"),
+                    "Bitrate of the last frame received from the station, in 100 kbit/s",
+                    &STATION_LABELS
+                )
+                .expect(&format!("cannot create {prfx}_station_rx_bitrate gauge")),
+                stale_series_grace,
+                &STATION_KEY,
+            ),
+            station_rx_mcs_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_station_rx_mcs"),
+                    "MCS index of the last frame received from the station",
+                    &STATION_LABELS
+                )
+                .expect(&format!("cannot create {prfx}_station_rx_mcs gauge")),
+                stale_series_grace,
+                &STATION_KEY,
+            ),
+            station_rx_shortgi_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_station_rx_shortgi"),
+                    "1 when the last frame received from the station used short guard interval",
+                    &STATION_LABELS
+                )
+                .expect(&format!("cannot create {prfx}_station_rx_shortgi gauge")),
+                stale_series_grace,
+                &STATION_KEY,
+            ),
+            station_rx_vht_mcs_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_station_rx_vht_mcs"),
+                    "VHT MCS index of the last frame received from the station",
+                    &STATION_LABELS
+                )
+                .expect(&format!("cannot create {prfx}_station_rx_vht_mcs gauge")),
+                stale_series_grace,
+                &STATION_KEY,
+            ),
+            station_rx_width_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_station_rx_width"),
+                    "Channel width of the last frame received from the station, in MHz",
+                    &STATION_LABELS
+                )
+                .expect(&format!("cannot create {prfx}_station_rx_width gauge")),
+                stale_series_grace,
+                &STATION_KEY,
+            ),
+            station_rx_bytes_counter: TrackedVec::keyed(
+                register_int_counter_vec!(
+                    format!("{prfx}_station_rx_bytes_total"),
+                    "Bytes received from the station",
+                    &STATION_LABELS
+                )
+                .expect(&format!(
+                    "cannot create {prfx}_station_rx_bytes_total counter"
+                )),
+                stale_series_grace,
+                &STATION_KEY,
+            ),
+            station_rx_rate_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_station_rx_bytes_per_second"),
+                    "Bytes received from the station per second",
+                    &STATION_LABELS
+                )
+                .expect(&format!(
+                    "cannot create {prfx}_station_rx_bytes_per_second gauge"
+                )),
+                stale_series_grace,
+                &STATION_KEY,
+            ),
+            station_tx_bitrate_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_station_tx_bitrate"),
+                    "Bitrate of the last frame sent to the station, in 100 kbit/s",
+                    &STATION_LABELS
+                )
+                .expect(&format!("cannot create {prfx}_station_tx_bitrate gauge")),
+                stale_series_grace,
+                &STATION_KEY,
+            ),
+            station_tx_mcs_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_station_tx_mcs"),
+                    "MCS index of the last frame sent to the station",
+                    &STATION_LABELS
+                )
+                .expect(&format!("cannot create {prfx}_station_tx_mcs gauge")),
+                stale_series_grace,
+                &STATION_KEY,
+            ),
+            station_tx_shortgi_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_station_tx_shortgi"),
+                    "1 when the last frame sent to the station used short guard interval",
+                    &STATION_LABELS
+                )
+                .expect(&format!("cannot create {prfx}_station_tx_shortgi gauge")),
+                stale_series_grace,
+                &STATION_KEY,
+            ),
+            station_tx_vht_mcs_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_station_tx_vht_mcs"),
+                    "VHT MCS index of the last frame sent to the station",
+                    &STATION_LABELS
+                )
+                .expect(&format!("cannot create {prfx}_station_tx_vht_mcs gauge")),
+                stale_series_grace,
+                &STATION_KEY,
+            ),
+            station_tx_width_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_station_tx_width"),
+                    "Channel width of the last frame sent to the station, in MHz",
+                    &STATION_LABELS
+                )
+                .expect(&format!("cannot create {prfx}_station_tx_width gauge")),
+                stale_series_grace,
+                &STATION_KEY,
+            ),
+            station_tx_bytes_counter: TrackedVec::keyed(
+                register_int_counter_vec!(
+                    format!("{prfx}_station_tx_bytes_total"),
+                    "Bytes sent to the station",
+                    &STATION_LABELS
+                )
+                .expect(&format!(
+                    "cannot create {prfx}_station_tx_bytes_total counter"
+                )),
+                stale_series_grace,
+                &STATION_KEY,
+            ),
+            station_tx_rate_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_station_tx_bytes_per_second"),
+                    "Bytes sent to the station per second",
+                    &STATION_LABELS
+                )
+                .expect(&format!(
+                    "cannot create {prfx}_station_tx_bytes_per_second gauge"
+                )),
+                stale_series_grace,
+                &STATION_KEY,
+            ),
+            station_signal_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_station_signal_dbm"),
+                    "Station signal strength in dBm",
+                    &STATION_LABELS
+                )
+                .expect(&format!("cannot create {prfx}_station_signal_dbm gauge")),
+                stale_series_grace,
+                &STATION_KEY,
+            ),
+            station_inactive_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_station_inactive_seconds"),
+                    "Time since the station was last active, in seconds",
+                    &STATION_LABELS
+                )
+                .expect(&format!(
+                    "cannot create {prfx}_station_inactive_seconds gauge"
+                )),
+                stale_series_grace,
+                &STATION_KEY,
+            ),
+            station_state_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_station_state"),
+                    "Station association state, always 1",
+                    &[
+                        "primary_name",
+                        "ipv4",
+                        "ap_name",
+                        "band",
+                        "ap_id",
+                        "mac",
+                        "state"
+                    ]
+                )
+                .expect(&format!("cannot create {prfx}_station_state gauge")),
+                stale_series_grace,
+                &STATION_KEY,
+            ),
+            station_flags_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_station_flags"),
+                    "Station capability flags, always 1",
+                    &[
+                        "primary_name",
+                        "ipv4",
+                        "ap_name",
+                        "band",
+                        "ap_id",
+                        "mac",
+                        "vht",
+                        "legacy",
+                        "authorized",
+                        "ht"
+                    ]
+                )
+                .expect(&format!("cannot create {prfx}_station_vht gauge")),
+                stale_series_grace,
+                &STATION_KEY,
+            ),
+            station_last_activity_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_station_last_activity_timestamp_seconds"),
+                    "Station host last activity as a unix timestamp",
+                    &STATION_LABELS
+                )
+                .expect(&format!(
+                    "cannot create {prfx}_station_last_activity_timestamp_seconds gauge"
+                )),
+                stale_series_grace,
+                &STATION_KEY,
+            ),
+            station_last_time_reachable_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_station_last_time_reachable_timestamp_seconds"),
+                    "Station host last time reachable as a unix timestamp",
+                    &STATION_LABELS
+                )
+                .expect(&format!(
+                    "cannot create {prfx}_station_last_time_reachable_timestamp_seconds gauge"
+                )),
+                stale_series_grace,
+                &STATION_KEY,
+            ),
+            station_guest_gauge: TrackedVec::new(
+                register_int_gauge_vec!(
+                    format!("{prfx}_station_guest"),
+                    "Station guest flag, 1 when the station is on a guest BSS or connected with a guest access key",
+                    &STATION_GUEST_LABELS
+                )
+                .expect(&format!("cannot create {prfx}_station_guest gauge")),
+                stale_series_grace,
+            ),
+            neighbors_access_point_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_neighbors_access_point_signal_dbm"),
+                    "Neighbor access point signal strength in dBm",
+                    &[
+                        "channel",
+                        "ssid",
+                        "bssid",
+                        "band",
+                        "vht",
+                        "legacy",
+                        "he",
+                        "ht",
+                        "eht",
+                        "secondary_channel"
+                    ]
+                )
+                .expect(&format!(
+                    "cannot create {prfx}_neighbors_access_point gauge"
+                )),
+                stale_series_grace,
+                &[2],
+            ),
+            channel_usage_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_channel_usage_noise_dbm"),
+                    "Channel noise level in dBm",
+                    &["band", "channel", "rx_busy_percent"]
+                )
+                .expect(&format!(
+                    "cannot create {prfx}_channel_usage_noise_dbm gauge"
+                )),
+                stale_series_grace,
+                &[0, 1],
+            ),
+            ap_up_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_ap_up"),
+                    "Access point state, 1 when the access point is active",
+                    &["ap_id", "ap_name", "band", "state"]
+                )
+                .expect(&format!("cannot create {prfx}_ap_up gauge")),
+                stale_series_grace,
+                &[0],
+            ),
+            ap_primary_channel_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_ap_primary_channel"),
+                    "Primary channel in use by the access point",
+                    &AP_LABELS
+                )
+                .expect(&format!("cannot create {prfx}_ap_primary_channel gauge")),
+                stale_series_grace,
+                &[0],
+            ),
+            ap_secondary_channel_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_ap_secondary_channel"),
+                    "Secondary channel in use by the access point, 0 when none",
+                    &AP_LABELS
+                )
+                .expect(&format!("cannot create {prfx}_ap_secondary_channel gauge")),
+                stale_series_grace,
+                &[0],
+            ),
+            ap_channel_width_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_ap_channel_width_mhz"),
+                    "Channel width in use by the access point in MHz",
+                    &AP_LABELS
+                )
+                .expect(&format!("cannot create {prfx}_ap_channel_width_mhz gauge")),
+                stale_series_grace,
+                &[0],
+            ),
+            ap_dfs_enabled_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_ap_dfs_enabled"),
+                    "Access point DFS channels, 1 when enabled in the configuration and not disabled by the box",
+                    &AP_LABELS
+                )
+                .expect(&format!("cannot create {prfx}_ap_dfs_enabled gauge")),
+                stale_series_grace,
+                &[0],
+            ),
+            ap_dfs_cac_remaining_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_ap_dfs_cac_remaining_seconds"),
+                    "Remaining DFS channel availability check time before the access point can emit",
+                    &AP_LABELS
+                )
+                .expect(&format!(
+                    "cannot create {prfx}_ap_dfs_cac_remaining_seconds gauge"
+                )),
+                stale_series_grace,
+                &[0],
+            ),
+            bss_up_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_bss_up"),
+                    "BSS state, 1 when the BSS is enabled and active",
+                    &["bssid", "ap_id", "ssid", "encryption", "guest", "state"]
+                )
+                .expect(&format!("cannot create {prfx}_bss_up gauge")),
+                stale_series_grace,
+                &[0],
+            ),
+            bss_stations_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_bss_stations"),
+                    "Number of stations associated to the BSS",
+                    &BSS_LABELS
+                )
+                .expect(&format!("cannot create {prfx}_bss_stations gauge")),
+                stale_series_grace,
+                &[0],
+            ),
+            bss_authorized_stations_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_bss_authorized_stations"),
+                    "Number of stations associated and authenticated to the BSS",
+                    &BSS_LABELS
+                )
+                .expect(&format!(
+                    "cannot create {prfx}_bss_authorized_stations gauge"
+                )),
+                stale_series_grace,
+                &[0],
+            ),
+            custom_key_remaining_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_custom_key_remaining_seconds"),
+                    "Remaining validity of the guest access key, 0 once expired",
+                    &CUSTOM_KEY_LABELS
+                )
+                .expect(&format!(
+                    "cannot create {prfx}_custom_key_remaining_seconds gauge"
+                )),
+                stale_series_grace,
+                &[0],
+            ),
+            custom_key_max_use_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_custom_key_max_use_count"),
+                    "Maximum number of devices allowed to use the guest access key, 0 when unlimited",
+                    &CUSTOM_KEY_LABELS
+                )
+                .expect(&format!("cannot create {prfx}_custom_key_max_use_count gauge")),
+                stale_series_grace,
+                &[0],
+            ),
+            custom_key_users_gauge: TrackedVec::keyed(
+                register_int_gauge_vec!(
+                    format!("{prfx}_custom_key_users"),
+                    "Number of devices registered with the guest access key, connected or not",
+                    &CUSTOM_KEY_LABELS
+                )
+                .expect(&format!("cannot create {prfx}_custom_key_users gauge")),
+                stale_series_grace,
+                &[0],
+            ),
         }
     }
 
@@ -448,6 +732,49 @@ impl<'a> WifiMetricMap<'a> {
         }
     }
 
+    /// BSS are listed once for every access point
+    async fn get_bss(&mut self) -> Result<Vec<Bss>, Box<dyn std::error::Error + Send + Sync>> {
+        debug!("fetching bss");
+        let client = self.get_managed_client().await?;
+
+        let res = client
+            .get(format!("{}v4/wifi/bss/", self.factory.api_url))
+            .send()
+            .await?
+            .json::<FreeboxResponse<Vec<Bss>>>()
+            .await?;
+
+        if res.success.unwrap_or(false) {
+            Ok(res.result.unwrap_or_default())
+        } else {
+            Err(Box::new(FreeboxResponseError::new(
+                res.msg.unwrap_or_default(),
+            )))
+        }
+    }
+
+    async fn get_custom_keys(
+        &mut self,
+    ) -> Result<Vec<CustomKey>, Box<dyn std::error::Error + Send + Sync>> {
+        debug!("fetching custom keys");
+        let client = self.get_managed_client().await?;
+
+        let res = client
+            .get(format!("{}v4/wifi/custom_key/", self.factory.api_url))
+            .send()
+            .await?
+            .json::<FreeboxResponse<Vec<CustomKey>>>()
+            .await?;
+
+        if res.success.unwrap_or(false) {
+            Ok(res.result.unwrap_or_default())
+        } else {
+            Err(Box::new(FreeboxResponseError::new(
+                res.msg.unwrap_or_default(),
+            )))
+        }
+    }
+
     async fn get_access_points(
         &mut self,
     ) -> Result<Vec<AccessPoint>, Box<dyn std::error::Error + Send + Sync>> {
@@ -629,9 +956,17 @@ impl<'a> WifiMetricMap<'a> {
             .with_label_values(&[&primary_name, &addr, &ap_name, &band, &ap_id, &mac])
             .set(rx_width.parse::<i64>().unwrap_or(0));
 
-        self.station_rx_bytes_gauge
-            .with_label_values(&[&primary_name, &addr, &ap_name, &band, &ap_id, &mac])
-            .set(rx_bytes as i64);
+        set_counter(
+            &self.station_rx_bytes_counter.with_label_values(&[
+                &primary_name,
+                &addr,
+                &ap_name,
+                &band,
+                &ap_id,
+                &mac,
+            ]),
+            rx_bytes as i64,
+        );
 
         self.station_rx_rate_gauge
             .with_label_values(&[&primary_name, &addr, &ap_name, &band, &ap_id, &mac])
@@ -657,9 +992,17 @@ impl<'a> WifiMetricMap<'a> {
             .with_label_values(&[&primary_name, &addr, &ap_name, &band, &ap_id, &mac])
             .set(tx_width.parse::<i64>().unwrap_or(0));
 
-        self.station_tx_bytes_gauge
-            .with_label_values(&[&primary_name, &addr, &ap_name, &band, &ap_id, &mac])
-            .set(tx_bytes as i64);
+        set_counter(
+            &self.station_tx_bytes_counter.with_label_values(&[
+                &primary_name,
+                &addr,
+                &ap_name,
+                &band,
+                &ap_id,
+                &mac,
+            ]),
+            tx_bytes as i64,
+        );
 
         self.station_tx_rate_gauge
             .with_label_values(&[&primary_name, &addr, &ap_name, &band, &ap_id, &mac])
@@ -697,6 +1040,10 @@ impl<'a> WifiMetricMap<'a> {
         self.station_last_time_reachable_gauge
             .with_label_values(&[&primary_name, &addr, &ap_name, &band, &ap_id, &mac])
             .set(last_time_reachable);
+
+        self.station_guest_gauge
+            .with_label_values(&[&ap_id, &mac])
+            .set(self.is_guest(station).into());
     }
 
     async fn set_station_metrics_with_defaults(
@@ -745,9 +1092,17 @@ impl<'a> WifiMetricMap<'a> {
             .with_label_values(&[&primary_name, &addr, &ap_name, &band, &ap_id, &mac])
             .set(0);
 
-        self.station_rx_bytes_gauge
-            .with_label_values(&[&primary_name, &addr, &ap_name, &band, &ap_id, &mac])
-            .set(station.rx_bytes.unwrap_or(0) as i64);
+        set_counter(
+            &self.station_rx_bytes_counter.with_label_values(&[
+                &primary_name,
+                &addr,
+                &ap_name,
+                &band,
+                &ap_id,
+                &mac,
+            ]),
+            station.rx_bytes.unwrap_or(0) as i64,
+        );
 
         self.station_rx_rate_gauge
             .with_label_values(&[&primary_name, &addr, &ap_name, &band, &ap_id, &mac])
@@ -773,9 +1128,17 @@ impl<'a> WifiMetricMap<'a> {
             .with_label_values(&[&primary_name, &addr, &ap_name, &band, &ap_id, &mac])
             .set(0);
 
-        self.station_tx_bytes_gauge
-            .with_label_values(&[&primary_name, &addr, &ap_name, &band, &ap_id, &mac])
-            .set(station.tx_bytes.unwrap_or(0) as i64);
+        set_counter(
+            &self.station_tx_bytes_counter.with_label_values(&[
+                &primary_name,
+                &addr,
+                &ap_name,
+                &band,
+                &ap_id,
+                &mac,
+            ]),
+            station.tx_bytes.unwrap_or(0) as i64,
+        );
 
         self.station_tx_rate_gauge
             .with_label_values(&[&primary_name, &addr, &ap_name, &band, &ap_id, &mac])
@@ -814,36 +1177,183 @@ impl<'a> WifiMetricMap<'a> {
         self.station_last_time_reachable_gauge
             .with_label_values(&[&primary_name, &addr, &ap_name, &band, &ap_id, &mac])
             .set(host.to_owned().last_time_reachable.unwrap_or(i64::MIN));
+
+        self.station_guest_gauge
+            .with_label_values(&[&ap_id, &mac])
+            .set(self.is_guest(station).into());
+    }
+
+    pub fn sweep_all(&self) {
+        self.station_active_gauge.sweep();
+        self.station_rx_bitrate_gauge.sweep();
+        self.station_rx_mcs_gauge.sweep();
+        self.station_rx_shortgi_gauge.sweep();
+        self.station_rx_vht_mcs_gauge.sweep();
+        self.station_rx_width_gauge.sweep();
+        self.station_rx_bytes_counter.sweep();
+        self.station_rx_rate_gauge.sweep();
+        self.station_tx_bitrate_gauge.sweep();
+        self.station_tx_mcs_gauge.sweep();
+        self.station_tx_shortgi_gauge.sweep();
+        self.station_tx_vht_mcs_gauge.sweep();
+        self.station_tx_width_gauge.sweep();
+        self.station_tx_bytes_counter.sweep();
+        self.station_tx_rate_gauge.sweep();
+        self.station_signal_gauge.sweep();
+        self.station_inactive_gauge.sweep();
+        self.station_state_gauge.sweep();
+        self.station_flags_gauge.sweep();
+        self.station_last_activity_gauge.sweep();
+        self.station_last_time_reachable_gauge.sweep();
+        self.station_guest_gauge.sweep();
+        self.neighbors_access_point_gauge.sweep();
+        self.channel_usage_gauge.sweep();
+        self.ap_up_gauge.sweep();
+        self.ap_primary_channel_gauge.sweep();
+        self.ap_secondary_channel_gauge.sweep();
+        self.ap_channel_width_gauge.sweep();
+        self.ap_dfs_enabled_gauge.sweep();
+        self.ap_dfs_cac_remaining_gauge.sweep();
+        self.bss_up_gauge.sweep();
+        self.bss_stations_gauge.sweep();
+        self.bss_authorized_stations_gauge.sweep();
+        self.custom_key_remaining_gauge.sweep();
+        self.custom_key_max_use_gauge.sweep();
+        self.custom_key_users_gauge.sweep();
+    }
+
+    fn is_guest(&self, station: &Station) -> bool {
+        let on_guest_bss = station
+            .bssid
+            .as_ref()
+            .is_some_and(|b| self.guest_bssids.contains(&b.to_uppercase()));
+        let with_custom_key = station
+            .mac
+            .as_ref()
+            .is_some_and(|m| self.guest_macs.contains(&m.to_uppercase()));
+
+        on_guest_bss || with_custom_key
     }
 
-    pub fn reset_all(&self) {
-        self.busy_percent_gauge.reset();
-        self.tx_percent_gauge.reset();
-        self.rx_percent_gauge.reset();
-        self.rx_bss_percent.reset();
-        self.station_active_gauge.reset();
-        self.station_rx_bitrate_gauge.reset();
-        self.station_rx_mcs_gauge.reset();
-        self.station_rx_shortgi_gauge.reset();
-        self.station_rx_vht_mcs_gauge.reset();
-        self.station_rx_width_gauge.reset();
-        self.station_rx_bytes_gauge.reset();
-        self.station_rx_rate_gauge.reset();
-        self.station_tx_bitrate_gauge.reset();
-        self.station_tx_mcs_gauge.reset();
-        self.station_tx_shortgi_gauge.reset();
-        self.station_tx_vht_mcs_gauge.reset();
-        self.station_tx_width_gauge.reset();
-        self.station_tx_bytes_gauge.reset();
-        self.station_tx_rate_gauge.reset();
-        self.station_signal_gauge.reset();
-        self.station_inactive_gauge.reset();
-        self.station_state_gauge.reset();
-        self.station_flags_gauge.reset();
-        self.station_last_activity_gauge.reset();
-        self.station_last_time_reachable_gauge.reset();
-        self.neighbors_access_point_gauge.reset();
-        self.channel_usage_gauge.reset();
+    fn set_custom_key_gauges(&self, keys: &[CustomKey]) {
+        for key in keys.iter() {
+            let Some(id) = key.id else {
+                continue;
+            };
+            let params = key.params.to_owned();
+            let id = id.to_string();
+            let description = params
+                .as_ref()
+                .and_then(|p| p.description.to_owned())
+                .unwrap_or_default();
+            let access_type = params
+                .as_ref()
+                .and_then(|p| p.access_type.to_owned())
+                .unwrap_or("unknown".to_string());
+            let labels = [id.as_str(), &description, &access_type];
+
+            self.custom_key_remaining_gauge
+                .with_label_values(&labels)
+                .set(key.remaining.unwrap_or_default().max(0));
+            self.custom_key_max_use_gauge
+                .with_label_values(&labels)
+                .set(params.and_then(|p| p.max_use_count).unwrap_or_default());
+            self.custom_key_users_gauge
+                .with_label_values(&labels)
+                .set(key.users.as_ref().map_or(0, |u| u.len() as i64));
+        }
+    }
+
+    /// The status gives the channel actually in use, the configuration
+    /// holds 0 when the channel is automatically selected
+    fn set_access_point_gauges(&self, ap: &AccessPoint) {
+        let ap_id = ap.id.map_or(i8::MIN, |i| i as i8).to_string();
+        let ap_name = ap.name.as_deref().unwrap_or("unknown");
+        let band = ap
+            .config
+            .as_ref()
+            .and_then(|c| c.band.as_deref())
+            .unwrap_or("unknown");
+        let labels = [ap_id.as_str(), ap_name, band];
+
+        let Some(status) = ap.status.as_ref() else {
+            return;
+        };
+        let state = status.state.as_deref().unwrap_or("unknown");
+        let dfs_enabled = ap
+            .config
+            .as_ref()
+            .and_then(|c| c.dfs_enabled)
+            .unwrap_or(false)
+            && !status.dfs_disabled.unwrap_or(false);
+
+        self.ap_up_gauge
+            .with_label_values(&[&ap_id, ap_name, band, state])
+            .set((state == AP_ACTIVE_STATE).into());
+        self.ap_primary_channel_gauge
+            .with_label_values(&labels)
+            .set(status.primary_channel.unwrap_or_default().into());
+        self.ap_secondary_channel_gauge
+            .with_label_values(&labels)
+            .set(status.secondary_channel.unwrap_or_default().into());
+        self.ap_channel_width_gauge.with_label_values(&labels).set(
+            status
+                .channel_width
+                .as_deref()
+                .and_then(get_channel_width_mhz)
+                .unwrap_or_default(),
+        );
+        self.ap_dfs_enabled_gauge
+            .with_label_values(&labels)
+            .set(dfs_enabled.into());
+        self.ap_dfs_cac_remaining_gauge
+            .with_label_values(&labels)
+            .set(status.dfs_cac_remaining_time.unwrap_or_default());
+    }
+
+    fn set_bss_gauges(&self, bss: &[Bss]) {
+        for b in bss.iter() {
+            let Some(bssid) = b.id.as_deref() else {
+                continue;
+            };
+            let config = b.config.to_owned();
+            let status = b.status.to_owned();
+            let ap_id = b.phy_id.map_or(i8::MIN, |i| i as i8).to_string();
+            let ssid = config
+                .as_ref()
+                .and_then(|c| c.ssid.to_owned())
+                .unwrap_or_default();
+            let encryption = config
+                .as_ref()
+                .and_then(|c| c.encryption.to_owned())
+                .unwrap_or_default();
+            let guest = config.as_ref().and_then(|c| c.guest).unwrap_or(false);
+            let state = status
+                .as_ref()
+                .and_then(|s| s.state.to_owned())
+                .unwrap_or("unknown".to_string());
+            let up = config.as_ref().and_then(|c| c.enabled).unwrap_or(false)
+                && state == AP_ACTIVE_STATE;
+            let stations = status.as_ref().and_then(|s| s.sta_count);
+            let authorized_stations = status.as_ref().and_then(|s| s.authorized_sta_count);
+
+            self.bss_up_gauge
+                .with_label_values(&[
+                    bssid,
+                    &ap_id,
+                    &ssid,
+                    &encryption,
+                    &guest.to_string(),
+                    &state,
+                ])
+                .set(up.into());
+            self.bss_stations_gauge
+                .with_label_values(&[bssid, &ap_id, &ssid])
+                .set(stations.unwrap_or_default());
+            self.bss_authorized_stations_gauge
+                .with_label_values(&[bssid, &ap_id, &ssid])
+                .set(authorized_stations.unwrap_or_default());
+        }
     }
 
     pub fn set_neighbors_access_points(
@@ -943,7 +1453,6 @@ impl<'a> WifiMetricMap<'a> {
     }
 
     pub async fn set_all(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        self.reset_all();
         let aps = self.get_access_points().await?;
 
         let aps = match aps.len() {
@@ -977,7 +1486,19 @@ impl<'a> WifiMetricMap<'a> {
             }
         };
 
+        // guest flags are kept from the previous refresh when a call fails
+        if let Ok(bss) = self.get_bss().await {
+            self.set_bss_gauges(&bss);
+            self.guest_bssids = get_guest_bssids(&bss);
+        }
+
+        if let Ok(keys) = self.get_custom_keys().await {
+            self.set_custom_key_gauges(&keys);
+            self.guest_macs = get_guest_macs(&keys);
+        }
+
         for ap in aps.iter() {
+            self.set_access_point_gauges(ap);
             self.set_channel_survey_history_gauges(&ap).await?;
 
             if let Ok(channel_usage) = self.get_channel_usage(&ap).await {
@@ -989,6 +1510,11 @@ impl<'a> WifiMetricMap<'a> {
             }
 
             let stations = self.get_stations(&ap).await?;
+
+            if let Some(sources) = self.device_sources.as_ref() {
+                sources.lock().unwrap().add_stations(&stations, ap);
+            }
+
             self.set_stations_gauges(&stations, &ap).await?;
         }
         Ok(())
@@ -1001,7 +1527,12 @@ impl<'a> MetricMap<'a> for WifiMetricMap<'a> {
         Ok(())
     }
     async fn set(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        self.set_all().await
+        let res = self.set_all().await;
+
+        // stations missed because of a failed call are kept until their grace expires
+        self.sweep_all();
+
+        res
     }
 }
 
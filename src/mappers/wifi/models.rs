@@ -36,6 +36,18 @@ pub struct Station {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AccessPointCapabilities {
     pub band: Option<String>,
+    pub dfs_enabled: Option<bool>,
+}
+
+/// Channel actually in use, the configuration gives 0 for automatic channels
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AccessPointStatus {
+    pub state: Option<String>,
+    pub primary_channel: Option<u8>,
+    pub secondary_channel: Option<u8>,
+    pub channel_width: Option<String>,
+    pub dfs_cac_remaining_time: Option<i64>,
+    pub dfs_disabled: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -43,6 +55,56 @@ pub struct AccessPoint {
     pub name: Option<String>,
     pub id: Option<u8>,
     pub config: Option<AccessPointCapabilities>,
+    pub status: Option<AccessPointStatus>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Bss {
+    pub id: Option<String>,
+    pub phy_id: Option<u8>,
+    pub status: Option<BssStatus>,
+    pub config: Option<BssConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BssStatus {
+    pub state: Option<String>,
+    pub sta_count: Option<i64>,
+    pub authorized_sta_count: Option<i64>,
+    pub is_main_bss: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BssConfig {
+    pub enabled: Option<bool>,
+    pub ssid: Option<String>,
+    pub encryption: Option<String>,
+    pub hide_ssid: Option<bool>,
+    /// Set on the guest network BSS
+    pub guest: Option<bool>,
+}
+
+/// Guest network access key, the key secret is deliberately not deserialized
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CustomKey {
+    pub id: Option<i64>,
+    pub remaining: Option<i64>,
+    pub params: Option<CustomKeyParams>,
+    pub users: Option<Vec<CustomKeyUser>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CustomKeyParams {
+    pub description: Option<String>,
+    pub access_type: Option<String>,
+    pub max_use_count: Option<i64>,
+    pub duration: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CustomKeyUser {
+    pub mac: Option<String>,
+    pub hostname: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -2,7 +2,7 @@
 mod tests_deserialize {
     use serde_json::from_str;
 
-    use crate::{core::common::transport::FreeboxResponse, mappers::{api_specs_provider::get_specs_data, wifi::{models::{ChannelSurveyHistory, ChannelUsage, NeighborsAccessPoint, Station, WifiConfig}, utils::calculate_avg_channel_survey_history}}};
+    use crate::{core::common::transport::FreeboxResponse, mappers::{api_specs_provider::get_specs_data, wifi::{models::{AccessPoint, Bss, ChannelSurveyHistory, ChannelUsage, CustomKey, NeighborsAccessPoint, Station, WifiConfig}, utils::{calculate_avg_channel_survey_history, get_channel_width_mhz, get_guest_bssids, get_guest_macs}}}};
 
     #[tokio::test]
     async fn deserialize_api_v2_wifi_config() {
@@ -82,4 +82,74 @@ mod tests_deserialize {
 
         assert!(data.is_ok());
     }
+
+    #[tokio::test]
+    async fn deserialize_api_v2_wifi_ap() {
+        let json_data = get_specs_data("wifi", "api_v2_wifi_ap")
+            .await
+            .unwrap();
+
+        let aps = from_str::<FreeboxResponse<Vec<AccessPoint>>>(&json_data)
+            .unwrap()
+            .result
+            .unwrap();
+        let status = aps[0].status.as_ref().unwrap();
+
+        assert_eq!(Some(6), status.primary_channel);
+        assert_eq!(Some("active"), status.state.as_deref());
+        assert_eq!(Some(80), aps[1].status.as_ref().unwrap().channel_width.as_deref().and_then(get_channel_width_mhz));
+        assert_eq!(Some(160), get_channel_width_mhz("160+"));
+    }
+
+    #[tokio::test]
+    async fn deserialize_api_v4_wifi_bss() {
+        let json_data = get_specs_data("wifi", "api_v4_wifi_bss-get")
+            .await
+            .unwrap();
+
+        let bss = from_str::<FreeboxResponse<Vec<Bss>>>(&json_data)
+            .unwrap()
+            .result
+            .unwrap();
+        let guest = bss.iter().find(|b| b.config.as_ref().unwrap().guest == Some(true)).unwrap();
+
+        assert_eq!(3, bss.len());
+        assert_eq!(Some(7), bss[1].status.as_ref().unwrap().sta_count);
+        assert_eq!(Some("Freebox-Invites"), guest.config.as_ref().unwrap().ssid.as_deref());
+        assert_eq!(Some(false), guest.status.as_ref().unwrap().is_main_bss);
+    }
+
+    #[tokio::test]
+    async fn deserialize_api_v4_wifi_custom_key_without_secret() {
+        let json_data = get_specs_data("wifi", "api_v4_wifi_custom_key-get")
+            .await
+            .unwrap();
+
+        let keys = from_str::<FreeboxResponse<Vec<CustomKey>>>(&json_data)
+            .unwrap()
+            .result
+            .unwrap();
+        let params = keys[0].params.as_ref().unwrap();
+
+        assert_eq!(Some("net_only"), params.access_type.as_deref());
+        assert_eq!(Some(3), params.max_use_count);
+        assert_eq!(Some(0), keys[1].remaining);
+        assert!(!serde_json::to_string(&keys).unwrap().contains("ne-pas-exporter"));
+    }
+
+    #[tokio::test]
+    async fn identify_guest_stations() {
+        let bss_data = get_specs_data("wifi", "api_v4_wifi_bss-get").await.unwrap();
+        let keys_data = get_specs_data("wifi", "api_v4_wifi_custom_key-get").await.unwrap();
+
+        let bss = from_str::<FreeboxResponse<Vec<Bss>>>(&bss_data).unwrap().result.unwrap();
+        let keys = from_str::<FreeboxResponse<Vec<CustomKey>>>(&keys_data).unwrap().result.unwrap();
+        let bssids = get_guest_bssids(&bss);
+        let macs = get_guest_macs(&keys);
+
+        assert_eq!(1, bssids.len());
+        assert!(bssids.contains("F6:CA:E5:1D:46:B1"));
+        assert_eq!(2, macs.len());
+        assert!(macs.contains("A4:83:E7:12:34:56"));
+    }
 }
@@ -1,4 +1,6 @@
-use super::models::ChannelSurveyHistory;
+use std::collections::HashSet;
+
+use super::models::{Bss, ChannelSurveyHistory, CustomKey};
 
 pub fn calculate_avg_channel_survey_history(
     histories: &[ChannelSurveyHistory],
@@ -60,4 +62,29 @@ pub fn get_recent_channel_entries(
             }
         }
     }
-}
\ No newline at end of file
+}
+
+/// Channel widths are given in MHz as strings, such as "80" or "80+80"
+pub fn get_channel_width_mhz(channel_width: &str) -> Option<i64> {
+    channel_width
+        .split(|c: char| !c.is_ascii_digit())
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// BSSIDs of the guest network, upper cased
+pub fn get_guest_bssids(bss: &[Bss]) -> HashSet<String> {
+    bss.iter()
+        .filter(|b| b.config.as_ref().and_then(|c| c.guest).unwrap_or(false))
+        .filter_map(|b| b.id.as_ref().map(|id| id.to_uppercase()))
+        .collect()
+}
+
+/// MAC addresses of the devices connected with a custom key, upper cased
+pub fn get_guest_macs(keys: &[CustomKey]) -> HashSet<String> {
+    keys.iter()
+        .flat_map(|k| k.users.iter().flatten())
+        .filter_map(|u| u.mac.as_ref().map(|mac| mac.to_uppercase()))
+        .collect()
+}